@@ -1,23 +1,102 @@
-use crate::runner::{run_threaded, NetworkHandles, NetworkResponse, NetworkSignal};
-use egui::{Color32, DragValue, ProgressBar, Slider};
+use crate::runner::{run_threaded, Job, NetworkHandles, NetworkResponse, NetworkSignal};
+use egui::plot::{Line, Plot, PlotPoints};
+use egui::{vec2, Color32, DragValue, ProgressBar, Rect, Sense, Shape, Slider, Stroke};
 use egui_file::FileDialog;
+use porcino_core::cascade::{CascadeCorrelation, CascadeTraining};
+use porcino_core::data::train_validation_split;
 use porcino_core::enums::InitializationMethods;
-use porcino_core::network::Activations::{Linear, Sigmoid};
-use porcino_core::network::{LayerSettings, Network};
-use porcino_data::parse::{get_sampled_data, parse_data_file, ClassType, FileView, TaggedData};
+use porcino_core::errors::Loss;
+use porcino_core::metrics::{
+    ClassificationReport, EvaluationReport, Evaluator, RegressionReport, Task,
+};
+use porcino_core::network::{
+    Activations, BatchNorm, Dropout, Embedding, FFLayer, Head, Network, Regularization, Sigmoid,
+};
+use porcino_core::parzen::{Grnn, Pnn};
+use porcino_core::som::{Som, SomTraining, Topology};
+use porcino_core::training::{
+    Constant, CosineAnnealing, EarlyStopping, ExponentialDecay, ExtremeLearningMachine,
+    GradientDescent, Lbfgs, LevenbergMarquardt, LinearWarmup, LrSchedule, Monitor, Neuroevolution,
+    ReduceOnPlateau, Rprop, RpropVariant, Scg, StepDecay, StopConditions, StopReason, Trainer,
+};
+use porcino_core::traits::Layer;
+use porcino_data::parse::{
+    get_sampled_data, parse_data_file, ClassType, FileView, Metadata, TaggedData, TrainingSample,
+};
 use porcino_data::parse::{ColumnType, DataSettings, ParameterType};
 use serde::{Deserialize, Serialize};
 use std::default::Default;
 use std::path::PathBuf;
 use std::sync::mpsc::channel;
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+// Keeps the validation split identical between "Conf dataset" and "Toggle evaluation"
+const SPLIT_SEED: u64 = 0;
 
 #[derive(Debug, Default)]
 pub struct NetworkInfo {
     pub epochs: usize,
     pub epochs_to_run: usize,
     pub last_eval_result: f64,
+    pub evaluation: Option<EvaluationReport>,
+    pub stop_reason: Option<StopReason>,
     pub running: bool,
+    pub history: TrainingHistory,
+}
+
+const MAX_HISTORY_POINTS: usize = 5000;
+
+/// Per-epoch values stored as plot points, x being the epoch
+#[derive(Debug, Default, Clone)]
+pub struct TrainingHistory {
+    pub training_loss: Vec<[f64; 2]>,
+    pub validation_loss: Vec<[f64; 2]>,
+    pub learning_rate: Vec<[f64; 2]>,
+}
+impl TrainingHistory {
+    pub fn push(
+        &mut self,
+        epoch: usize,
+        training_loss: f64,
+        validation_loss: Option<f64>,
+        eta: f64,
+    ) {
+        let epoch = epoch as f64;
+        self.training_loss.push([epoch, training_loss]);
+        if let Some(loss) = validation_loss {
+            self.validation_loss.push([epoch, loss]);
+        }
+        self.learning_rate.push([epoch, eta]);
+    }
+
+    /// Moves the points of `other` to the end of this history. A series longer than
+    /// `MAX_HISTORY_POINTS` keeps every other point, so long runs stay cheap to plot.
+    pub fn append(&mut self, other: &mut Self) {
+        for (series, new) in [
+            (&mut self.training_loss, &mut other.training_loss),
+            (&mut self.validation_loss, &mut other.validation_loss),
+            (&mut self.learning_rate, &mut other.learning_rate),
+        ] {
+            series.append(new);
+            if series.len() > MAX_HISTORY_POINTS {
+                thin(series);
+            }
+        }
+    }
+}
+
+// Drops every second point, keeping the newest one
+fn thin(series: &mut Vec<[f64; 2]>) {
+    let last = series[series.len() - 1];
+    let mut keep = false;
+    series.retain(|_| {
+        keep = !keep;
+        keep
+    });
+    if series.last() != Some(&last) {
+        series.push(last);
+    }
 }
 pub struct PorcinoApp {
     current_panel: Panels,
@@ -25,6 +104,7 @@ pub struct PorcinoApp {
     opened_file_dialog: Option<FileDialog>,
     save_data_dialog: Option<FileDialog>,
     load_data_dialog: Option<FileDialog>,
+    save_network_dialog: Option<FileDialog>,
     has_headers: bool,
     separator: String,
     preview_lines: usize,
@@ -38,7 +118,16 @@ pub struct PorcinoApp {
     read_progress: bool,
     progress: f32,
     total_sse: f64,
+    evaluation: Option<EvaluationReport>,
+    stop_reason: Option<StopReason>,
+    history: TrainingHistory,
     report_interval: usize,
+    stop_conf: StopPreConfig,
+    som: Option<Som>,
+    som_conf: SomPreConfig,
+    benchmark: Option<Result<Benchmark, String>>,
+    benchmark_job: Option<Job<Result<Benchmark, String>>>,
+    cascade_conf: CascadePreConfig,
 }
 
 #[derive(Debug)]
@@ -52,10 +141,70 @@ enum Panels {
     Data,
     Network,
     Visualize,
+    Map,
+}
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Schedules {
+    Constant,
+    StepDecay,
+    Exponential,
+    Cosine,
+    Plateau,
+}
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum Trainers {
+    GradientDescent,
+    LevenbergMarquardt,
+    Rprop,
+    Lbfgs,
+    Scg,
+    Neuroevolution,
+    ExtremeLearningMachine,
+}
+enum LayerConf {
+    Dense {
+        neurons: usize,
+        regularization: Regularization,
+    },
+    Dropout {
+        rate: f64,
+    },
+    BatchNorm,
+}
+struct HeadConf {
+    activation: Activations,
+    loss: Loss,
+    weight: f64,
+}
+impl Default for HeadConf {
+    fn default() -> Self {
+        Self {
+            activation: Activations::Linear,
+            loss: Loss::Sse,
+            weight: 1.0,
+        }
+    }
 }
 struct NetPreConfig {
-    layers: Vec<usize>,
+    layers: Vec<LayerConf>,
+    output_regularization: Regularization,
+    heads: Vec<HeadConf>,
+    // Head every class column is predicted by
+    head_of: Vec<usize>,
+    embedding_dim: usize,
+    trainer: Trainers,
+    levenberg_marquardt: LevenbergMarquardt,
+    rprop: Rprop,
+    neuroevolution: Neuroevolution,
+    elm: ExtremeLearningMachine,
     eta: f64,
+    schedule: Schedules,
+    decay_factor: f64,
+    decay_steps: usize,
+    min_eta: f64,
+    cycle_mult: usize,
+    plateau_patience: usize,
+    warmup_steps: usize,
     lr_inc: f64,
     lr_dec: f64,
     mc: f64,
@@ -66,7 +215,23 @@ impl Default for NetPreConfig {
     fn default() -> Self {
         Self {
             layers: Vec::default(),
+            output_regularization: Regularization::default(),
+            heads: vec![HeadConf::default()],
+            head_of: Vec::new(),
+            embedding_dim: 4,
+            trainer: Trainers::GradientDescent,
+            levenberg_marquardt: LevenbergMarquardt::default(),
+            rprop: Rprop::default(),
+            neuroevolution: Neuroevolution::default(),
+            elm: ExtremeLearningMachine::default(),
             eta: 0.05,
+            schedule: Schedules::Constant,
+            decay_factor: 0.5,
+            decay_steps: 1000,
+            min_eta: 0.0,
+            cycle_mult: 1,
+            plateau_patience: 100,
+            warmup_steps: 0,
             lr_inc: 1.0,
             lr_dec: 1.0,
             mc: 0.0,
@@ -76,6 +241,251 @@ impl Default for NetPreConfig {
     }
 }
 
+impl NetPreConfig {
+    fn network(&self, meta: &Metadata) -> Network {
+        let mut layers: Vec<Box<dyn Layer>> = Vec::new();
+        let mut width = meta.params.len();
+        // Label params go through embedding tables instead of being fed as an index
+        if meta.has_categories() && self.embedding_dim > 0 {
+            let embedding = Embedding::new(
+                &meta.categories(),
+                self.embedding_dim,
+                InitializationMethods::Random,
+            );
+            width = embedding.outputs();
+            layers.push(Box::new(embedding));
+        }
+        for layer in self.layers.iter() {
+            match layer {
+                LayerConf::Dense {
+                    neurons,
+                    regularization,
+                } => {
+                    let mut dense =
+                        FFLayer::new(width, *neurons, InitializationMethods::Random, &Sigmoid);
+                    dense.regularization = *regularization;
+                    layers.push(Box::new(dense));
+                    width = *neurons;
+                }
+                LayerConf::Dropout { rate } => layers.push(Box::new(Dropout::new(*rate))),
+                LayerConf::BatchNorm => layers.push(Box::new(BatchNorm::new(width))),
+            }
+        }
+        let trunk_len = layers.len();
+        let mut network = Network::with_heads(
+            layers,
+            width,
+            self.heads(meta),
+            InitializationMethods::Random,
+        );
+        for layer in network.layers[trunk_len..].iter_mut() {
+            layer.set_regularization(self.output_regularization);
+        }
+        network
+    }
+
+    // Heads without any class column are left out
+    fn heads(&self, meta: &Metadata) -> Vec<Head> {
+        self.heads
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, conf)| {
+                let columns = (0..meta.classes.len())
+                    .filter(|column| self.head_of.get(*column).copied().unwrap_or(0) == idx)
+                    .collect::<Vec<_>>();
+                (!columns.is_empty()).then(|| Head {
+                    name: format!("Head {}", idx + 1),
+                    columns,
+                    activation: conf.activation,
+                    loss: conf.loss,
+                    weight: conf.weight,
+                })
+            })
+            .collect()
+    }
+
+    fn trainer(&self) -> Box<dyn Trainer> {
+        match self.trainer {
+            Trainers::GradientDescent => Box::new(GradientDescent),
+            Trainers::LevenbergMarquardt => Box::new(self.levenberg_marquardt),
+            Trainers::Rprop => Box::new(self.rprop.clone()),
+            Trainers::Lbfgs => Box::new(Lbfgs::default()),
+            Trainers::Scg => Box::new(Scg::default()),
+            Trainers::Neuroevolution => Box::new(self.neuroevolution.clone()),
+            Trainers::ExtremeLearningMachine => Box::new(self.elm),
+        }
+    }
+
+    fn schedule(&self) -> Box<dyn LrSchedule> {
+        let schedule: Box<dyn LrSchedule> = match self.schedule {
+            Schedules::Constant => Box::new(Constant { eta: self.eta }),
+            Schedules::StepDecay => Box::new(StepDecay {
+                initial: self.eta,
+                factor: self.decay_factor,
+                step_size: self.decay_steps,
+            }),
+            Schedules::Exponential => Box::new(ExponentialDecay {
+                initial: self.eta,
+                gamma: self.decay_factor,
+            }),
+            Schedules::Cosine => Box::new(CosineAnnealing {
+                max: self.eta,
+                min: self.min_eta,
+                period: self.decay_steps,
+                period_mult: self.cycle_mult,
+            }),
+            Schedules::Plateau => Box::new(ReduceOnPlateau::new(
+                self.eta,
+                self.decay_factor,
+                self.plateau_patience,
+                0.0,
+                self.min_eta,
+            )),
+        };
+        if self.warmup_steps > 0 {
+            Box::new(LinearWarmup {
+                steps: self.warmup_steps,
+                schedule,
+            })
+        } else {
+            schedule
+        }
+    }
+}
+
+struct StopPreConfig {
+    validation_split: f64,
+    early_stopping: bool,
+    monitor: Monitor,
+    patience: usize,
+    min_delta: f64,
+    restore_best: bool,
+    use_target_error: bool,
+    target_error: f64,
+    use_time_limit: bool,
+    time_limit_minutes: f64,
+    validation_interval: usize,
+}
+impl Default for StopPreConfig {
+    fn default() -> Self {
+        Self {
+            validation_split: 20.0,
+            early_stopping: false,
+            monitor: Monitor::ValidationLoss,
+            patience: 50,
+            min_delta: 0.0,
+            restore_best: true,
+            use_target_error: false,
+            target_error: 0.01,
+            use_time_limit: false,
+            time_limit_minutes: 60.0,
+            validation_interval: 1,
+        }
+    }
+}
+impl StopPreConfig {
+    fn conditions(&self) -> StopConditions {
+        StopConditions {
+            early_stopping: self.early_stopping.then(|| {
+                EarlyStopping::new(
+                    self.monitor,
+                    self.patience,
+                    self.min_delta,
+                    self.restore_best,
+                )
+            }),
+            target_error: self.use_target_error.then_some(self.target_error),
+            max_duration: self
+                .use_time_limit
+                .then(|| Duration::from_secs_f64(self.time_limit_minutes * 60.0)),
+            validation_interval: self.validation_interval,
+        }
+    }
+
+    // Training and validation samples, the whole dataset serves as both when there is no split
+    fn samples(&self, data: &TaggedData) -> (Vec<TrainingSample>, Vec<TrainingSample>) {
+        let samples = get_sampled_data(data);
+        if self.validation_split > 0.0 {
+            train_validation_split(samples, self.validation_split / 100.0, SPLIT_SEED)
+        } else {
+            (samples.clone(), samples)
+        }
+    }
+}
+
+// Kernel model fitted on the training split, scored on the validation one
+struct Benchmark {
+    model: &'static str,
+    sigma: f64,
+    report: EvaluationReport,
+}
+
+impl Benchmark {
+    // PNN for classification and GRNN for regression datasets. Kernel models memorize the
+    // training split, so both splits need samples.
+    fn fit(
+        evaluator: &Evaluator,
+        training: &[TrainingSample],
+        validation: &[TrainingSample],
+    ) -> Result<Self, String> {
+        if training.is_empty() {
+            return Err("The training split has no samples".to_string());
+        }
+        if validation.is_empty() {
+            return Err("The validation split has no samples to score the model on".to_string());
+        }
+        Ok(match evaluator.task {
+            Task::Classification => {
+                let mut pnn = Pnn::fit(training);
+                Self {
+                    model: "PNN",
+                    sigma: pnn.sigma,
+                    report: evaluator.report(&mut pnn, validation),
+                }
+            }
+            Task::Regression => {
+                let mut grnn = Grnn::fit(training);
+                Self {
+                    model: "GRNN",
+                    sigma: grnn.sigma,
+                    report: evaluator.report(&mut grnn, validation),
+                }
+            }
+        })
+    }
+}
+
+struct SomPreConfig {
+    rows: usize,
+    cols: usize,
+    topology: Topology,
+    training: SomTraining,
+    quantization_error: Option<f64>,
+    // Map being trained and its quantization error
+    job: Option<Job<(Som, f64)>>,
+}
+
+impl Default for SomPreConfig {
+    fn default() -> Self {
+        Self {
+            rows: 10,
+            cols: 10,
+            topology: Topology::Hexagonal,
+            training: SomTraining::default(),
+            quantization_error: None,
+            job: None,
+        }
+    }
+}
+
+#[derive(Default)]
+struct CascadePreConfig {
+    training: CascadeTraining,
+    // Hidden units and training error of the last grown network
+    grown: Option<Result<(usize, f64), String>>,
+    job: Option<Job<CascadeCorrelation>>,
+}
+
 impl Default for PorcinoApp {
     fn default() -> Self {
         Self {
@@ -83,6 +493,7 @@ impl Default for PorcinoApp {
             opened_file_dialog: None,
             save_data_dialog: None,
             load_data_dialog: None,
+            save_network_dialog: None,
             opened_file: None,
             has_headers: false,
             separator: String::from(";"),
@@ -97,7 +508,16 @@ impl Default for PorcinoApp {
             read_progress: false,
             progress: 0.0,
             total_sse: 0.0,
+            evaluation: None,
+            stop_reason: None,
+            history: TrainingHistory::default(),
             report_interval: 0,
+            stop_conf: StopPreConfig::default(),
+            som: None,
+            som_conf: SomPreConfig::default(),
+            benchmark: None,
+            benchmark_job: None,
+            cascade_conf: CascadePreConfig::default(),
         }
     }
 }
@@ -136,9 +556,19 @@ impl eframe::App for PorcinoApp {
             read_progress,
             progress,
             total_sse,
+            evaluation,
+            stop_reason,
+            history,
             report_interval,
+            stop_conf,
             save_data_dialog,
             load_data_dialog,
+            save_network_dialog,
+            som,
+            som_conf,
+            benchmark,
+            benchmark_job,
+            cascade_conf,
         } = self;
 
         // Examples of how to create different panels and windows.
@@ -183,6 +613,9 @@ impl eframe::App for PorcinoApp {
             if ui.button("Network visualizer").clicked() {
                 *current_panel = Panels::Visualize;
             }
+            if ui.button("Self-organizing map").clicked() {
+                *current_panel = Panels::Map;
+            }
         });
 
         egui::CentralPanel::default().show(ctx, |ui| {
@@ -212,6 +645,18 @@ impl eframe::App for PorcinoApp {
                 }
             }
 
+            if let Some(dialog) = save_network_dialog {
+                if dialog.show(ctx).selected() {
+                    if let (Some(file), Some(handles)) =
+                        (dialog.path(), active_networks.get(*selected_network))
+                    {
+                        let _ = handles
+                            .tx_handle
+                            .send(NetworkSignal::Save(file.to_path_buf()));
+                    }
+                }
+            }
+
             match current_panel {
                 Panels::Landing => {
                     ui.heading("eframe template");
@@ -339,6 +784,13 @@ impl eframe::App for PorcinoApp {
                                                                     ),
                                                                     "Number",
                                                                 );
+                                                                ui.selectable_value(
+                                                                    &mut data_settings.columns[i],
+                                                                    ColumnType::Class(
+                                                                        ClassType::NormalizedValue
+                                                                    ),
+                                                                    "Number (normalized)",
+                                                                );
                                                             });
                                                         });
                                                         data.fields.iter().for_each(|row| {
@@ -371,43 +823,151 @@ impl eframe::App for PorcinoApp {
                             ui.label("Network parameters");
                             ui.add(egui::DragValue::new(&mut net_conf.eta));
                         });
+                        show_trainer(ui, net_conf);
+                        if net_conf.trainer().uses_learning_rate(){
+                            show_schedule(ui, net_conf);
+                        }
                         ui.separator();
 
                         ui.label(format!("Input neurons: {}", dataset.meta.params.len()));
-                        if ui.button("Add layer").clicked(){
-                            net_conf.layers.push(0);
+                        if dataset.meta.has_categories(){
+                            ui.horizontal(|ui| {
+                                ui.add(Slider::new(&mut net_conf.embedding_dim, 0usize..=32usize).text("Embedding size"));
+                                ui.label("(0 feeds labels as an index)");
+                            });
                         }
+                        ui.horizontal(|ui| {
+                            if ui.button("Add layer").clicked(){
+                                net_conf.layers.push(LayerConf::Dense{neurons: 1, regularization: Regularization::default()});
+                            }
+                            if ui.button("Add dropout").clicked(){
+                                net_conf.layers.push(LayerConf::Dropout{rate: 0.5});
+                            }
+                            if ui.button("Add batch norm").clicked(){
+                                net_conf.layers.push(LayerConf::BatchNorm);
+                            }
+                        });
 
                         let mut rm_layer = None;
+                        let mut add_dropout = None;
+                        let mut add_batch_norm = None;
                         for (idx, layer) in net_conf.layers.iter_mut().enumerate(){
-                            ui.horizontal(|ui| {
-                                ui.add(Slider::new(layer, 1usize..=100usize).text("Neurons"));
-                                if ui.button("Remove").clicked(){
-                                    rm_layer = Some(idx);
+                            match layer {
+                                LayerConf::Dense{neurons, regularization} => {
+                                    ui.horizontal(|ui| {
+                                        ui.add(Slider::new(neurons, 1usize..=100usize).text("Neurons"));
+                                        if ui.button("Dropout after").clicked(){
+                                            add_dropout = Some(idx + 1);
+                                        }
+                                        if ui.button("Batch norm after").clicked(){
+                                            add_batch_norm = Some(idx + 1);
+                                        }
+                                        if ui.button("Remove").clicked(){
+                                            rm_layer = Some(idx);
+                                        }
+                                    });
+                                    show_regularization(ui, idx, regularization);
                                 }
-                            });
+                                LayerConf::Dropout{rate} => {
+                                    ui.horizontal(|ui| {
+                                        ui.add(Slider::new(rate, 0.0..=0.95).text("Dropout rate"));
+                                        if ui.button("Remove").clicked(){
+                                            rm_layer = Some(idx);
+                                        }
+                                    });
+                                }
+                                LayerConf::BatchNorm => {
+                                    ui.horizontal(|ui| {
+                                        ui.label("Batch normalization");
+                                        if ui.button("Remove").clicked(){
+                                            rm_layer = Some(idx);
+                                        }
+                                    });
+                                }
+                            }
                         }
                         if let Some(idx) = rm_layer{
                             net_conf.layers.remove(idx);
                         }
+                        if let Some(idx) = add_dropout{
+                            net_conf.layers.insert(idx, LayerConf::Dropout{rate: 0.5});
+                        }
+                        if let Some(idx) = add_batch_norm{
+                            net_conf.layers.insert(idx, LayerConf::BatchNorm);
+                        }
                         ui.label(format!("Output neurons: {}", dataset.meta.classes.len()));
+                        show_heads(ui, net_conf, &dataset.meta);
+                        show_regularization(ui, net_conf.layers.len(), &mut net_conf.output_regularization);
 
-                        if ui.button("Generate Network structure").clicked(){
-                            let mut total_layers = net_conf.layers.iter().map(|v| LayerSettings{neurons: *v, activation: Sigmoid}).collect::<Vec<_>>();
-                            total_layers.insert(0, LayerSettings{neurons: dataset.meta.params.len(), activation: Linear});
-                            total_layers.push(LayerSettings{neurons: dataset.meta.classes.len(), activation: Linear});
-                            let local_network = Network::new(total_layers, InitializationMethods::Random);
-                            let signals = channel::<NetworkSignal>();
-                            let responses = channel::<NetworkResponse>();
-
-                            let handle = run_threaded(local_network, responses.0, signals.1, network_info.clone(), net_conf.eta);
-                            active_networks.push(
-                                NetworkHandles{
-                                    thread_handler: handle,
-                                    tx_handle: signals.0,
-                                    rx_handle: responses.1
-                                });
+                        let has_outputs = !dataset.meta.classes.is_empty();
+                        if ui.add_enabled(has_outputs, egui::Button::new("Generate Network structure")).on_disabled_hover_text("The dataset has no class columns to predict").clicked(){
+                            let local_network = net_conf.network(&dataset.meta);
+                            active_networks.push(launch(local_network, &dataset.meta, net_conf, network_info));
                         }
+                        ui.collapsing("Grow with Cascade-Correlation", |ui| {
+                            ui.label("Adds hidden units one at a time until the target error is reached, instead of using the layers above");
+                            show_cascade_settings(ui, &mut cascade_conf.training);
+                            if let Some(job) = cascade_conf.job.take(){
+                                match job.poll(){
+                                    Ok(Some(cascade)) => {
+                                        cascade_conf.grown = cascade.history().last().map(|error| Ok((cascade.hidden_units(), *error)));
+                                        active_networks.push(launch(cascade.to_network(), &dataset.meta, net_conf, network_info));
+                                    }
+                                    Ok(None) => cascade_conf.grown = Some(Err("Growing the network failed".to_string())),
+                                    Err(job) => {
+                                        ui.add(ProgressBar::new(0.0).text("Growing").animate(true));
+                                        cascade_conf.job = Some(job);
+                                    }
+                                }
+                            }else if ui.button("Grow network").clicked(){
+                                let (training, _) = stop_conf.samples(dataset);
+                                // Targets are label indices or numbers, both fit by the default linear outputs
+                                let settings = cascade_conf.training;
+                                if training.is_empty(){
+                                    cascade_conf.grown = Some(Err("The training split has no samples".to_string()));
+                                }else{
+                                    cascade_conf.job = Some(Job::spawn(move |_| CascadeCorrelation::fit(&training, settings)));
+                                }
+                            }
+                            match &cascade_conf.grown{
+                                Some(Ok((units, error))) => {
+                                    ui.label(format!("Grew {units} hidden units, training error {error:.6}"));
+                                }
+                                Some(Err(error)) => {
+                                    ui.colored_label(Color32::DARK_RED, error.as_str());
+                                }
+                                None => {}
+                            }
+                        });
+                        ui.collapsing("Kernel benchmark", |ui| {
+                            ui.label("PNN for classification and GRNN for regression, σ tuned by leave-one-out on the training split");
+                            if let Some(job) = benchmark_job.take(){
+                                match job.poll(){
+                                    Ok(fitted) => *benchmark = Some(fitted.unwrap_or_else(|| Err("Fitting the kernel model failed".to_string()))),
+                                    Err(job) => {
+                                        ui.add(ProgressBar::new(0.0).text("Tuning σ").animate(true));
+                                        *benchmark_job = Some(job);
+                                    }
+                                }
+                            }else if ui.button("Fit kernel model").clicked(){
+                                let evaluator = Evaluator::from_metadata(&dataset.meta);
+                                let (training, validation) = stop_conf.samples(dataset);
+                                *benchmark_job = Some(Job::spawn(move |_| Benchmark::fit(&evaluator, &training, &validation)));
+                            }
+                            match benchmark{
+                                Some(Ok(benchmark)) => {
+                                    ui.label(format!("{} with σ = {:.6}", benchmark.model, benchmark.sigma));
+                                    match &benchmark.report{
+                                        EvaluationReport::Classification(report) => show_classification(ui, report),
+                                        EvaluationReport::Regression(report) => show_regression(ui, report),
+                                    }
+                                }
+                                Some(Err(error)) => {
+                                    ui.colored_label(Color32::DARK_RED, error.as_str());
+                                }
+                                None => {}
+                            }
+                        });
                     }else{
                     ui.colored_label(Color32::DARK_RED, "No active dataset! Cannot infer network options");
                     }
@@ -416,8 +976,13 @@ impl eframe::App for PorcinoApp {
                     if let Some(handles) = active_networks.get(*selected_network){
                         // Send signal
                         if let Some(data) = dataset{
+                            ui.add(Slider::new(&mut stop_conf.validation_split, 0.0..=50.0).text("Validation split (%)"));
                             if ui.button("Conf dataset").clicked(){
-                                let _ = handles.tx_handle.send(NetworkSignal::SetData(get_sampled_data(data)));
+                                let (training, validation) = stop_conf.samples(data);
+                                let _ = handles.tx_handle.send(NetworkSignal::SetData(training));
+                                if stop_conf.validation_split > 0.0 {
+                                    let _ = handles.tx_handle.send(NetworkSignal::EvalData(Some(validation)));
+                                }
                             }
                             if ui.button("Conf epochs").clicked(){
                                 let _ = handles.tx_handle.send(NetworkSignal::SetEpochs(3000));
@@ -429,20 +994,79 @@ impl eframe::App for PorcinoApp {
                             if ui.button("Toggle learning process").clicked(){
                                 let _ = handles.tx_handle.send(NetworkSignal::Toggle);
                             }
+                            if ui.button("Save network").clicked(){
+                                let mut dialog = FileDialog::save_file(Some(PathBuf::new()));
+                                dialog.open();
+                                *save_network_dialog = Some(dialog);
+                            }
                             if ui.button("Toggle evaluation").clicked(){
-                                let _ = handles.tx_handle.send(NetworkSignal::EvalData(Some(get_sampled_data(data))));
+                                let _ = handles.tx_handle.send(NetworkSignal::EvalData(Some(stop_conf.samples(data).1)));
                             }
+                            ui.collapsing("Stop conditions", |ui| {
+                                show_stop_conditions(ui, stop_conf);
+                                if ui.button("Set stop conditions").clicked(){
+                                    let _ = handles.tx_handle.send(NetworkSignal::SetStopConditions(Box::new(stop_conf.conditions())));
+                                }
+                            });
 
                         }
                         // Try recieve signal
 
-                        if let Ok(info) = network_info.try_read(){
+                        if let Ok(mut info) = network_info.try_write(){
                             *progress = info.epochs as f32 / info.epochs_to_run as f32;
                             *total_sse = info.last_eval_result;
+                            *evaluation = info.evaluation.clone();
+                            *stop_reason = info.stop_reason;
+                            // Only the points reported since the last frame are moved over
+                            history.append(&mut info.history);
                             *read_progress = info.running;
                         }
                         ui.add(ProgressBar::new(*progress).show_percentage().fill(if *read_progress{Color32::BLUE } else{Color32::LIGHT_RED}).desired_width(100.0).animate(*read_progress));
                         ui.add_enabled(false, DragValue::new(total_sse));
+                        if let Some(reason) = stop_reason{
+                            ui.label(format!("Stopped: {}", reason));
+                        }
+                        show_history(ui, history);
+                        match evaluation{
+                            Some(EvaluationReport::Classification(report)) => show_classification(ui, report),
+                            Some(EvaluationReport::Regression(report)) => show_regression(ui, report),
+                            None => {}
+                        }
+                    }
+                }
+                Panels::Map => {
+                    if let Some(data) = dataset {
+                        show_som_settings(ui, som_conf);
+                        if let Some(job) = som_conf.job.take() {
+                            match job.poll() {
+                                Ok(trained) => {
+                                    som_conf.quantization_error = trained.as_ref().map(|(_, error)| *error);
+                                    *som = trained.map(|(map, _)| map);
+                                }
+                                Err(job) => {
+                                    ui.add(ProgressBar::new(job.progress()).show_percentage().animate(true));
+                                    som_conf.job = Some(job);
+                                }
+                            }
+                        } else if ui.button("Train map").clicked() {
+                            let samples = get_sampled_data(data);
+                            let (rows, cols, topology, training) = (som_conf.rows, som_conf.cols, som_conf.topology, som_conf.training);
+                            som_conf.job = Some(Job::spawn(move |progress| {
+                                let inputs = samples.first().map_or(0, |sample| sample.input.nrows());
+                                let mut map = Som::new(rows, cols, inputs, topology);
+                                map.initialize(&samples, training.seed);
+                                map.train_reporting(&samples, &training, |epoch| {
+                                    progress(epoch as f32 / training.epochs as f32)
+                                });
+                                let error = map.quantization_error(&samples);
+                                (map, error)
+                            }));
+                        }
+                        if let Some(som) = som {
+                            show_som(ui, som, som_conf);
+                        }
+                    } else {
+                        ui.colored_label(Color32::DARK_RED, "No active dataset! Nothing to map");
                     }
                 }
             }
@@ -512,3 +1136,616 @@ impl eframe::App for PorcinoApp {
         });
     }
 }
+
+fn show_heads(ui: &mut egui::Ui, conf: &mut NetPreConfig, meta: &Metadata) {
+    conf.head_of.resize(meta.classes.len(), 0);
+    egui::CollapsingHeader::new("Output heads").show(ui, |ui| {
+        egui::Grid::new("head_columns").show(ui, |ui| {
+            for (column, head) in conf.head_of.iter_mut().enumerate() {
+                let class_type = meta.class_types.get(column);
+                ui.label(format!("Output {} ({:?})", column + 1, class_type));
+                egui::ComboBox::from_id_source(("head_of", column))
+                    .selected_text(format!("Head {}", *head + 1))
+                    .show_ui(ui, |ui| {
+                        for idx in 0..conf.heads.len() {
+                            ui.selectable_value(head, idx, format!("Head {}", idx + 1));
+                        }
+                    });
+                ui.end_row();
+            }
+        });
+
+        let mut rm_head = None;
+        for (idx, head) in conf.heads.iter_mut().enumerate() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Head {}", idx + 1));
+                egui::ComboBox::from_id_source(("head_activation", idx))
+                    .selected_text(format!("{:?}", head.activation))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut head.activation, Activations::Linear, "Linear");
+                        ui.selectable_value(&mut head.activation, Activations::Sigmoid, "Sigmoid");
+                        ui.selectable_value(&mut head.activation, Activations::Tanh, "Tanh");
+                    });
+                egui::ComboBox::from_id_source(("head_loss", idx))
+                    .selected_text(format!("{:?}", head.loss))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut head.loss, Loss::Sse, "SSE");
+                        ui.selectable_value(&mut head.loss, Loss::CrossEntropy, "Cross-entropy");
+                    });
+                ui.label("Weight:");
+                ui.add(
+                    DragValue::new(&mut head.weight)
+                        .speed(0.01)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+                if idx > 0 && ui.button("Remove").clicked() {
+                    rm_head = Some(idx);
+                }
+            });
+        }
+        if let Some(idx) = rm_head {
+            conf.heads.remove(idx);
+            for head in conf.head_of.iter_mut() {
+                if *head == idx {
+                    *head = 0;
+                } else if *head > idx {
+                    *head -= 1;
+                }
+            }
+        }
+        if ui.button("Add head").clicked() {
+            conf.heads.push(HeadConf::default());
+        }
+    });
+}
+
+fn show_regularization(ui: &mut egui::Ui, layer: usize, reg: &mut Regularization) {
+    egui::CollapsingHeader::new("Regularization")
+        .id_source(("regularization", layer))
+        .show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("L1:");
+                ui.add(
+                    DragValue::new(&mut reg.l1)
+                        .speed(0.0001)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+                ui.label("L2:");
+                ui.add(
+                    DragValue::new(&mut reg.l2)
+                        .speed(0.0001)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+                ui.checkbox(&mut reg.decoupled, "Decoupled weight decay");
+            });
+            ui.horizontal(|ui| {
+                let mut constrained = reg.max_norm.is_some();
+                ui.checkbox(&mut constrained, "Max-norm");
+                match (constrained, reg.max_norm.as_mut()) {
+                    (true, Some(max_norm)) => {
+                        ui.add(
+                            DragValue::new(max_norm)
+                                .speed(0.01)
+                                .clamp_range(0.0..=f64::MAX),
+                        );
+                    }
+                    (true, None) => reg.max_norm = Some(3.0),
+                    (false, _) => reg.max_norm = None,
+                }
+            });
+            ui.checkbox(&mut reg.include_biases, "Regularize biases");
+        });
+}
+
+fn show_trainer(ui: &mut egui::Ui, conf: &mut NetPreConfig) {
+    egui::ComboBox::from_label("Training algorithm")
+        .selected_text(format!("{:?}", conf.trainer))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(
+                &mut conf.trainer,
+                Trainers::GradientDescent,
+                "Gradient descent",
+            );
+            ui.selectable_value(
+                &mut conf.trainer,
+                Trainers::LevenbergMarquardt,
+                "Levenberg-Marquardt",
+            );
+            ui.selectable_value(&mut conf.trainer, Trainers::Rprop, "Rprop");
+            ui.selectable_value(&mut conf.trainer, Trainers::Lbfgs, "L-BFGS");
+            ui.selectable_value(
+                &mut conf.trainer,
+                Trainers::Scg,
+                "Scaled conjugate gradient",
+            );
+            ui.selectable_value(
+                &mut conf.trainer,
+                Trainers::Neuroevolution,
+                "Genetic algorithm",
+            );
+            ui.selectable_value(
+                &mut conf.trainer,
+                Trainers::ExtremeLearningMachine,
+                "Extreme learning machine",
+            );
+        });
+    if conf.trainer == Trainers::Rprop {
+        let rprop = &mut conf.rprop;
+        ui.horizontal(|ui| {
+            egui::ComboBox::from_label("Variant")
+                .selected_text(format!("{:?}", rprop.variant))
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut rprop.variant, RpropVariant::RpropPlus, "Rprop+");
+                    ui.selectable_value(&mut rprop.variant, RpropVariant::RpropMinus, "Rprop-");
+                    ui.selectable_value(&mut rprop.variant, RpropVariant::IRpropPlus, "iRprop+");
+                    ui.selectable_value(&mut rprop.variant, RpropVariant::IRpropMinus, "iRprop-");
+                });
+            ui.label("Initial step:");
+            ui.add(
+                DragValue::new(&mut rprop.initial_step)
+                    .speed(0.001)
+                    .clamp_range(rprop.min_step..=rprop.max_step),
+            );
+        });
+    }
+    if conf.trainer == Trainers::ExtremeLearningMachine {
+        ui.horizontal(|ui| {
+            ui.label("Ridge:");
+            ui.add(
+                DragValue::new(&mut conf.elm.ridge)
+                    .speed(1e-6)
+                    .clamp_range(0.0..=100.0),
+            );
+            ui.label("Hidden layers keep their initial weights, one epoch solves the output layer");
+        });
+        if conf.heads.iter().any(|head| head.activation != Activations::Linear) {
+            ui.colored_label(
+                Color32::DARK_RED,
+                "The solve ignores output activations, use linear heads for a good fit",
+            );
+        }
+    }
+    if conf.trainer == Trainers::Neuroevolution {
+        let ga = &mut conf.neuroevolution;
+        ui.horizontal(|ui| {
+            ui.label("Population:");
+            ui.add(DragValue::new(&mut ga.population_size).clamp_range(2..=1000));
+            ui.label("Tournament:");
+            ui.add(DragValue::new(&mut ga.tournament_size).clamp_range(1..=ga.population_size));
+            ui.label("Elites:");
+            ui.add(DragValue::new(&mut ga.elitism).clamp_range(0..=ga.population_size));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Crossover rate:");
+            ui.add(Slider::new(&mut ga.crossover_rate, 0.0..=1.0));
+            ui.label("Mutation rate:");
+            ui.add(Slider::new(&mut ga.mutation_rate, 0.0..=1.0));
+            ui.label("Mutation σ:");
+            ui.add(
+                DragValue::new(&mut ga.mutation_std)
+                    .speed(0.001)
+                    .clamp_range(0.0..=10.0),
+            );
+        });
+    }
+    if conf.trainer == Trainers::LevenbergMarquardt {
+        let lm = &mut conf.levenberg_marquardt;
+        ui.horizontal(|ui| {
+            ui.label("Initial damping:");
+            ui.add(
+                DragValue::new(&mut lm.mu)
+                    .speed(0.0001)
+                    .clamp_range(1e-12..=lm.mu_max),
+            );
+            ui.label("Increase:");
+            ui.add(
+                DragValue::new(&mut lm.mu_inc)
+                    .speed(0.1)
+                    .clamp_range(1.1..=1000.0),
+            );
+            ui.label("Decrease:");
+            ui.add(
+                DragValue::new(&mut lm.mu_dec)
+                    .speed(0.01)
+                    .clamp_range(0.0..=1.0),
+            );
+        });
+    }
+}
+
+fn show_schedule(ui: &mut egui::Ui, conf: &mut NetPreConfig) {
+    egui::ComboBox::from_label("Learning rate schedule")
+        .selected_text(format!("{:?}", conf.schedule))
+        .show_ui(ui, |ui| {
+            ui.selectable_value(&mut conf.schedule, Schedules::Constant, "Constant");
+            ui.selectable_value(&mut conf.schedule, Schedules::StepDecay, "Step decay");
+            ui.selectable_value(
+                &mut conf.schedule,
+                Schedules::Exponential,
+                "Exponential decay",
+            );
+            ui.selectable_value(&mut conf.schedule, Schedules::Cosine, "Cosine annealing");
+            ui.selectable_value(&mut conf.schedule, Schedules::Plateau, "Reduce on plateau");
+        });
+    match conf.schedule {
+        Schedules::Constant => {}
+        Schedules::StepDecay => {
+            ui.horizontal(|ui| {
+                ui.label("Factor:");
+                ui.add(
+                    DragValue::new(&mut conf.decay_factor)
+                        .speed(0.01)
+                        .clamp_range(0.0..=1.0),
+                );
+                ui.label("every (epochs):");
+                ui.add(DragValue::new(&mut conf.decay_steps).clamp_range(1..=usize::MAX));
+            });
+        }
+        Schedules::Exponential => {
+            ui.horizontal(|ui| {
+                ui.label("Gamma:");
+                ui.add(
+                    DragValue::new(&mut conf.decay_factor)
+                        .speed(0.0001)
+                        .clamp_range(0.0..=1.0),
+                );
+            });
+        }
+        Schedules::Cosine => {
+            ui.horizontal(|ui| {
+                ui.label("Minimum rate:");
+                ui.add(
+                    DragValue::new(&mut conf.min_eta)
+                        .speed(0.0001)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+            });
+            ui.horizontal(|ui| {
+                ui.label("Cycle (epochs):");
+                ui.add(DragValue::new(&mut conf.decay_steps).clamp_range(1..=usize::MAX));
+                ui.label("Cycle multiplier:");
+                ui.add(DragValue::new(&mut conf.cycle_mult).clamp_range(1..=10));
+            });
+        }
+        Schedules::Plateau => {
+            ui.horizontal(|ui| {
+                ui.label("Factor:");
+                ui.add(
+                    DragValue::new(&mut conf.decay_factor)
+                        .speed(0.01)
+                        .clamp_range(0.0..=1.0),
+                );
+                ui.label("Patience:");
+                ui.add(DragValue::new(&mut conf.plateau_patience).clamp_range(1..=usize::MAX));
+            });
+            ui.horizontal(|ui| {
+                ui.label("Minimum rate:");
+                ui.add(
+                    DragValue::new(&mut conf.min_eta)
+                        .speed(0.0001)
+                        .clamp_range(0.0..=f64::MAX),
+                );
+            });
+        }
+    }
+    ui.horizontal(|ui| {
+        ui.label("Warm-up (epochs):");
+        ui.add(DragValue::new(&mut conf.warmup_steps));
+    });
+}
+
+fn show_history(ui: &mut egui::Ui, history: &TrainingHistory) {
+    ui.columns(2, |columns| {
+        columns[0].label("Loss");
+        Plot::new("loss_plot")
+            .height(200.0)
+            .show(&mut columns[0], |plot_ui| {
+                plot_ui.line(
+                    Line::new(PlotPoints::from(history.training_loss.clone())).name("Training"),
+                );
+                plot_ui.line(
+                    Line::new(PlotPoints::from(history.validation_loss.clone())).name("Validation"),
+                );
+            });
+        columns[1].label("Learning rate");
+        Plot::new("learning_rate_plot")
+            .height(200.0)
+            .show(&mut columns[1], |plot_ui| {
+                plot_ui.line(
+                    Line::new(PlotPoints::from(history.learning_rate.clone()))
+                        .name("Learning rate"),
+                );
+            });
+    });
+}
+
+fn show_stop_conditions(ui: &mut egui::Ui, conf: &mut StopPreConfig) {
+    ui.checkbox(&mut conf.early_stopping, "Early stopping");
+    ui.add_enabled_ui(conf.early_stopping, |ui| {
+        egui::ComboBox::from_label("Monitored value")
+            .selected_text(format!("{:?}", conf.monitor))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut conf.monitor, Monitor::ValidationLoss, "ValidationLoss");
+                ui.selectable_value(&mut conf.monitor, Monitor::TrainingLoss, "TrainingLoss");
+                ui.selectable_value(
+                    &mut conf.monitor,
+                    Monitor::ValidationScore,
+                    "ValidationScore",
+                )
+                .on_hover_text("Accuracy for classification, R² for regression");
+            });
+        ui.horizontal(|ui| {
+            ui.label("Patience:");
+            ui.add(DragValue::new(&mut conf.patience).clamp_range(1..=100000));
+        });
+        ui.horizontal(|ui| {
+            ui.label("Minimum delta:");
+            ui.add(
+                DragValue::new(&mut conf.min_delta)
+                    .speed(0.0001)
+                    .clamp_range(0.0..=f64::MAX),
+            );
+        });
+        ui.checkbox(&mut conf.restore_best, "Restore best weights");
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut conf.use_target_error, "Target error");
+        ui.add_enabled(
+            conf.use_target_error,
+            DragValue::new(&mut conf.target_error)
+                .speed(0.0001)
+                .clamp_range(0.0..=f64::MAX),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.checkbox(&mut conf.use_time_limit, "Time limit (minutes)");
+        ui.add_enabled(
+            conf.use_time_limit,
+            DragValue::new(&mut conf.time_limit_minutes).clamp_range(0.0..=f64::MAX),
+        );
+    });
+    ui.horizontal(|ui| {
+        ui.label("Validate every (epochs):");
+        ui.add(DragValue::new(&mut conf.validation_interval).clamp_range(1..=10000));
+    });
+}
+
+fn show_classification(ui: &mut egui::Ui, report: &ClassificationReport) {
+    ui.separator();
+    ui.label(format!("Accuracy: {:.4}", report.accuracy));
+    ui.label(format!("Cohen's kappa: {:.4}", report.kappa));
+    ui.label(format!(
+        "Macro precision / recall / F1: {:.4} / {:.4} / {:.4}",
+        report.macro_avg.precision, report.macro_avg.recall, report.macro_avg.f1
+    ));
+    ui.label(format!(
+        "Micro precision / recall / F1: {:.4} / {:.4} / {:.4}",
+        report.micro_avg.precision, report.micro_avg.recall, report.micro_avg.f1
+    ));
+    if let Some(log_loss) = report.log_loss {
+        ui.label(format!("Log-loss: {:.4}", log_loss));
+    }
+    if let Some(auc) = report.macro_roc_auc {
+        ui.label(format!("ROC AUC (one-vs-rest, macro): {:.4}", auc));
+    }
+
+    ui.collapsing("Confusion matrix", |ui| {
+        let classes = report.confusion.classes();
+        egui::Grid::new("confusion_matrix")
+            .striped(true)
+            .show(ui, |ui| {
+                ui.label("Actual \\ Predicted");
+                for predicted in 0..classes {
+                    ui.strong(predicted.to_string());
+                }
+                ui.strong("Recall");
+                ui.end_row();
+                for actual in 0..classes {
+                    ui.strong(actual.to_string());
+                    for predicted in 0..classes {
+                        let count = report.confusion.counts[(actual, predicted)];
+                        if actual == predicted {
+                            ui.colored_label(Color32::GREEN, count.to_string());
+                        } else {
+                            ui.label(count.to_string());
+                        }
+                    }
+                    ui.label(format!("{:.3}", report.per_class[actual].recall));
+                    ui.end_row();
+                }
+                ui.strong("Precision");
+                for predicted in 0..classes {
+                    ui.label(format!("{:.3}", report.per_class[predicted].precision));
+                }
+                ui.end_row();
+            });
+    });
+}
+
+fn show_regression(ui: &mut egui::Ui, report: &RegressionReport) {
+    ui.separator();
+    ui.label(format!("MAE: {:.4}", report.mae));
+    ui.label(format!("RMSE: {:.4}", report.rmse));
+    ui.label(format!("R²: {:.4}", report.r2));
+    if let Some(mape) = report.mape {
+        ui.label(format!("MAPE: {:.2}%", mape));
+    }
+    ui.collapsing("Residuals", |ui| {
+        egui::Grid::new("residual_stats").show(ui, |ui| {
+            for (name, value) in [
+                ("Mean", report.residuals.mean),
+                ("Std. deviation", report.residuals.std_dev),
+                ("Min", report.residuals.min),
+                ("Median", report.residuals.median),
+                ("Max", report.residuals.max),
+            ] {
+                ui.label(name);
+                ui.label(format!("{:.4}", value));
+                ui.end_row();
+            }
+        });
+    });
+}
+
+fn show_som_settings(ui: &mut egui::Ui, conf: &mut SomPreConfig) {
+    ui.horizontal(|ui| {
+        ui.add(Slider::new(&mut conf.rows, 1usize..=40usize).text("Rows"));
+        ui.add(Slider::new(&mut conf.cols, 1usize..=40usize).text("Columns"));
+        egui::ComboBox::from_label("Grid")
+            .selected_text(format!("{:?}", conf.topology))
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut conf.topology, Topology::Rectangular, "Rectangular");
+                ui.selectable_value(&mut conf.topology, Topology::Hexagonal, "Hexagonal");
+            });
+    });
+    let training = &mut conf.training;
+    ui.horizontal(|ui| {
+        ui.add(Slider::new(&mut training.epochs, 1usize..=1000usize).text("Epochs"));
+        ui.label("Learning rate from");
+        ui.add(
+            DragValue::new(&mut training.initial_rate)
+                .speed(0.01)
+                .clamp_range(0.0..=1.0),
+        );
+        ui.label("to");
+        ui.add(
+            DragValue::new(&mut training.final_rate)
+                .speed(0.001)
+                .clamp_range(0.0..=1.0),
+        );
+    });
+    ui.horizontal(|ui| {
+        let mut automatic = training.initial_radius.is_none();
+        ui.checkbox(&mut automatic, "Radius from half the map");
+        if automatic {
+            training.initial_radius = None;
+        } else {
+            let radius = training
+                .initial_radius
+                .get_or_insert(conf.rows.max(conf.cols) as f64 / 2.0);
+            ui.label("Radius from");
+            ui.add(DragValue::new(radius).speed(0.1).clamp_range(0.1..=40.0));
+        }
+        ui.label("to");
+        ui.add(
+            DragValue::new(&mut training.final_radius)
+                .speed(0.05)
+                .clamp_range(0.01..=40.0),
+        );
+    });
+}
+
+fn show_som(ui: &mut egui::Ui, som: &Som, conf: &SomPreConfig) {
+    if let Some(error) = conf.quantization_error {
+        ui.label(format!("Quantization error: {:.6}", error));
+    }
+    egui::ScrollArea::vertical().show(ui, |ui| {
+        ui.label("U-matrix, distance to the neighbouring units");
+        draw_map(ui, som, &som.u_matrix(), 24.0);
+        ui.label("Component planes");
+        ui.horizontal_wrapped(|ui| {
+            for feature in 0..som.weights.ncols() {
+                ui.vertical(|ui| {
+                    ui.label(format!("Param {}", feature + 1));
+                    draw_map(ui, som, &som.component_plane(feature), 10.0);
+                });
+            }
+        });
+    });
+}
+
+// Values laid out like the grid of the map, from dark blue for the lowest to yellow for the highest
+fn draw_map(ui: &mut egui::Ui, som: &Som, values: &ndarray::Array2<f64>, cell: f32) {
+    let (min, max) = values
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), v| {
+            (min.min(*v), max.max(*v))
+        });
+    let range = if max > min { max - min } else { 1.0 };
+    let (width, height) = match som.topology {
+        Topology::Rectangular => (som.cols as f32, som.rows as f32),
+        Topology::Hexagonal => (
+            som.cols as f32 + 0.5,
+            (som.rows as f32 - 1.0) * 3f32.sqrt() / 2.0 + 2.0 / 3f32.sqrt(),
+        ),
+    };
+    let (response, painter) = ui.allocate_painter(vec2(width, height) * cell, Sense::hover());
+    let origin = response.rect.min;
+
+    for unit in 0..som.units() {
+        let (row, col) = som.coords(unit);
+        let t = ((values[[row, col]] - min) / range) as f32;
+        let color = Color32::from_rgb(
+            (40.0 + 215.0 * t) as u8,
+            (30.0 + 200.0 * t) as u8,
+            (120.0 * (1.0 - t)) as u8,
+        );
+        let (x, y) = som.position(unit);
+        match som.topology {
+            Topology::Rectangular => {
+                let min = origin + vec2(x as f32, y as f32) * cell;
+                painter.rect_filled(Rect::from_min_size(min, vec2(cell, cell)), 0.0, color);
+            }
+            Topology::Hexagonal => {
+                let radius = cell / 3f32.sqrt();
+                let center = origin + vec2(x as f32 + 0.5, y as f32) * cell + vec2(0.0, radius);
+                let corners = (0..6)
+                    .map(|corner| {
+                        let angle =
+                            std::f32::consts::PI / 3.0 * corner as f32 + std::f32::consts::PI / 6.0;
+                        center + vec2(angle.cos(), angle.sin()) * radius
+                    })
+                    .collect();
+                painter.add(Shape::convex_polygon(corners, color, Stroke::NONE));
+            }
+        }
+    }
+}
+
+// Starts training `network` on its own thread with the configured trainer
+fn launch(
+    network: Network,
+    meta: &Metadata,
+    conf: &NetPreConfig,
+    info: &Arc<RwLock<NetworkInfo>>,
+) -> NetworkHandles {
+    let signals = channel::<NetworkSignal>();
+    let responses = channel::<NetworkResponse>();
+
+    let handle = run_threaded(
+        network,
+        responses.0,
+        signals.1,
+        info.clone(),
+        conf.schedule(),
+        conf.trainer(),
+    );
+    let _ = signals
+        .0
+        .send(NetworkSignal::SetEvaluator(Evaluator::from_metadata(meta)));
+    NetworkHandles {
+        thread_handler: handle,
+        tx_handle: signals.0,
+        rx_handle: responses.1,
+    }
+}
+
+fn show_cascade_settings(ui: &mut egui::Ui, training: &mut CascadeTraining) {
+    ui.horizontal(|ui| {
+        ui.label("Target error");
+        ui.add(
+            DragValue::new(&mut training.target_error)
+                .speed(1e-4)
+                .clamp_range(0.0..=f64::MAX),
+        );
+        ui.add(Slider::new(&mut training.max_hidden, 1usize..=100usize).text("Max hidden units"));
+    });
+    ui.horizontal(|ui| {
+        ui.add(Slider::new(&mut training.candidates, 1usize..=32usize).text("Candidates"));
+        ui.add(
+            Slider::new(&mut training.candidate_epochs, 10usize..=1000usize)
+                .text("Candidate epochs"),
+        );
+        ui.add(Slider::new(&mut training.output_epochs, 10usize..=1000usize).text("Output epochs"));
+    });
+}
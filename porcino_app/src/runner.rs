@@ -1,11 +1,14 @@
-use crate::app::NetworkInfo;
-use porcino_core::errors::Sse;
+use crate::app::{NetworkInfo, TrainingHistory};
+use porcino_core::enums::Mode;
+use porcino_core::metrics::{EvaluationReport, Evaluator};
 use porcino_core::network::Network;
-use porcino_core::traits::ErrorFn;
+use porcino_core::training::{LrSchedule, Observation, StopConditions, StopReason, Trainer};
 use porcino_data::parse::TrainingSample;
+use std::path::PathBuf;
 use std::sync::{mpsc, Arc, RwLock};
 use std::thread;
 use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
 
 pub struct NetworkHandles {
     pub thread_handler: JoinHandle<()>,
@@ -20,13 +23,53 @@ pub enum NetworkSignal {
     SetData(Vec<TrainingSample>),
     SetReportInterval(usize),
     EvalData(Option<Vec<TrainingSample>>),
+    SetEvaluator(Evaluator),
+    SetStopConditions(Box<StopConditions>),
+    Save(PathBuf),
 }
+
+/// Work running on its own thread, which the UI polls every frame
+pub struct Job<T> {
+    progress: Arc<RwLock<f32>>,
+    handle: JoinHandle<T>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    /// Starts `work`, which reports how much of it is done, from 0 to 1, as it goes
+    pub fn spawn(work: impl FnOnce(&dyn Fn(f32)) -> T + Send + 'static) -> Self {
+        let progress = Arc::new(RwLock::new(0.0));
+        let shared = progress.clone();
+        let handle = thread::spawn(move || {
+            work(&|done| {
+                if let Ok(mut guard) = shared.write() {
+                    *guard = done;
+                }
+            })
+        });
+        Self { progress, handle }
+    }
+
+    pub fn progress(&self) -> f32 {
+        self.progress.read().map_or(0.0, |progress| *progress)
+    }
+
+    /// Result of the finished work, `None` if it panicked, or the job itself while it runs
+    pub fn poll(self) -> Result<Option<T>, Self> {
+        if self.handle.is_finished() {
+            Ok(self.handle.join().ok())
+        } else {
+            Err(self)
+        }
+    }
+}
+
 pub fn run_threaded(
     mut network: Network,
     tx: mpsc::Sender<NetworkResponse>,
     rx: mpsc::Receiver<NetworkSignal>,
     status: Arc<RwLock<NetworkInfo>>,
-    initial_eta: f64,
+    mut schedule: Box<dyn LrSchedule>,
+    mut trainer: Box<dyn Trainer>,
 ) -> JoinHandle<()> {
     thread::spawn(move || {
         // Basically state variables
@@ -39,7 +82,13 @@ pub fn run_threaded(
         let mut report_interval = 0;
         let mut resume_message: Option<NetworkSignal> = None;
         let mut eval_result: f64 = 0.0;
-        let eta = initial_eta;
+        let mut evaluator = Evaluator::default();
+        let mut evaluation = None;
+        let mut stop_conditions = StopConditions::default();
+        let mut stop_reason = None;
+        let mut elapsed = Duration::ZERO;
+        let mut history = TrainingHistory::default();
+        let mut pending_loss = None;
         loop {
             // Thread communication
             // This may significantly impact performance
@@ -52,27 +101,35 @@ pub fn run_threaded(
             };
             if let Ok(signal) = signal {
                 match signal {
-                    NetworkSignal::Toggle => running = !running,
+                    NetworkSignal::Toggle => {
+                        running = !running;
+                        // Resuming after a stop starts the stop conditions over
+                        if running && stop_reason.take().is_some() {
+                            stop_conditions.reset();
+                            elapsed = Duration::ZERO;
+                        }
+                    }
                     NetworkSignal::SetEpochs(epochs) => epochs_to_run += epochs,
                     NetworkSignal::SetData(data) => training_data = data.clone(),
                     NetworkSignal::EvalData(data) => {
                         eval_data = data;
                     }
                     NetworkSignal::SetReportInterval(interval) => report_interval = interval,
+                    NetworkSignal::SetEvaluator(new_evaluator) => evaluator = new_evaluator,
+                    NetworkSignal::SetStopConditions(conditions) => stop_conditions = *conditions,
+                    NetworkSignal::Save(path) => {
+                        if let Err(e) = porcino_core::persistence::save(&network, &path) {
+                            log::error!("Saving network to {:?} failed: {}", path, e);
+                        }
+                    }
                     NetworkSignal::Kill => break,
                 }
             } else {
                 if report_interval != 0 && epoch_count % report_interval == 0 {
                     if let Some(data) = &eval_data {
-                        eval_result = data
-                            .iter()
-                            .map(|record| {
-                                network.process_data(&record.input);
-                                let output = &network.layers.last().unwrap().state;
-                                Sse::cost_function(output, &record.expected_output)
-                            })
-                            .map(|v| v * v)
-                            .sum();
+                        let (result, _, report) = evaluate(&mut network, data, &evaluator);
+                        eval_result = result;
+                        evaluation = Some(report);
                     }
 
                     report_status(
@@ -80,14 +137,62 @@ pub fn run_threaded(
                         epoch_count,
                         epochs_to_run,
                         eval_result,
+                        evaluation.clone(),
+                        stop_reason,
                         running,
                     );
+                    report_history(&status, &mut history);
                 }
 
                 // Network stuff
                 if running && epoch_count < epochs_to_run {
-                    network.gradient_descent(&training_data, eta);
+                    let eta = schedule.learning_rate(epoch_count, pending_loss.take());
+                    let started = Instant::now();
+                    let training_loss = trainer.epoch(&mut network, &training_data, eta)
+                        / training_data.len().max(1) as f64;
+                    elapsed += started.elapsed();
                     epoch_count += 1;
+
+                    let mut validation_loss = None;
+                    if stop_conditions.validation_due(epoch_count) || schedule.uses_loss() {
+                        if let Some(data) = &eval_data {
+                            let (result, loss, report) = evaluate(&mut network, data, &evaluator);
+                            eval_result = result;
+                            validation_loss = Some(loss);
+                            evaluation = Some(report);
+                        }
+                    }
+                    // Without a validation set the schedule follows the training loss
+                    pending_loss = match eval_data {
+                        Some(_) => validation_loss,
+                        None => Some(training_loss),
+                    };
+                    history.push(epoch_count, training_loss, validation_loss, eta);
+
+                    let observation = Observation {
+                        epoch: epoch_count,
+                        elapsed,
+                        training_loss: Some(training_loss),
+                        validation_loss,
+                        has_validation_set: eval_data.is_some(),
+                        report: validation_loss.and(evaluation.as_ref()),
+                    };
+
+                    if let Some(reason) = stop_conditions.check(&observation, &mut network) {
+                        stop_reason = Some(reason);
+                        running = false;
+                        // Weights may have been restored, so refresh the reported metrics
+                        if let Some(data) = &eval_data {
+                            let (result, _, report) = evaluate(&mut network, data, &evaluator);
+                            eval_result = result;
+                            evaluation = Some(report);
+                        }
+                    } else if trainer.converged() {
+                        stop_reason = Some(StopReason::Converged);
+                        running = false;
+                    } else if epoch_count == epochs_to_run {
+                        stop_reason = Some(StopReason::EpochsCompleted);
+                    }
                 } else {
                     // Send thread to sleep
                     report_status(
@@ -95,8 +200,11 @@ pub fn run_threaded(
                         epoch_count,
                         epochs_to_run,
                         eval_result,
+                        evaluation.clone(),
+                        stop_reason,
                         false,
                     );
+                    report_history(&status, &mut history);
                     resume_message = rx.recv().ok();
                 }
             }
@@ -104,17 +212,55 @@ pub fn run_threaded(
     })
 }
 
+// Runs the network over the evaluation set, returning the reported result,
+// the mean loss per sample and the metrics
+fn evaluate(
+    network: &mut Network,
+    data: &[TrainingSample],
+    evaluator: &Evaluator,
+) -> (f64, f64, EvaluationReport) {
+    network.set_mode(Mode::Inference);
+    let (outputs, errors): (Vec<_>, Vec<_>) = data
+        .iter()
+        .map(|record| {
+            network.process_data(&record.input);
+            (
+                network.output().clone(),
+                network.loss(&record.expected_output),
+            )
+        })
+        .unzip();
+    network.set_mode(Mode::Train);
+
+    (
+        errors.iter().map(|v| v * v).sum(),
+        errors.iter().sum::<f64>() / data.len().max(1) as f64,
+        evaluator.evaluate(&outputs, data),
+    )
+}
+
 fn report_status(
     lock: Arc<RwLock<NetworkInfo>>,
     epochs: usize,
     epochs_to_run: usize,
     last_eval_result: f64,
+    evaluation: Option<EvaluationReport>,
+    stop_reason: Option<StopReason>,
     running: bool,
 ) {
     if let Ok(mut guard) = lock.write() {
         guard.epochs = epochs;
         guard.epochs_to_run = epochs_to_run;
         guard.last_eval_result = last_eval_result;
+        guard.evaluation = evaluation;
+        guard.stop_reason = stop_reason;
         guard.running = running;
     }
 }
+
+// Moves the history gathered since the last report into the shared status
+fn report_history(lock: &Arc<RwLock<NetworkInfo>>, history: &mut TrainingHistory) {
+    if let Ok(mut guard) = lock.write() {
+        guard.history.append(history);
+    }
+}
@@ -0,0 +1,118 @@
+//! Trains a small convolutional network on digits rendered from a 5x7 bitmap font,
+//! shifted around a 10x10 canvas and covered in noise.
+//!
+//! cargo run --release -p porcino_core --example cnn_digits
+use ndarray::Array2;
+use porcino_core::data::train_validation_split;
+use porcino_core::enums::{InitializationMethods, Mode};
+use porcino_core::metrics::ClassificationReport;
+use porcino_core::network::{Conv2D, FFLayer, Flatten, MaxPool, Network, Shape, Sigmoid};
+use porcino_core::traits::Layer;
+use porcino_data::parse::TrainingSample;
+use rand::prelude::*;
+
+const SIDE: usize = 10;
+
+const FONT: [[&str; 7]; 10] = [
+    [
+        ".###.", "#...#", "#..##", "#.#.#", "##..#", "#...#", ".###.",
+    ],
+    [
+        "..#..", ".##..", "..#..", "..#..", "..#..", "..#..", ".###.",
+    ],
+    [
+        ".###.", "#...#", "....#", "...#.", "..#..", ".#...", "#####",
+    ],
+    [
+        "####.", "....#", "....#", ".###.", "....#", "....#", "####.",
+    ],
+    [
+        "...#.", "..##.", ".#.#.", "#..#.", "#####", "...#.", "...#.",
+    ],
+    [
+        "#####", "#....", "####.", "....#", "....#", "#...#", ".###.",
+    ],
+    [
+        ".###.", "#....", "#....", "####.", "#...#", "#...#", ".###.",
+    ],
+    [
+        "#####", "....#", "...#.", "..#..", ".#...", ".#...", ".#...",
+    ],
+    [
+        ".###.", "#...#", "#...#", ".###.", "#...#", "#...#", ".###.",
+    ],
+    [
+        ".###.", "#...#", "#...#", ".####", "....#", "....#", ".###.",
+    ],
+];
+
+fn render(digit: usize, rng: &mut StdRng) -> TrainingSample {
+    let dx = rng.gen_range(0..=SIDE - 5);
+    let dy = rng.gen_range(0..=SIDE - 7);
+    let mut input = Array2::from_shape_fn((SIDE * SIDE, 1), |_| rng.gen_range(0.0..0.3));
+    for (y, row) in FONT[digit].iter().enumerate() {
+        for (x, pixel) in row.chars().enumerate() {
+            if pixel == '#' {
+                input[[(y + dy) * SIDE + x + dx, 0]] = rng.gen_range(0.7..1.0);
+            }
+        }
+    }
+    let mut expected_output = Array2::zeros((10, 1));
+    expected_output[[digit, 0]] = 1.0;
+    TrainingSample {
+        input,
+        expected_output,
+    }
+}
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let samples = (0..2000)
+        .map(|i| render(i % 10, &mut rng))
+        .collect::<Vec<_>>();
+    let (mut train, test) = train_validation_split(samples, 0.25, 7);
+
+    let conv = Conv2D::new(
+        Shape::new(1, SIDE, SIDE),
+        8,
+        (3, 3),
+        InitializationMethods::Random,
+        &Sigmoid,
+    );
+    let pool = MaxPool::new(conv.output_shape(), (2, 2));
+    let flatten = Flatten::new(pool.output_shape());
+    let dense = FFLayer::new(flatten.outputs(), 10, InitializationMethods::Zero, &Sigmoid);
+    let layers: Vec<Box<dyn Layer>> = vec![
+        Box::new(conv),
+        Box::new(pool),
+        Box::new(flatten),
+        Box::new(dense),
+    ];
+    let mut network = Network::from_layers(layers);
+
+    for epoch in 1..=50 {
+        train.shuffle(&mut rng);
+        let error = train
+            .chunks(10)
+            .map(|batch| network.gradient_descent(batch, 0.1))
+            .sum::<f64>();
+        if epoch % 10 == 0 {
+            println!("epoch {epoch}: error {:.4}", error / train.len() as f64);
+        }
+    }
+
+    network.set_mode(Mode::Inference);
+    let outputs = test
+        .iter()
+        .map(|sample| {
+            network.process_data(&sample.input);
+            network.output().clone()
+        })
+        .collect::<Vec<_>>();
+    let report = ClassificationReport::new(&outputs, &test);
+    println!(
+        "Test accuracy: {}/{}",
+        report.confusion.correct(),
+        report.confusion.total()
+    );
+}
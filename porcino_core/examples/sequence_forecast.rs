@@ -0,0 +1,75 @@
+//! Forecasts the next reading of a noisy periodic signal with an LSTM, trained on
+//! windows cut from the series with truncated backpropagation through time.
+//!
+//! cargo run --release -p porcino_core --example sequence_forecast
+use porcino_core::enums::InitializationMethods;
+use porcino_core::network::{FFLayer, Linear, Network};
+use porcino_core::recurrent::{Lstm, SequenceNetwork};
+use porcino_core::traits::Layer;
+use porcino_data::parse::{Metadata, TaggedData};
+use porcino_data::sequence::{get_windowed_data, SequenceMode, WindowSettings};
+use rand::prelude::*;
+
+fn main() {
+    let mut rng = StdRng::seed_from_u64(5);
+    // A single sensor column, used both as the input and as the forecast target
+    let series = TaggedData {
+        data: (0..600)
+            .map(|t| {
+                let t = t as f64 * 0.2;
+                let reading =
+                    0.5 + 0.3 * t.sin() + 0.1 * (2.7 * t).sin() + rng.gen_range(-0.02..0.02);
+                vec![reading, reading]
+            })
+            .collect(),
+        meta: Metadata {
+            params: vec![0],
+            classes: vec![1],
+            ..Default::default()
+        },
+    };
+    let mut samples = get_windowed_data(
+        &series,
+        &WindowSettings {
+            length: 20,
+            stride: 2,
+            horizon: 1,
+            mode: SequenceMode::ManyToOne,
+        },
+    );
+    let test = samples.split_off(samples.len() * 4 / 5);
+
+    let head = Network::from_layers(vec![Box::new(FFLayer::new(
+        16,
+        1,
+        InitializationMethods::Random,
+        &Linear,
+    )) as Box<dyn Layer>]);
+    let mut network = SequenceNetwork::new(
+        vec![Box::new(Lstm::new(1, 16, InitializationMethods::Random))],
+        head,
+        SequenceMode::ManyToOne,
+    )
+    .with_truncation(10);
+
+    for epoch in 1..=60 {
+        samples.shuffle(&mut rng);
+        let error = samples
+            .chunks(8)
+            .map(|batch| network.gradient_descent(batch, 0.1))
+            .sum::<f64>();
+        if epoch % 10 == 0 {
+            println!("epoch {epoch}: mse {:.6}", error / samples.len() as f64);
+        }
+    }
+
+    let mse = test
+        .iter()
+        .map(|sample| {
+            let output = network.process_sequence(&sample.inputs);
+            (&output[0] - &sample.expected_outputs[0])[[0, 0]].powi(2)
+        })
+        .sum::<f64>()
+        / test.len() as f64;
+    println!("Test mse: {:.6}", mse);
+}
@@ -0,0 +1,391 @@
+//! Reverse mode automatic differentiation over matrices. A `Tape` records every operation
+//! of a forward expression, `backward` then walks it in reverse to get the gradient of
+//! the result with respect to every recorded value.
+//!
+//! Element-wise operations broadcast like ndarray does, so a column of biases can be added
+//! to a batch with one sample per column. Gradients are summed back into the broadcast shape.
+use ndarray::{s, Array2, Axis};
+
+/// Handle of a value recorded on a `Tape`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Var(usize);
+
+#[derive(Debug, Clone)]
+enum Op {
+    Leaf,
+    MatMul(Var, Var),
+    Add(Var, Var),
+    Sub(Var, Var),
+    Mul(Var, Var),
+    Div(Var, Var),
+    Neg(Var),
+    Scale(Var, f64),
+    Offset(Var),
+    Powi(Var, i32),
+    Exp(Var),
+    Ln(Var),
+    Sqrt(Var),
+    Sigmoid(Var),
+    Tanh(Var),
+    Relu(Var),
+    /// Element-wise function with its derivative already evaluated at the input
+    Map(Var, Array2<f64>),
+    /// Gradient passes through the clamp unchanged
+    Clamp(Var),
+    Transpose(Var),
+    Sum(Var),
+    SumCols(Var),
+    Mean(Var),
+    Rows(Var, usize),
+    Concat(Vec<Var>),
+}
+
+#[derive(Debug, Clone)]
+struct Entry {
+    value: Array2<f64>,
+    op: Op,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Tape {
+    entries: Vec<Entry>,
+}
+
+/// Gradients of one output with respect to the values of a tape
+#[derive(Debug, Clone)]
+pub struct Gradients {
+    grads: Vec<Option<Array2<f64>>>,
+}
+
+impl Gradients {
+    /// Gradient with respect to `var`, `None` when the output does not depend on it
+    pub fn of(&self, var: Var) -> Option<&Array2<f64>> {
+        self.grads[var.0].as_ref()
+    }
+
+    /// Like `of`, taking the gradient out
+    pub fn take(&mut self, var: Var) -> Option<Array2<f64>> {
+        self.grads[var.0].take()
+    }
+}
+
+impl Tape {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Records a value to differentiate against, e.g. an input or a parameter
+    pub fn var(&mut self, value: Array2<f64>) -> Var {
+        self.push(value, Op::Leaf)
+    }
+
+    pub fn value(&self, var: Var) -> &Array2<f64> {
+        &self.entries[var.0].value
+    }
+
+    /// Number of recorded values
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn matmul(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a).dot(self.value(b));
+        self.push(value, Op::MatMul(a, b))
+    }
+
+    pub fn add(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) + self.value(b);
+        self.push(value, Op::Add(a, b))
+    }
+
+    pub fn sub(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) - self.value(b);
+        self.push(value, Op::Sub(a, b))
+    }
+
+    /// Element-wise product
+    pub fn mul(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) * self.value(b);
+        self.push(value, Op::Mul(a, b))
+    }
+
+    /// Element-wise quotient
+    pub fn div(&mut self, a: Var, b: Var) -> Var {
+        let value = self.value(a) / self.value(b);
+        self.push(value, Op::Div(a, b))
+    }
+
+    pub fn neg(&mut self, a: Var) -> Var {
+        let value = -self.value(a);
+        self.push(value, Op::Neg(a))
+    }
+
+    /// Multiplies every element by a constant
+    pub fn scale(&mut self, a: Var, factor: f64) -> Var {
+        let value = self.value(a) * factor;
+        self.push(value, Op::Scale(a, factor))
+    }
+
+    /// Adds a constant to every element
+    pub fn offset(&mut self, a: Var, by: f64) -> Var {
+        let value = self.value(a) + by;
+        self.push(value, Op::Offset(a))
+    }
+
+    pub fn powi(&mut self, a: Var, n: i32) -> Var {
+        let value = self.value(a).mapv(|v| v.powi(n));
+        self.push(value, Op::Powi(a, n))
+    }
+
+    pub fn square(&mut self, a: Var) -> Var {
+        self.powi(a, 2)
+    }
+
+    pub fn exp(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(f64::exp);
+        self.push(value, Op::Exp(a))
+    }
+
+    pub fn ln(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(f64::ln);
+        self.push(value, Op::Ln(a))
+    }
+
+    pub fn sqrt(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(f64::sqrt);
+        self.push(value, Op::Sqrt(a))
+    }
+
+    pub fn sigmoid(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(|v| 1.0 / (1.0 + (-v).exp()));
+        self.push(value, Op::Sigmoid(a))
+    }
+
+    pub fn tanh(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(f64::tanh);
+        self.push(value, Op::Tanh(a))
+    }
+
+    pub fn relu(&mut self, a: Var) -> Var {
+        let value = self.value(a).mapv(|v| v.max(0.0));
+        self.push(value, Op::Relu(a))
+    }
+
+    /// Element-wise function given by its values and derivative at `a`
+    pub fn map(&mut self, a: Var, value: Array2<f64>, derivative: Array2<f64>) -> Var {
+        assert_eq!(value.raw_dim(), self.value(a).raw_dim());
+        assert_eq!(derivative.raw_dim(), self.value(a).raw_dim());
+        self.push(value, Op::Map(a, derivative))
+    }
+
+    /// Keeps values between `min` and `max`, e.g. away from the poles of a logarithm.
+    /// The gradient is not cut off, so clamped values keep learning.
+    pub fn clamp(&mut self, a: Var, min: f64, max: f64) -> Var {
+        let value = self.value(a).mapv(|v| v.clamp(min, max));
+        self.push(value, Op::Clamp(a))
+    }
+
+    pub fn transpose(&mut self, a: Var) -> Var {
+        let value = self.value(a).t().to_owned();
+        self.push(value, Op::Transpose(a))
+    }
+
+    /// Sum of all elements as a 1x1 matrix
+    pub fn sum(&mut self, a: Var) -> Var {
+        let value = Array2::from_elem((1, 1), self.value(a).sum());
+        self.push(value, Op::Sum(a))
+    }
+
+    /// Sum over the columns, i.e. over the samples of a batch
+    pub fn sum_cols(&mut self, a: Var) -> Var {
+        let value = self.value(a).sum_axis(Axis(1)).insert_axis(Axis(1));
+        self.push(value, Op::SumCols(a))
+    }
+
+    /// Mean of all elements as a 1x1 matrix
+    pub fn mean(&mut self, a: Var) -> Var {
+        let value = Array2::from_elem((1, 1), self.value(a).mean().unwrap_or(0.0));
+        self.push(value, Op::Mean(a))
+    }
+
+    /// `len` rows starting at `start`
+    pub fn rows(&mut self, a: Var, start: usize, len: usize) -> Var {
+        let value = self.value(a).slice(s![start..start + len, ..]).to_owned();
+        self.push(value, Op::Rows(a, start))
+    }
+
+    /// Values stacked on top of each other
+    pub fn concat(&mut self, from: &[Var]) -> Var {
+        let views = from
+            .iter()
+            .map(|v| self.value(*v).view())
+            .collect::<Vec<_>>();
+        let value = ndarray::concatenate(Axis(0), &views)
+            .expect("concatenated values need as many columns");
+        self.push(value, Op::Concat(from.to_vec()))
+    }
+
+    /// Gradient of the sum of `output` with respect to everything recorded before it
+    pub fn backward(&self, output: Var) -> Gradients {
+        let seed = Array2::ones(self.value(output).raw_dim());
+        self.backward_from(output, seed)
+    }
+
+    /// Gradients given the gradient `seed` with respect to `output`, like a layer receives
+    /// the gradient of the loss with respect to its output
+    pub fn backward_from(&self, output: Var, seed: Array2<f64>) -> Gradients {
+        assert_eq!(seed.raw_dim(), self.value(output).raw_dim());
+        let mut grads: Vec<Option<Array2<f64>>> = vec![None; self.entries.len()];
+        grads[output.0] = Some(seed);
+
+        for index in (0..=output.0).rev() {
+            let Some(grad) = grads[index].take() else {
+                continue;
+            };
+            let entry = &self.entries[index];
+            for (var, contribution) in self.local_gradients(entry, &grad) {
+                match &mut grads[var.0] {
+                    Some(sum) => *sum += &contribution,
+                    empty => *empty = Some(contribution),
+                }
+            }
+            grads[index] = Some(grad);
+        }
+        Gradients { grads }
+    }
+
+    fn push(&mut self, value: Array2<f64>, op: Op) -> Var {
+        self.entries.push(Entry { value, op });
+        Var(self.entries.len() - 1)
+    }
+
+    // Gradients flowing from an entry into the values it was computed from
+    fn local_gradients(&self, entry: &Entry, grad: &Array2<f64>) -> Vec<(Var, Array2<f64>)> {
+        let value = |var: &Var| self.value(*var);
+        let out = &entry.value;
+        let reduced = |var: &Var, g: Array2<f64>| (*var, reduce_to(g, value(var).dim()));
+        match &entry.op {
+            Op::Leaf => Vec::new(),
+            Op::MatMul(a, b) => vec![(*a, grad.dot(&value(b).t())), (*b, value(a).t().dot(grad))],
+            Op::Add(a, b) => vec![reduced(a, grad.clone()), reduced(b, grad.clone())],
+            Op::Sub(a, b) => vec![reduced(a, grad.clone()), reduced(b, -grad)],
+            Op::Mul(a, b) => vec![reduced(a, grad * value(b)), reduced(b, grad * value(a))],
+            Op::Div(a, b) => vec![
+                reduced(a, grad / value(b)),
+                reduced(b, -(grad * value(a)) / value(b).mapv(|v| v * v)),
+            ],
+            Op::Neg(a) => vec![(*a, -grad)],
+            Op::Scale(a, factor) => vec![(*a, grad * *factor)],
+            Op::Offset(a) | Op::Clamp(a) => vec![(*a, grad.clone())],
+            Op::Powi(a, n) => {
+                let n = *n;
+                vec![(*a, grad * &value(a).mapv(|v| n as f64 * v.powi(n - 1)))]
+            }
+            Op::Exp(a) => vec![(*a, grad * out)],
+            Op::Ln(a) => vec![(*a, grad / value(a))],
+            Op::Sqrt(a) => vec![(*a, grad / &(out * 2.0))],
+            Op::Sigmoid(a) => vec![(*a, grad * &out.mapv(|v| v * (1.0 - v)))],
+            Op::Tanh(a) => vec![(*a, grad * &out.mapv(|v| 1.0 - v * v))],
+            Op::Relu(a) => vec![(
+                *a,
+                grad * &value(a).mapv(|v| if v > 0.0 { 1.0 } else { 0.0 }),
+            )],
+            Op::Map(a, derivative) => vec![(*a, grad * derivative)],
+            Op::Transpose(a) => vec![(*a, grad.t().to_owned())],
+            Op::Sum(a) => vec![(*a, Array2::from_elem(value(a).raw_dim(), grad[[0, 0]]))],
+            Op::SumCols(a) => {
+                let spread = grad.broadcast(value(a).raw_dim()).expect("column gradient");
+                vec![(*a, spread.to_owned())]
+            }
+            Op::Mean(a) => {
+                let len = value(a).len().max(1) as f64;
+                vec![(
+                    *a,
+                    Array2::from_elem(value(a).raw_dim(), grad[[0, 0]] / len),
+                )]
+            }
+            Op::Rows(a, start) => {
+                let mut full = Array2::zeros(value(a).raw_dim());
+                full.slice_mut(s![*start..*start + grad.nrows(), ..])
+                    .assign(grad);
+                vec![(*a, full)]
+            }
+            Op::Concat(from) => {
+                let mut start = 0;
+                from.iter()
+                    .map(|var| {
+                        let rows = value(var).nrows();
+                        let part = grad.slice(s![start..start + rows, ..]).to_owned();
+                        start += rows;
+                        (*var, part)
+                    })
+                    .collect()
+            }
+        }
+    }
+}
+
+// Sums a gradient over the axes its value was broadcast along
+fn reduce_to(mut grad: Array2<f64>, (rows, cols): (usize, usize)) -> Array2<f64> {
+    if grad.nrows() != rows {
+        grad = grad.sum_axis(Axis(0)).insert_axis(Axis(0));
+    }
+    if grad.ncols() != cols {
+        grad = grad.sum_axis(Axis(1)).insert_axis(Axis(1));
+    }
+    grad
+}
+
+/// Forward pass of a layer kept for its backward pass
+#[derive(Debug, Clone)]
+pub(crate) struct Recorded {
+    pub tape: Tape,
+    pub input: Var,
+    pub params: Vec<Var>,
+    pub output: Var,
+}
+
+impl Recorded {
+    /// Gradients with respect to the input and every parameter, given the one
+    /// with respect to the output
+    pub fn backward(&self, delta: &Array2<f64>) -> (Array2<f64>, Vec<Array2<f64>>) {
+        let mut grads = self.tape.backward_from(self.output, delta.clone());
+        let mut take = |var: Var| {
+            grads
+                .take(var)
+                .unwrap_or_else(|| Array2::zeros(self.tape.value(var).raw_dim()))
+        };
+        let input = take(self.input);
+        let params = self.params.iter().map(|p| take(*p)).collect();
+        (input, params)
+    }
+}
+
+/// Value and gradient of a loss given as an expression of the output and the reference,
+/// both with one sample per column. The gradient is the one of half the loss, which is
+/// what the backward pass of a `Network` expects.
+pub fn loss_gradient(
+    loss: impl Fn(&mut Tape, Var, Var) -> Var,
+    output: &Array2<f64>,
+    reference: &Array2<f64>,
+) -> (f64, Array2<f64>) {
+    assert_eq!(output.raw_dim(), reference.raw_dim());
+    let mut tape = Tape::new();
+    let out = tape.var(output.clone());
+    let reference = tape.var(reference.clone());
+    let value = loss(&mut tape, out, reference);
+    assert_eq!(
+        tape.value(value).dim(),
+        (1, 1),
+        "losses evaluate to a 1x1 value"
+    );
+    let gradient = tape
+        .backward(value)
+        .take(out)
+        .unwrap_or_else(|| Array2::zeros(output.raw_dim()));
+    (tape.value(value)[[0, 0]], gradient * 0.5)
+}
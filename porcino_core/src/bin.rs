@@ -1,49 +1,56 @@
-use ndarray::Array2;
-use porcino_core::enums::InitializationMethods;
-use porcino_core::{data, network::Network};
+use porcino_core::data;
+use porcino_core::enums::{InitializationMethods, Mode};
+use porcino_core::metrics::ClassificationReport;
+use porcino_core::network::{Activations, LayerSettings, Network};
+use porcino_data::parse::TrainingSample;
 fn main() {
-    let mut x = Network::new(vec![13, 8, 3], InitializationMethods::Random);
+    let mut x = Network::new(
+        vec![
+            LayerSettings {
+                neurons: 13,
+                activation: Activations::Linear,
+            },
+            LayerSettings {
+                neurons: 8,
+                activation: Activations::Sigmoid,
+            },
+            LayerSettings {
+                neurons: 3,
+                activation: Activations::Sigmoid,
+            },
+        ],
+        InitializationMethods::Random,
+    );
     let t = data::prepare_file("wine.data", ",");
+    let samples =
+        t.0.into_iter()
+            .map(|(input, expected_output)| TrainingSample {
+                input,
+                expected_output,
+            })
+            .collect::<Vec<_>>();
 
     for _ in 0..100000 {
-        x.gradient_descent(t.0.iter().map(|v| &(&v.0, &v.1)).collect(), 0.0001);
+        x.gradient_descent(&samples, 0.0001);
     }
 
-    let result = evaluate(&mut x, &t.0);
-    println!("Poprawne dopasowania: {}/{}", result.1, result.0);
+    let report = evaluate(&mut x, &samples);
+    println!(
+        "Poprawne dopasowania: {}/{}",
+        report.confusion.correct(),
+        report.confusion.total()
+    );
 }
 
-fn evaluate(net: &mut Network, test_data: &Vec<(Array2<f64>, Array2<f64>)>) -> (usize, usize) {
-    let mut local_data = test_data.clone();
-    let x = local_data
-        .iter_mut()
-        .map(|(x, y)| {
-            (
-                {
-                    net.process_data(x);
-                    net.layers
-                        .last()
-                        .unwrap()
-                        .state
-                        .iter()
-                        .enumerate()
-                        .max_by(|(_, a), (_, b)| {
-                            a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal)
-                        })
-                        .map(|(index, _)| index)
-                },
-                y,
-            )
+fn evaluate(net: &mut Network, test_data: &[TrainingSample]) -> ClassificationReport {
+    net.set_mode(Mode::Inference);
+    let outputs = test_data
+        .iter()
+        .map(|sample| {
+            net.process_data(&sample.input);
+            net.output().clone()
         })
-        .filter(|(a, b)| {
-            a.unwrap_or(0)
-                == b.iter()
-                    .enumerate()
-                    .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
-                    .map(|(index, _)| index)
-                    .unwrap()
-        })
-        .count();
+        .collect::<Vec<_>>();
 
-    (test_data.len(), x)
+    ClassificationReport::new(&outputs, test_data)
 }
@@ -0,0 +1,284 @@
+//! Fahlman's Cascade-Correlation, which grows the hidden units of a network one at a time
+use crate::data::stack_samples;
+use crate::enums::InitializationMethods;
+use crate::network::{Activations, FFLayer, GraphBuilder, Network};
+use crate::training::{Rprop, Trainer};
+use crate::traits::Predictor;
+use ndarray::{concatenate, s, Array2, Axis, Zip};
+use porcino_data::parse::TrainingSample;
+use rand::prelude::*;
+
+/// How `CascadeCorrelation` grows its network
+#[derive(Debug, Clone, Copy)]
+pub struct CascadeTraining {
+    /// Mean error per sample, as reported while training, at which growing stops
+    pub target_error: f64,
+    pub max_hidden: usize,
+    /// Candidates trained for every new hidden unit, of which the best is installed
+    pub candidates: usize,
+    pub candidate_epochs: usize,
+    /// Rprop epochs of the output layer after every installed unit
+    pub output_epochs: usize,
+    pub hidden_activation: Activations,
+    pub output_activation: Activations,
+    pub seed: u64,
+}
+
+impl Default for CascadeTraining {
+    fn default() -> Self {
+        Self {
+            target_error: 1e-3,
+            max_hidden: 20,
+            candidates: 8,
+            candidate_epochs: 200,
+            output_epochs: 300,
+            hidden_activation: Activations::Tanh,
+            output_activation: Activations::Linear,
+            seed: 0,
+        }
+    }
+}
+
+/// Network that starts with its inputs wired straight to the output layer and adds
+/// hidden units of a single neuron until the target error is reached. Every unit reads
+/// the inputs and the outputs of all units before it. A new unit is the best of a pool
+/// of candidates trained to maximize the covariance of their output with the residual
+/// error, and its weights are frozen once installed, so that only the output layer is
+/// retrained. `to_network` exports the result as a regular `Network`.
+#[derive(Debug, Clone)]
+pub struct CascadeCorrelation {
+    pub training: CascadeTraining,
+    inputs: usize,
+    hidden: Vec<FFLayer>,
+    output: FFLayer,
+    // Training error without hidden units and after every installed one
+    history: Vec<f64>,
+    rng: StdRng,
+}
+
+impl CascadeCorrelation {
+    pub fn new(inputs: usize, outputs: usize, training: CascadeTraining) -> Self {
+        Self {
+            inputs,
+            hidden: Vec::new(),
+            output: FFLayer::new(
+                inputs,
+                outputs,
+                InitializationMethods::Zero,
+                training.output_activation.function(),
+            ),
+            history: Vec::new(),
+            rng: StdRng::seed_from_u64(training.seed),
+            training,
+        }
+    }
+
+    /// Network grown on `data`
+    pub fn fit(data: &[TrainingSample], training: CascadeTraining) -> Self {
+        let first = data.first().expect("cascade-correlation needs samples");
+        let mut cascade = Self::new(first.input.nrows(), first.expected_output.nrows(), training);
+        cascade.grow(data);
+        cascade
+    }
+
+    pub fn hidden_units(&self) -> usize {
+        self.hidden.len()
+    }
+
+    /// Training error before the first hidden unit and after every one installed since
+    pub fn history(&self) -> &[f64] {
+        &self.history
+    }
+
+    /// Trains the output layer and installs hidden units until the target error or the
+    /// maximum number of units is reached. Returns the final training error.
+    pub fn grow(&mut self, data: &[TrainingSample]) -> f64 {
+        assert!(!data.is_empty(), "cascade-correlation needs samples");
+        let (inputs, targets) = stack_samples(data);
+        let mut features = self.features(&inputs);
+        let mut error = self.train_outputs(&features, &targets);
+        self.history.push(error);
+
+        while error > self.training.target_error && self.hidden.len() < self.training.max_hidden {
+            let residuals = self.output_value(&features) - &targets;
+            let unit = self.best_candidate(&features, &residuals);
+            features = concatenate![Axis(0), features, unit_value(&unit, &features)];
+            self.hidden.push(unit);
+
+            // The new input starts with a zero weight, so the error can only improve
+            let mut output = FFLayer::new(
+                features.nrows(),
+                targets.nrows(),
+                InitializationMethods::Zero,
+                self.training.output_activation.function(),
+            );
+            output
+                .weights
+                .slice_mut(s![.., ..self.output.weights.ncols()])
+                .assign(&self.output.weights);
+            output.biases.assign(&self.output.biases);
+            self.output = output;
+
+            error = self.train_outputs(&features, &targets);
+            self.history.push(error);
+        }
+        error
+    }
+
+    /// Plain network with the same weights. Every hidden unit is a dense layer of one
+    /// neuron reading the inputs stacked with the units before it.
+    pub fn to_network(&self) -> Network {
+        let mut graph = GraphBuilder::new();
+        let mut features = graph.input(self.inputs);
+        for unit in &self.hidden {
+            let value = graph.layer(Box::new(unit.clone()), features);
+            features = graph.concat(&[features, value]);
+        }
+        let output = graph.layer(Box::new(self.output.clone()), features);
+        graph.build(&[output])
+    }
+
+    // Inputs stacked with the outputs of all hidden units
+    fn features(&self, inputs: &Array2<f64>) -> Array2<f64> {
+        let mut features = inputs.clone();
+        for unit in &self.hidden {
+            features = concatenate![Axis(0), features, unit_value(unit, &features)];
+        }
+        features
+    }
+
+    fn output_value(&self, features: &Array2<f64>) -> Array2<f64> {
+        unit_value(&self.output, features)
+    }
+
+    fn train_outputs(&mut self, features: &Array2<f64>, targets: &Array2<f64>) -> f64 {
+        let samples: Vec<TrainingSample> = features
+            .columns()
+            .into_iter()
+            .zip(targets.columns())
+            .map(|(input, target)| TrainingSample {
+                input: input.to_owned().insert_axis(Axis(1)),
+                expected_output: target.to_owned().insert_axis(Axis(1)),
+            })
+            .collect();
+
+        let mut network = Network::from_layers(vec![Box::new(self.output.clone())]);
+        let mut rprop = Rprop::default();
+        for _ in 0..self.training.output_epochs {
+            let error = rprop.epoch(&mut network, &samples, 0.0) / samples.len() as f64;
+            if error <= self.training.target_error {
+                break;
+            }
+        }
+        let params = network.layers[0].params();
+        self.output.weights.assign(params[0]);
+        self.output.biases.assign(params[1]);
+
+        let errors = self.output_value(features) - targets;
+        errors.mapv(|e| e * e).sum() / samples.len() as f64
+    }
+
+    fn best_candidate(&mut self, features: &Array2<f64>, residuals: &Array2<f64>) -> FFLayer {
+        let centered = residuals - &residuals.mean_axis(Axis(1)).unwrap().insert_axis(Axis(1));
+        let activation = self.training.hidden_activation.function();
+        let mut best = None;
+        let mut best_score = f64::NEG_INFINITY;
+        for _ in 0..self.training.candidates.max(1) {
+            let mut unit =
+                FFLayer::new(features.nrows(), 1, InitializationMethods::Zero, activation);
+            unit.weights.mapv_inplace(|_| self.rng.gen_range(-1.0..1.0));
+            unit.biases.mapv_inplace(|_| self.rng.gen_range(-1.0..1.0));
+            let score = train_candidate(
+                &mut unit,
+                features,
+                &centered,
+                self.training.candidate_epochs,
+            );
+            if score > best_score || best.is_none() {
+                best = Some(unit);
+                best_score = score;
+            }
+        }
+        best.unwrap()
+    }
+}
+
+impl Predictor for CascadeCorrelation {
+    fn predict(&mut self, input: &Array2<f64>) -> Array2<f64> {
+        self.output_value(&self.features(input))
+    }
+}
+
+fn unit_value(unit: &FFLayer, features: &Array2<f64>) -> Array2<f64> {
+    unit.activation
+        .function(&(unit.weights.dot(features) + &unit.biases))
+}
+
+// Rprop ascent on S = Σ_o |Σ_p V_p (E_op - Ē_o)|, the summed magnitude of the
+// covariances between the unit's output V and the centered residuals of every output.
+// Returns the final S.
+fn train_candidate(
+    unit: &mut FFLayer,
+    features: &Array2<f64>,
+    centered: &Array2<f64>,
+    epochs: usize,
+) -> f64 {
+    let mut weight_steps = Array2::from_elem(unit.weights.raw_dim(), 0.1);
+    let mut bias_steps = Array2::from_elem(unit.biases.raw_dim(), 0.1);
+    let mut previous_weights = Array2::zeros(unit.weights.raw_dim());
+    let mut previous_biases = Array2::zeros(unit.biases.raw_dim());
+    let mut epoch = 0;
+    loop {
+        let z = unit.weights.dot(features) + &unit.biases;
+        let value = unit.activation.function(&z);
+        let covariances = centered.dot(&value.t());
+        let score = covariances.mapv(f64::abs).sum();
+        if epoch == epochs {
+            return score;
+        }
+        epoch += 1;
+
+        let signs = covariances.mapv(f64::signum);
+        let slope = unit.activation.derivative(&z, Some(&value)) * signs.t().dot(centered);
+        let weight_gradient = slope.dot(&features.t());
+        let bias_gradient = slope.sum_axis(Axis(1)).insert_axis(Axis(1));
+        ascend(
+            &mut unit.weights,
+            &weight_gradient,
+            &mut weight_steps,
+            &mut previous_weights,
+        );
+        ascend(
+            &mut unit.biases,
+            &bias_gradient,
+            &mut bias_steps,
+            &mut previous_biases,
+        );
+    }
+}
+
+// iRprop- step up the gradient
+fn ascend(
+    values: &mut Array2<f64>,
+    gradient: &Array2<f64>,
+    steps: &mut Array2<f64>,
+    previous: &mut Array2<f64>,
+) {
+    Zip::from(values)
+        .and(gradient)
+        .and(steps)
+        .and(previous)
+        .for_each(|value, &gradient, step, previous| {
+            let mut gradient = gradient;
+            if gradient * *previous > 0.0 {
+                *step = (*step * 1.2).min(50.0);
+            } else if gradient * *previous < 0.0 {
+                *step = (*step * 0.5).max(1e-6);
+                gradient = 0.0;
+            }
+            if gradient != 0.0 {
+                *value += *step * gradient.signum();
+            }
+            *previous = gradient;
+        });
+}
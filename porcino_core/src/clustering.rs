@@ -0,0 +1,131 @@
+//! Unsupervised grouping of samples
+use ndarray::{Array2, ArrayView1, Axis};
+use rand::prelude::*;
+
+/// Result of `kmeans`
+#[derive(Debug, Clone)]
+pub struct KMeans {
+    /// One center per row
+    pub centers: Array2<f64>,
+    /// Center every sample belongs to
+    pub assignments: Vec<usize>,
+}
+
+impl KMeans {
+    /// Samples assigned to every center
+    pub fn members(&self) -> Vec<Vec<usize>> {
+        let mut members = vec![Vec::new(); self.centers.nrows()];
+        for (sample, center) in self.assignments.iter().enumerate() {
+            members[*center].push(sample);
+        }
+        members
+    }
+}
+
+/// Lloyd's k-means of `samples`, one sample per column, seeded by k-means++.
+/// Stops when no sample changes its center or after `iterations` rounds. A center
+/// left without samples restarts at the sample farthest from its own center.
+pub fn kmeans(samples: &Array2<f64>, k: usize, iterations: usize, seed: u64) -> KMeans {
+    let count = samples.ncols();
+    assert!(
+        k > 0 && k <= count,
+        "k-means needs between 1 and {count} centers"
+    );
+    let mut rng = StdRng::seed_from_u64(seed);
+
+    let mut centers = Array2::zeros((k, samples.nrows()));
+    centers
+        .row_mut(0)
+        .assign(&samples.column(rng.gen_range(0..count)));
+    let mut distances: Vec<f64> = samples
+        .columns()
+        .into_iter()
+        .map(|x| squared_distance(x, centers.row(0)))
+        .collect();
+    for center in 1..k {
+        let total: f64 = distances.iter().sum();
+        let chosen = if total > 0.0 {
+            let mut target = rng.gen_range(0.0..total);
+            distances
+                .iter()
+                .position(|d| {
+                    target -= d;
+                    target < 0.0
+                })
+                .unwrap_or(count - 1)
+        } else {
+            rng.gen_range(0..count)
+        };
+        centers.row_mut(center).assign(&samples.column(chosen));
+        for (distance, x) in distances.iter_mut().zip(samples.columns()) {
+            *distance = distance.min(squared_distance(x, centers.row(center)));
+        }
+    }
+
+    let mut assignments = vec![usize::MAX; count];
+    for _ in 0..iterations.max(1) {
+        if !assign(samples, &centers, &mut assignments) {
+            break;
+        }
+
+        let mut sums = Array2::<f64>::zeros(centers.raw_dim());
+        let mut sizes = vec![0usize; k];
+        for (x, center) in samples.columns().into_iter().zip(assignments.iter()) {
+            let mut sum = sums.row_mut(*center);
+            sum += &x;
+            sizes[*center] += 1;
+        }
+        for (center, size) in sizes.into_iter().enumerate() {
+            if size > 0 {
+                centers
+                    .row_mut(center)
+                    .assign(&(&sums.row(center) / size as f64));
+            } else {
+                let farthest = samples
+                    .columns()
+                    .into_iter()
+                    .zip(assignments.iter())
+                    .map(|(x, assigned)| squared_distance(x, centers.row(*assigned)))
+                    .enumerate()
+                    .max_by(|a, b| a.1.total_cmp(&b.1))
+                    .map_or(0, |(sample, _)| sample);
+                centers.row_mut(center).assign(&samples.column(farthest));
+                assignments[farthest] = center;
+            }
+        }
+    }
+
+    // Centers moved by the last round keep their samples up to date
+    assign(samples, &centers, &mut assignments);
+    KMeans {
+        centers,
+        assignments,
+    }
+}
+
+// Moves every sample to its nearest center, telling whether any of them moved
+fn assign(samples: &Array2<f64>, centers: &Array2<f64>, assignments: &mut [usize]) -> bool {
+    let mut changed = false;
+    for (sample, x) in samples.columns().into_iter().enumerate() {
+        let (center, _) = nearest(centers, x);
+        if assignments[sample] != center {
+            assignments[sample] = center;
+            changed = true;
+        }
+    }
+    changed
+}
+
+/// Row of `centers` closest to `x`, with its squared distance
+pub fn nearest(centers: &Array2<f64>, x: ArrayView1<f64>) -> (usize, f64) {
+    centers
+        .axis_iter(Axis(0))
+        .map(|center| squared_distance(x, center))
+        .enumerate()
+        .min_by(|a, b| a.1.total_cmp(&b.1))
+        .unwrap_or((0, f64::INFINITY))
+}
+
+pub(crate) fn squared_distance(a: ArrayView1<f64>, b: ArrayView1<f64>) -> f64 {
+    a.iter().zip(b.iter()).map(|(a, b)| (a - b) * (a - b)).sum()
+}
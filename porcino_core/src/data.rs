@@ -1,6 +1,9 @@
 use std::collections::HashMap;
 
-use ndarray::{Array, Array2};
+use ndarray::{concatenate, Array, Array2, Axis};
+use porcino_data::parse::TrainingSample;
+use porcino_data::sequence::SequenceSample;
+use rand::prelude::*;
 
 pub fn prepare_file(
     filename: &str,
@@ -87,3 +90,51 @@ pub fn prepare_file(
 
     (parsed, classes)
 }
+
+/// Shuffles the samples and moves `validation_fraction` of them into a separate set.
+/// Returns the training and validation sets, in that order.
+pub fn train_validation_split(
+    mut samples: Vec<TrainingSample>,
+    validation_fraction: f64,
+    seed: u64,
+) -> (Vec<TrainingSample>, Vec<TrainingSample>) {
+    samples.shuffle(&mut StdRng::seed_from_u64(seed));
+    let validation_len = (samples.len() as f64 * validation_fraction.clamp(0.0, 1.0)).round();
+    let validation = samples.split_off(samples.len() - validation_len as usize);
+    (samples, validation)
+}
+
+/// Joins the inputs and the expected outputs of the samples into matrices with one sample per column
+pub fn stack_samples(samples: &[TrainingSample]) -> (Array2<f64>, Array2<f64>) {
+    let inputs = samples.iter().map(|s| s.input.view()).collect::<Vec<_>>();
+    let outputs = samples
+        .iter()
+        .map(|s| s.expected_output.view())
+        .collect::<Vec<_>>();
+    (
+        concatenate(Axis(1), &inputs).unwrap(),
+        concatenate(Axis(1), &outputs).unwrap(),
+    )
+}
+
+/// Joins equally long sequences step by step, each step holding one sample per column.
+/// Returns the inputs and the expected outputs of every step.
+pub fn stack_sequences(samples: &[SequenceSample]) -> (Vec<Array2<f64>>, Vec<Array2<f64>>) {
+    let join = |steps: fn(&SequenceSample) -> &Vec<Array2<f64>>| {
+        let len = steps(&samples[0]).len();
+        assert!(
+            samples.iter().all(|s| steps(s).len() == len),
+            "sequences in a batch must have the same length"
+        );
+        (0..len)
+            .map(|step| {
+                let columns = samples
+                    .iter()
+                    .map(|s| steps(s)[step].view())
+                    .collect::<Vec<_>>();
+                concatenate(Axis(1), &columns).unwrap()
+            })
+            .collect::<Vec<_>>()
+    };
+    (join(|s| &s.inputs), join(|s| &s.expected_outputs))
+}
@@ -0,0 +1,113 @@
+use ndarray::{Array1, Array2, ArrayView1};
+
+/// How `Hopfield::store` turns patterns into weights
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LearningRule {
+    /// Sum of the outer products of the patterns
+    #[default]
+    Hebbian,
+    /// Incremental rule subtracting the local fields of the stored patterns, which
+    /// holds more patterns before they start to interfere
+    Storkey,
+}
+
+/// Auto-associative memory of bipolar patterns. Recall updates one unit at a time
+/// towards the sign of its local field, which never raises the energy, until the
+/// state settles in a stored pattern (or a spurious mixture of them).
+#[derive(Debug, Clone)]
+pub struct Hopfield {
+    /// Symmetric, with a zero diagonal
+    pub weights: Array2<f64>,
+    pub thresholds: Array1<f64>,
+}
+
+impl Hopfield {
+    pub fn new(units: usize) -> Self {
+        Self {
+            weights: Array2::zeros((units, units)),
+            thresholds: Array1::zeros(units),
+        }
+    }
+
+    pub fn units(&self) -> usize {
+        self.thresholds.len()
+    }
+
+    /// Adds the patterns, one per column, to the memory. Values are read as bipolar,
+    /// non-negative ones as +1 and negative ones as -1.
+    pub fn store(&mut self, patterns: &Array2<f64>, rule: LearningRule) {
+        let units = self.units();
+        assert_eq!(patterns.nrows(), units, "patterns need a row per unit");
+        let scale = 1.0 / units as f64;
+        for pattern in patterns.columns() {
+            let xi = bipolar(pattern);
+            match rule {
+                LearningRule::Hebbian => {
+                    for i in 0..units {
+                        for j in 0..units {
+                            if i != j {
+                                self.weights[[i, j]] += scale * xi[i] * xi[j];
+                            }
+                        }
+                    }
+                }
+                LearningRule::Storkey => {
+                    // Local fields of the memory before this pattern, h_ij leaving out i and j
+                    let fields = self.weights.dot(&xi);
+                    let h = |i: usize, j: usize| fields[i] - self.weights[[i, j]] * xi[j];
+                    let mut change = Array2::zeros((units, units));
+                    for i in 0..units {
+                        for j in 0..units {
+                            if i != j {
+                                change[[i, j]] =
+                                    scale * (xi[i] * xi[j] - xi[i] * h(j, i) - h(i, j) * xi[j]);
+                            }
+                        }
+                    }
+                    self.weights += &change;
+                }
+            }
+        }
+    }
+
+    /// `-½ sᵀ W s + θᵀ s` of a bipolar state
+    pub fn energy(&self, state: ArrayView1<f64>) -> f64 {
+        let state = bipolar(state);
+        -0.5 * state.dot(&self.weights.dot(&state)) + self.thresholds.dot(&state)
+    }
+
+    /// Settles every column of `inputs` by sweeping over the units in order, at most
+    /// `max_sweeps` times or until a sweep changes nothing
+    pub fn recall(&self, inputs: &Array2<f64>, max_sweeps: usize) -> Array2<f64> {
+        let mut states = inputs.clone();
+        for mut column in states.columns_mut() {
+            let mut state = bipolar(column.view());
+            for _ in 0..max_sweeps {
+                let mut changed = false;
+                for unit in 0..self.units() {
+                    let field = self.weights.row(unit).dot(&state) - self.thresholds[unit];
+                    let value = if field > 0.0 {
+                        1.0
+                    } else if field < 0.0 {
+                        -1.0
+                    } else {
+                        state[unit]
+                    };
+                    if value != state[unit] {
+                        state[unit] = value;
+                        changed = true;
+                    }
+                }
+                if !changed {
+                    break;
+                }
+            }
+            column.assign(&state);
+        }
+        states
+    }
+}
+
+fn bipolar(values: ArrayView1<f64>) -> Array1<f64> {
+    values.mapv(|v| if v < 0.0 { -1.0 } else { 1.0 })
+}
@@ -0,0 +1,6 @@
+//! Energy-based models, trained without backpropagation
+mod hopfield;
+mod rbm;
+
+pub use hopfield::{Hopfield, LearningRule};
+pub use rbm::{pretrain, Rbm, RbmTraining};
@@ -0,0 +1,177 @@
+use crate::data::stack_samples;
+use crate::enums::Mode;
+use crate::network::{Network, Node, NodeId};
+use crate::traits::ParamKind;
+use ndarray::{Array2, Axis};
+use porcino_data::parse::TrainingSample;
+use rand::prelude::*;
+
+/// How an `Rbm` is trained
+#[derive(Debug, Clone, Copy)]
+pub struct RbmTraining {
+    pub epochs: usize,
+    pub batch_size: usize,
+    pub learning_rate: f64,
+    /// Gibbs steps of contrastive divergence
+    pub k: usize,
+    pub seed: u64,
+}
+
+impl Default for RbmTraining {
+    fn default() -> Self {
+        Self {
+            epochs: 20,
+            batch_size: 10,
+            learning_rate: 0.1,
+            k: 1,
+            seed: 0,
+        }
+    }
+}
+
+/// Bernoulli restricted Boltzmann machine, visible and hidden units being binary with
+/// sigmoid probabilities. Inputs are expected between 0 and 1. Weights are laid out like
+/// the ones of an `FFLayer` from the visible to the hidden units, so that a trained
+/// machine can initialize one.
+#[derive(Debug, Clone)]
+pub struct Rbm {
+    /// One row per hidden unit
+    pub weights: Array2<f64>,
+    pub visible_biases: Array2<f64>,
+    pub hidden_biases: Array2<f64>,
+    rng: StdRng,
+}
+
+impl Rbm {
+    pub fn new(visible: usize, hidden: usize, seed: u64) -> Self {
+        let mut rng = StdRng::seed_from_u64(seed);
+        Self {
+            weights: Array2::from_shape_fn((hidden, visible), |_| rng.gen_range(-0.01..0.01)),
+            visible_biases: Array2::zeros((visible, 1)),
+            hidden_biases: Array2::zeros((hidden, 1)),
+            rng,
+        }
+    }
+
+    /// Probability of every hidden unit being on, one sample per column
+    pub fn hidden_probabilities(&self, visible: &Array2<f64>) -> Array2<f64> {
+        (self.weights.dot(visible) + &self.hidden_biases).mapv(sigmoid)
+    }
+
+    /// Probability of every visible unit being on, one sample per column
+    pub fn visible_probabilities(&self, hidden: &Array2<f64>) -> Array2<f64> {
+        (self.weights.t().dot(hidden) + &self.visible_biases).mapv(sigmoid)
+    }
+
+    /// Visible probabilities after one round trip through the hidden units
+    pub fn reconstruct(&self, visible: &Array2<f64>) -> Array2<f64> {
+        self.visible_probabilities(&self.hidden_probabilities(visible))
+    }
+
+    /// One CD-k update on a batch, returning its summed squared reconstruction error.
+    /// Hidden states are sampled, visible ones kept as probabilities.
+    pub fn contrastive_divergence(&mut self, batch: &Array2<f64>, k: usize, eta: f64) -> f64 {
+        let positive = self.hidden_probabilities(batch);
+        let mut hidden = positive.clone();
+        let mut visible = batch.clone();
+        for _ in 0..k.max(1) {
+            let sampled = self.sample(&hidden);
+            visible = self.visible_probabilities(&sampled);
+            hidden = self.hidden_probabilities(&visible);
+        }
+
+        let scale = eta / batch.ncols().max(1) as f64;
+        self.weights.scaled_add(
+            scale,
+            &(positive.dot(&batch.t()) - hidden.dot(&visible.t())),
+        );
+        self.visible_biases.scaled_add(
+            scale,
+            &(batch - &visible).sum_axis(Axis(1)).insert_axis(Axis(1)),
+        );
+        self.hidden_biases.scaled_add(
+            scale,
+            &(&positive - &hidden).sum_axis(Axis(1)).insert_axis(Axis(1)),
+        );
+        (batch - &visible).mapv(|e| e * e).sum()
+    }
+
+    /// Trains on `data`, one sample per column, in shuffled batches. Returns the summed
+    /// reconstruction error of the last epoch.
+    pub fn train(&mut self, data: &Array2<f64>, training: &RbmTraining) -> f64 {
+        let mut order: Vec<usize> = (0..data.ncols()).collect();
+        let mut shuffle = StdRng::seed_from_u64(training.seed);
+        let mut error = 0.0;
+        for _ in 0..training.epochs {
+            order.shuffle(&mut shuffle);
+            error = 0.0;
+            for batch in order.chunks(training.batch_size.max(1)) {
+                let batch = data.select(Axis(1), batch);
+                error += self.contrastive_divergence(&batch, training.k, training.learning_rate);
+            }
+        }
+        error
+    }
+
+    fn sample(&mut self, probabilities: &Array2<f64>) -> Array2<f64> {
+        probabilities.mapv(|p| if self.rng.gen::<f64>() < p { 1.0 } else { 0.0 })
+    }
+}
+
+/// Greedy layer-wise pretraining of a chain of layers. Every dense layer but the last
+/// one is initialized from an RBM trained on what reaches it, starting with the inputs
+/// of `data`, and then passes that on to the next. Other layers only pass their input
+/// on, as in inference. Returns the reconstruction error of every trained RBM.
+pub fn pretrain(
+    network: &mut Network,
+    data: &[TrainingSample],
+    training: &RbmTraining,
+) -> Vec<f64> {
+    // Layer i reads node i, the output of the layer before it
+    let chained = |(idx, node): (usize, &Node)| match node {
+        Node::Layer { layer, input } => *layer == idx && input.0 == idx,
+        _ => false,
+    };
+    let chain = network.outputs() == [NodeId(network.layers.len())]
+        && network.nodes().iter().skip(1).enumerate().all(chained);
+    assert!(chain, "greedy pretraining needs a chain of layers");
+    if data.is_empty() {
+        return Vec::new();
+    }
+
+    let dense: Vec<bool> = network
+        .layers
+        .iter()
+        .map(|layer| layer.is_dense())
+        .collect();
+    let last_dense = dense.iter().rposition(|dense| *dense);
+
+    let (mut values, _) = stack_samples(data);
+    let mut errors = Vec::new();
+    let mode = network.mode();
+    network.set_mode(Mode::Inference);
+    for (idx, layer) in network.layers.iter_mut().enumerate() {
+        if Some(idx) == last_dense {
+            break;
+        }
+        if dense[idx] {
+            let hidden = layer.params()[0].nrows();
+            let mut rbm = Rbm::new(values.nrows(), hidden, training.seed + idx as u64);
+            errors.push(rbm.train(&values, training));
+            for param in layer.params_mut() {
+                match param.kind {
+                    ParamKind::Weights => param.value.assign(&rbm.weights),
+                    ParamKind::Biases => param.value.assign(&rbm.hidden_biases),
+                    ParamKind::Other => {}
+                }
+            }
+        }
+        values = layer.feed_forward(&values).clone();
+    }
+    network.set_mode(mode);
+    errors
+}
+
+fn sigmoid(x: f64) -> f64 {
+    1.0 / (1.0 + (-x).exp())
+}
@@ -1,3 +1,6 @@
+use ndarray::Array2;
+use rand::prelude::*;
+
 #[derive(Copy, Clone)]
 pub enum InitializationMethods {
     Zero,
@@ -5,3 +8,30 @@ pub enum InitializationMethods {
     PseudoSpread,
     Random,
 }
+
+impl InitializationMethods {
+    /// Weights scaled to the number of inputs feeding each unit. `Random` draws
+    /// uniformly from ±1/√fan_in.
+    pub(crate) fn scaled(&self, shape: (usize, usize), fan_in: usize) -> Array2<f64> {
+        let bound = 1.0 / (fan_in.max(1) as f64).sqrt();
+        match self {
+            InitializationMethods::Zero => Array2::zeros(shape),
+            InitializationMethods::One => Array2::ones(shape),
+            InitializationMethods::PseudoSpread => Array2::from_shape_fn(shape, |(i, j)| {
+                ((i as f64 + 1.0) * (j as f64 + 2.0)).sin() * bound
+            }),
+            InitializationMethods::Random => {
+                let mut rng = StdRng::from_entropy();
+                Array2::from_shape_fn(shape, |_| rng.gen_range(-bound..=bound))
+            }
+        }
+    }
+}
+
+/// Layers such as dropout behave differently while training
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub enum Mode {
+    #[default]
+    Train,
+    Inference,
+}
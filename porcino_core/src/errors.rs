@@ -1,4 +1,7 @@
+use crate::autodiff::{loss_gradient, Tape, Var};
 use crate::traits::ErrorFn;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
 
 pub struct Sse;
 impl ErrorFn for Sse {
@@ -15,3 +18,59 @@ impl ErrorFn for Sse {
             .sum::<f64>()
     }
 }
+
+/// Loss of a single output head
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Loss {
+    #[default]
+    Sse,
+    /// Binary cross-entropy of every output unit, meant for sigmoid outputs
+    /// and targets between 0 and 1
+    CrossEntropy,
+}
+
+impl Loss {
+    const EPS: f64 = 1e-12;
+
+    /// Records the loss of `output` against `reference` on a tape as a 1x1 value
+    pub fn record(&self, tape: &mut Tape, output: Var, reference: Var) -> Var {
+        match self {
+            Loss::Sse => {
+                let error = tape.sub(output, reference);
+                let squared = tape.square(error);
+                tape.sum(squared)
+            }
+            Loss::CrossEntropy => {
+                let p = tape.clamp(output, Self::EPS, 1.0 - Self::EPS);
+                let ln_p = tape.ln(p);
+                let hits = tape.mul(reference, ln_p);
+
+                let not_p = tape.neg(p);
+                let not_p = tape.offset(not_p, 1.0);
+                let ln_not_p = tape.ln(not_p);
+                let not_reference = tape.neg(reference);
+                let not_reference = tape.offset(not_reference, 1.0);
+                let misses = tape.mul(not_reference, ln_not_p);
+
+                let likelihood = tape.add(hits, misses);
+                let likelihood = tape.sum(likelihood);
+                tape.neg(likelihood)
+            }
+        }
+    }
+
+    pub fn value(&self, output: &Array2<f64>, reference: &Array2<f64>) -> f64 {
+        self.evaluate(output, reference).0
+    }
+
+    /// Gradient of half the loss with respect to the output. Halving follows the backward
+    /// pass, where the SSE gradient is `output - reference`, and keeps head weights comparable.
+    pub fn gradient(&self, output: &Array2<f64>, reference: &Array2<f64>) -> Array2<f64> {
+        self.evaluate(output, reference).1
+    }
+
+    /// Value and gradient of half the loss at once
+    pub fn evaluate(&self, output: &Array2<f64>, reference: &Array2<f64>) -> (f64, Array2<f64>) {
+        loss_gradient(|tape, o, r| self.record(tape, o, r), output, reference)
+    }
+}
@@ -0,0 +1,160 @@
+//! Compares the gradients computed by backpropagation with central finite differences.
+//! Layers that draw random numbers while training, like dropout, need to be put into
+//! inference mode first, so that every forward pass computes the same function.
+use crate::network::Network;
+use crate::recurrent::SequenceNetwork;
+use ndarray::Array2;
+use porcino_data::parse::TrainingSample;
+use porcino_data::sequence::SequenceSample;
+
+/// Gradients closer to zero than this are compared by their absolute difference
+const RELATIVE_FLOOR: f64 = 1e-4;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LayerCheck {
+    pub max_relative_error: f64,
+    pub max_absolute_error: f64,
+    /// Number of parameter entries compared
+    pub checked: usize,
+}
+
+/// Errors of every layer, in the order the network holds them. For sequence networks
+/// the recurrent layers come first, followed by the layers of the head.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GradientCheck {
+    pub layers: Vec<LayerCheck>,
+}
+
+impl GradientCheck {
+    pub fn max_relative_error(&self) -> f64 {
+        self.layers
+            .iter()
+            .map(|layer| layer.max_relative_error)
+            .fold(0.0, f64::max)
+    }
+
+    pub fn passed(&self, tolerance: f64) -> bool {
+        self.max_relative_error() <= tolerance
+    }
+}
+
+/// Checks the gradient of the network's loss over the batch, with every parameter
+/// moved by `epsilon` in both directions
+pub fn check_gradients(
+    network: &mut Network,
+    samples: &[TrainingSample],
+    epsilon: f64,
+) -> GradientCheck {
+    compare(network, samples, epsilon)
+}
+
+/// Same as `check_gradients`, through time for a network of recurrent layers. Truncated
+/// backpropagation only matches the full gradient without a truncation.
+pub fn check_sequence_gradients(
+    network: &mut SequenceNetwork,
+    samples: &[SequenceSample],
+    epsilon: f64,
+) -> GradientCheck {
+    compare(network, samples, epsilon)
+}
+
+// What the check needs to know about a network
+trait Checked {
+    type Sample;
+
+    /// Accumulated gradients of every parameter of every layer
+    fn analytic(&mut self, samples: &[Self::Sample]) -> Vec<Vec<Array2<f64>>>;
+
+    /// Loss whose gradient backpropagation computes. The backward pass works
+    /// with the gradient of half the loss.
+    fn half_loss(&mut self, samples: &[Self::Sample]) -> f64;
+
+    fn nudge(&mut self, layer: usize, param: usize, index: (usize, usize), by: f64);
+}
+
+impl Checked for Network {
+    type Sample = TrainingSample;
+
+    fn analytic(&mut self, samples: &[TrainingSample]) -> Vec<Vec<Array2<f64>>> {
+        self.accumulate_gradient(samples);
+        self.layers
+            .iter()
+            .map(|layer| layer.grads().into_iter().cloned().collect())
+            .collect()
+    }
+
+    fn half_loss(&mut self, samples: &[TrainingSample]) -> f64 {
+        let (inputs, references) = crate::data::stack_samples(samples);
+        self.process_data(&inputs);
+        self.loss(&references) / 2.0
+    }
+
+    fn nudge(&mut self, layer: usize, param: usize, index: (usize, usize), by: f64) {
+        self.layers[layer].params_mut()[param].value[index] += by;
+    }
+}
+
+impl Checked for SequenceNetwork {
+    type Sample = SequenceSample;
+
+    fn analytic(&mut self, samples: &[SequenceSample]) -> Vec<Vec<Array2<f64>>> {
+        self.accumulate_gradient(samples);
+        let recurrent = self
+            .recurrent
+            .iter()
+            .map(|layer| layer.grads().into_iter().cloned().collect());
+        let head = self
+            .head
+            .layers
+            .iter()
+            .map(|layer| layer.grads().into_iter().cloned().collect());
+        recurrent.chain(head).collect()
+    }
+
+    fn half_loss(&mut self, samples: &[SequenceSample]) -> f64 {
+        (self.accumulate_gradient(samples) - self.head.regularization_loss()) / 2.0
+    }
+
+    fn nudge(&mut self, layer: usize, param: usize, index: (usize, usize), by: f64) {
+        let recurrent = self.recurrent.len();
+        let mut params = if layer < recurrent {
+            self.recurrent[layer].params_mut()
+        } else {
+            self.head.layers[layer - recurrent].params_mut()
+        };
+        params[param].value[index] += by;
+    }
+}
+
+fn compare<N: Checked>(network: &mut N, samples: &[N::Sample], epsilon: f64) -> GradientCheck {
+    let analytic = network.analytic(samples);
+    let layers = analytic
+        .iter()
+        .enumerate()
+        .map(|(layer, grads)| {
+            let mut check = LayerCheck {
+                max_relative_error: 0.0,
+                max_absolute_error: 0.0,
+                checked: 0,
+            };
+            for (param, grad) in grads.iter().enumerate() {
+                for (index, expected) in grad.indexed_iter() {
+                    network.nudge(layer, param, index, epsilon);
+                    let above = network.half_loss(samples);
+                    network.nudge(layer, param, index, -2.0 * epsilon);
+                    let below = network.half_loss(samples);
+                    network.nudge(layer, param, index, epsilon);
+
+                    let numeric = (above - below) / (2.0 * epsilon);
+                    let error = (numeric - expected).abs();
+                    let scale = numeric.abs().max(expected.abs()).max(RELATIVE_FLOOR);
+                    check.max_absolute_error = check.max_absolute_error.max(error);
+                    check.max_relative_error = check.max_relative_error.max(error / scale);
+                    check.checked += 1;
+                }
+            }
+            check
+        })
+        .collect();
+    GradientCheck { layers }
+}
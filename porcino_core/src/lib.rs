@@ -1,5 +1,17 @@
+pub mod autodiff;
+pub mod cascade;
+pub mod clustering;
 pub mod data;
+pub mod energy;
 pub mod enums;
 pub mod errors;
+pub mod gradcheck;
+mod linalg;
+pub mod metrics;
 pub mod network;
+pub mod parzen;
+pub mod persistence;
+pub mod recurrent;
+pub mod som;
+pub mod training;
 pub mod traits;
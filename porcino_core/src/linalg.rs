@@ -0,0 +1,51 @@
+//! Dense solvers for the trainers that solve for their weights
+use ndarray::Array2;
+
+/// Solves `a x = b` for a symmetric positive definite `a` through its Cholesky
+/// decomposition, for every column of `b`. `None` when `a` is not positive definite.
+pub(crate) fn solve_spd(a: &Array2<f64>, b: &Array2<f64>) -> Option<Array2<f64>> {
+    let n = a.nrows();
+    assert_eq!(a.ncols(), n, "system matrix must be square");
+    assert_eq!(b.nrows(), n, "right hand side must have a row per unknown");
+
+    // a = l lᵀ
+    let mut l = Array2::<f64>::zeros((n, n));
+    for j in 0..n {
+        let mut diagonal = a[[j, j]];
+        for k in 0..j {
+            diagonal -= l[[j, k]] * l[[j, k]];
+        }
+        if diagonal <= 0.0 || !diagonal.is_finite() {
+            return None;
+        }
+        let pivot = diagonal.sqrt();
+        l[[j, j]] = pivot;
+        for i in j + 1..n {
+            let mut value = a[[i, j]];
+            for k in 0..j {
+                value -= l[[i, k]] * l[[j, k]];
+            }
+            l[[i, j]] = value / pivot;
+        }
+    }
+
+    // l y = b, then lᵀ x = y
+    let mut x = b.clone();
+    for mut column in x.columns_mut() {
+        for i in 0..n {
+            let mut value = column[i];
+            for k in 0..i {
+                value -= l[[i, k]] * column[k];
+            }
+            column[i] = value / l[[i, i]];
+        }
+        for i in (0..n).rev() {
+            let mut value = column[i];
+            for k in i + 1..n {
+                value -= l[[k, i]] * column[k];
+            }
+            column[i] = value / l[[i, i]];
+        }
+    }
+    Some(x)
+}
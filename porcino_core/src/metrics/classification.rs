@@ -0,0 +1,280 @@
+use ndarray::Array2;
+use porcino_data::parse::TrainingSample;
+
+// Probabilities are clipped to this range before taking logarithms
+const EPS: f64 = 1e-15;
+
+/// Rows are the actual classes, columns are the predicted ones.
+#[derive(Debug, Clone)]
+pub struct ConfusionMatrix {
+    pub counts: Array2<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ClassScores {
+    pub precision: f64,
+    pub recall: f64,
+    pub f1: f64,
+    pub support: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct ClassificationReport {
+    pub confusion: ConfusionMatrix,
+    pub accuracy: f64,
+    pub kappa: f64,
+    pub per_class: Vec<ClassScores>,
+    pub macro_avg: ClassScores,
+    pub micro_avg: ClassScores,
+    /// Only available when the outputs can be read as class probabilities
+    pub log_loss: Option<f64>,
+    /// One-vs-rest area under the ROC curve of every class, `None` for classes
+    /// missing either positive or negative samples
+    pub roc_auc: Vec<Option<f64>>,
+    pub macro_roc_auc: Option<f64>,
+}
+
+impl ConfusionMatrix {
+    pub fn new(classes: usize) -> Self {
+        Self {
+            counts: Array2::zeros((classes, classes)),
+        }
+    }
+
+    pub fn add(&mut self, actual: usize, predicted: usize) {
+        self.counts[(actual, predicted)] += 1;
+    }
+
+    pub fn classes(&self) -> usize {
+        self.counts.nrows()
+    }
+
+    pub fn total(&self) -> usize {
+        self.counts.sum()
+    }
+
+    pub fn correct(&self) -> usize {
+        self.counts.diag().sum()
+    }
+
+    pub fn accuracy(&self) -> f64 {
+        ratio(self.correct(), self.total())
+    }
+
+    pub fn true_positives(&self, class: usize) -> usize {
+        self.counts[(class, class)]
+    }
+
+    pub fn false_positives(&self, class: usize) -> usize {
+        self.counts.column(class).sum() - self.true_positives(class)
+    }
+
+    pub fn false_negatives(&self, class: usize) -> usize {
+        self.counts.row(class).sum() - self.true_positives(class)
+    }
+
+    pub fn scores(&self, class: usize) -> ClassScores {
+        scores_from_counts(
+            self.true_positives(class),
+            self.false_positives(class),
+            self.false_negatives(class),
+        )
+    }
+
+    pub fn kappa(&self) -> f64 {
+        let total = self.total() as f64;
+        if total == 0.0 {
+            return 0.0;
+        }
+        let observed = self.accuracy();
+        let expected = (0..self.classes())
+            .map(|c| self.counts.row(c).sum() as f64 * self.counts.column(c).sum() as f64)
+            .sum::<f64>()
+            / (total * total);
+        if expected == 1.0 {
+            // Every sample belongs to, and was predicted as, a single class
+            return if observed == 1.0 { 1.0 } else { 0.0 };
+        }
+        (observed - expected) / (1.0 - expected)
+    }
+}
+
+impl ClassificationReport {
+    /// Builds the report from network outputs paired with the samples that produced them.
+    ///
+    /// Outputs with more than one row are treated as one-hot (argmax) encodings.
+    /// Single row outputs hold the label index, as produced by `ClassType::Label`.
+    pub fn new(outputs: &[Array2<f64>], samples: &[TrainingSample]) -> Self {
+        assert_eq!(outputs.len(), samples.len());
+        let classes = class_count(samples);
+
+        let mut confusion = ConfusionMatrix::new(classes);
+        let probabilities = outputs
+            .iter()
+            .map(|output| probabilities(output, classes))
+            .collect::<Option<Vec<_>>>();
+        let actual = samples
+            .iter()
+            .map(|sample| decode(&sample.expected_output, classes))
+            .collect::<Vec<_>>();
+        outputs
+            .iter()
+            .zip(actual.iter())
+            .for_each(|(output, actual)| confusion.add(*actual, decode(output, classes)));
+
+        let per_class = (0..classes)
+            .map(|c| confusion.scores(c))
+            .collect::<Vec<_>>();
+        let macro_avg = ClassScores {
+            precision: mean(per_class.iter().map(|s| s.precision)),
+            recall: mean(per_class.iter().map(|s| s.recall)),
+            f1: mean(per_class.iter().map(|s| s.f1)),
+            support: confusion.total(),
+        };
+        let micro_avg = scores_from_counts(
+            (0..classes).map(|c| confusion.true_positives(c)).sum(),
+            (0..classes).map(|c| confusion.false_positives(c)).sum(),
+            (0..classes).map(|c| confusion.false_negatives(c)).sum(),
+        );
+
+        let log_loss = probabilities.as_ref().map(|probabilities| {
+            -mean(
+                probabilities
+                    .iter()
+                    .zip(actual.iter())
+                    .map(|(p, actual)| p[*actual].clamp(EPS, 1.0).ln()),
+            )
+        });
+        let roc_auc = match &probabilities {
+            Some(probabilities) => (0..classes)
+                .map(|c| {
+                    roc_auc(
+                        &probabilities.iter().map(|p| p[c]).collect::<Vec<_>>(),
+                        &actual.iter().map(|a| *a == c).collect::<Vec<_>>(),
+                    )
+                })
+                .collect(),
+            None => vec![None; classes],
+        };
+        let macro_roc_auc = if roc_auc.iter().any(Option::is_some) {
+            Some(mean(roc_auc.iter().flatten().copied()))
+        } else {
+            None
+        };
+
+        Self {
+            accuracy: confusion.accuracy(),
+            kappa: confusion.kappa(),
+            confusion,
+            per_class,
+            macro_avg,
+            micro_avg,
+            log_loss,
+            roc_auc,
+            macro_roc_auc,
+        }
+    }
+}
+
+fn class_count(samples: &[TrainingSample]) -> usize {
+    match samples.first() {
+        Some(sample) if sample.expected_output.len() > 1 => sample.expected_output.len(),
+        _ => samples
+            .iter()
+            .map(|sample| sample.expected_output.iter().next().copied().unwrap_or(0.0))
+            .map(|v| v.round().max(0.0) as usize + 1)
+            .max()
+            .unwrap_or(0)
+            .max(2),
+    }
+}
+
+fn decode(vector: &Array2<f64>, classes: usize) -> usize {
+    if vector.len() > 1 {
+        vector
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(index, _)| index)
+            .unwrap_or(0)
+    } else {
+        let value = vector.iter().next().copied().unwrap_or(0.0);
+        (value.round().max(0.0) as usize).min(classes - 1)
+    }
+}
+
+// Reads the output as a probability distribution over the classes.
+// A single output can only be interpreted this way for binary problems.
+fn probabilities(output: &Array2<f64>, classes: usize) -> Option<Vec<f64>> {
+    if output.len() > 1 {
+        let clipped = output.iter().map(|v| v.clamp(EPS, 1.0)).collect::<Vec<_>>();
+        let sum = clipped.iter().sum::<f64>();
+        Some(clipped.iter().map(|v| v / sum).collect())
+    } else if classes == 2 {
+        let positive = output.iter().next().copied()?.clamp(EPS, 1.0 - EPS);
+        Some(vec![1.0 - positive, positive])
+    } else {
+        None
+    }
+}
+
+// Mann-Whitney formulation, tied scores share their average rank
+fn roc_auc(scores: &[f64], positive: &[bool]) -> Option<f64> {
+    let positives = positive.iter().filter(|p| **p).count();
+    let negatives = positive.len() - positives;
+    if positives == 0 || negatives == 0 {
+        return None;
+    }
+
+    let mut order = (0..scores.len()).collect::<Vec<_>>();
+    order.sort_by(|a, b| scores[*a].total_cmp(&scores[*b]));
+
+    let mut positive_rank_sum = 0.0;
+    let mut start = 0;
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && scores[order[end + 1]] == scores[order[start]] {
+            end += 1;
+        }
+        let rank = (start + end) as f64 / 2.0 + 1.0;
+        positive_rank_sum +=
+            rank * order[start..=end].iter().filter(|i| positive[**i]).count() as f64;
+        start = end + 1;
+    }
+
+    let positives = positives as f64;
+    Some((positive_rank_sum - positives * (positives + 1.0) / 2.0) / (positives * negatives as f64))
+}
+
+fn scores_from_counts(tp: usize, fp: usize, fn_: usize) -> ClassScores {
+    let precision = ratio(tp, tp + fp);
+    let recall = ratio(tp, tp + fn_);
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+    ClassScores {
+        precision,
+        recall,
+        f1,
+        support: tp + fn_,
+    }
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        0.0
+    } else {
+        numerator as f64 / denominator as f64
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> f64 {
+    let (sum, count) = values.fold((0.0, 0usize), |(sum, count), v| (sum + v, count + 1));
+    if count == 0 {
+        0.0
+    } else {
+        sum / count as f64
+    }
+}
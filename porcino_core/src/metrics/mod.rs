@@ -0,0 +1,80 @@
+mod classification;
+mod regression;
+
+pub use classification::{ClassScores, ClassificationReport, ConfusionMatrix};
+pub use regression::{RegressionReport, ResidualStats, TargetTransform};
+
+use crate::traits::Predictor;
+use ndarray::Array2;
+use porcino_data::parse::{ClassType, Metadata, TrainingSample};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Task {
+    #[default]
+    Classification,
+    Regression,
+}
+
+#[derive(Debug, Clone)]
+pub enum EvaluationReport {
+    Classification(ClassificationReport),
+    Regression(RegressionReport),
+}
+
+/// Picks the set of metrics matching the dataset's output columns.
+#[derive(Debug, Clone, Default)]
+pub struct Evaluator {
+    pub task: Task,
+    pub transform: TargetTransform,
+}
+
+impl Task {
+    /// Datasets with a single label output are classification problems. Numeric
+    /// outputs make the dataset a regression one, and so do several label columns,
+    /// whose indices can't be read as one one-hot encoding.
+    pub fn from_metadata(meta: &Metadata) -> Self {
+        match meta.class_types.as_slice() {
+            [] | [ClassType::Label] => Task::Classification,
+            _ => Task::Regression,
+        }
+    }
+}
+
+impl Evaluator {
+    pub fn from_metadata(meta: &Metadata) -> Self {
+        Self {
+            task: Task::from_metadata(meta),
+            transform: TargetTransform::from_metadata(meta),
+        }
+    }
+
+    pub fn evaluate(
+        &self,
+        outputs: &[Array2<f64>],
+        samples: &[TrainingSample],
+    ) -> EvaluationReport {
+        match self.task {
+            Task::Classification => {
+                EvaluationReport::Classification(ClassificationReport::new(outputs, samples))
+            }
+            Task::Regression => EvaluationReport::Regression(RegressionReport::new(
+                outputs,
+                samples,
+                &self.transform,
+            )),
+        }
+    }
+
+    /// Runs every sample through `model` and evaluates the outputs
+    pub fn report(
+        &self,
+        model: &mut dyn Predictor,
+        samples: &[TrainingSample],
+    ) -> EvaluationReport {
+        let outputs = samples
+            .iter()
+            .map(|sample| model.predict(&sample.input))
+            .collect::<Vec<_>>();
+        self.evaluate(&outputs, samples)
+    }
+}
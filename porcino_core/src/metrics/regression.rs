@@ -0,0 +1,160 @@
+use ndarray::Array2;
+use porcino_data::parse::{Metadata, Scaling, TrainingSample};
+
+// Targets closer to zero than this are left out of the percentage error
+const EPS: f64 = 1e-12;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResidualStats {
+    pub mean: f64,
+    pub std_dev: f64,
+    pub min: f64,
+    pub max: f64,
+    pub median: f64,
+}
+
+#[derive(Debug, Clone)]
+pub struct RegressionReport {
+    pub mae: f64,
+    pub rmse: f64,
+    /// Coefficient of determination, averaged over the output columns
+    pub r2: f64,
+    /// In percent, `None` when every target is zero
+    pub mape: Option<f64>,
+    pub residuals: ResidualStats,
+}
+
+/// Maps normalized network outputs and targets back to the original units of the data.
+#[derive(Debug, Clone, Default)]
+pub struct TargetTransform {
+    pub scaling: Vec<Option<Scaling>>,
+}
+
+impl TargetTransform {
+    pub fn from_metadata(meta: &Metadata) -> Self {
+        Self {
+            scaling: meta.class_scaling.clone(),
+        }
+    }
+
+    pub fn inverse(&self, values: &Array2<f64>) -> Array2<f64> {
+        Array2::from_shape_fn(values.raw_dim(), |(row, col)| {
+            match self.scaling.get(row).copied().flatten() {
+                Some(scaling) => scaling.invert(values[(row, col)]),
+                None => values[(row, col)],
+            }
+        })
+    }
+}
+
+impl RegressionReport {
+    /// Builds the report from network outputs paired with the samples that produced them,
+    /// both expressed in original target units through `transform`.
+    pub fn new(
+        outputs: &[Array2<f64>],
+        samples: &[TrainingSample],
+        transform: &TargetTransform,
+    ) -> Self {
+        assert_eq!(outputs.len(), samples.len());
+        let predicted = outputs
+            .iter()
+            .map(|output| transform.inverse(output))
+            .collect::<Vec<_>>();
+        let actual = samples
+            .iter()
+            .map(|sample| transform.inverse(&sample.expected_output))
+            .collect::<Vec<_>>();
+
+        // Residuals are actual - predicted, in sample order
+        let pairs = predicted
+            .iter()
+            .zip(actual.iter())
+            .flat_map(|(p, a)| p.iter().copied().zip(a.iter().copied()))
+            .collect::<Vec<(f64, f64)>>();
+        let residuals = pairs.iter().map(|(p, a)| a - p).collect::<Vec<_>>();
+        let count = residuals.len().max(1) as f64;
+
+        let mae = residuals.iter().map(|r| r.abs()).sum::<f64>() / count;
+        let rmse = (residuals.iter().map(|r| r * r).sum::<f64>() / count).sqrt();
+
+        let percentages = pairs
+            .iter()
+            .filter(|(_, a)| a.abs() > EPS)
+            .map(|(p, a)| ((a - p) / a).abs())
+            .collect::<Vec<_>>();
+        let mape = if percentages.is_empty() {
+            None
+        } else {
+            Some(100.0 * percentages.iter().sum::<f64>() / percentages.len() as f64)
+        };
+
+        Self {
+            mae,
+            rmse,
+            r2: r_squared(&predicted, &actual),
+            mape,
+            residuals: ResidualStats::new(&residuals),
+        }
+    }
+}
+
+impl ResidualStats {
+    pub fn new(residuals: &[f64]) -> Self {
+        if residuals.is_empty() {
+            return Self::default();
+        }
+        let count = residuals.len() as f64;
+        let mean = residuals.iter().sum::<f64>() / count;
+        let std_dev = (residuals.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / count).sqrt();
+
+        let mut sorted = residuals.to_vec();
+        sorted.sort_by(|a, b| a.total_cmp(b));
+        let middle = sorted.len() / 2;
+        let median = if sorted.len().is_multiple_of(2) {
+            (sorted[middle - 1] + sorted[middle]) / 2.0
+        } else {
+            sorted[middle]
+        };
+
+        Self {
+            mean,
+            std_dev,
+            min: sorted[0],
+            max: sorted[sorted.len() - 1],
+            median,
+        }
+    }
+}
+
+fn r_squared(predicted: &[Array2<f64>], actual: &[Array2<f64>]) -> f64 {
+    let outputs = match actual.first() {
+        Some(first) => first.len(),
+        None => return 0.0,
+    };
+    let count = actual.len() as f64;
+
+    (0..outputs)
+        .map(|o| {
+            let mean = actual.iter().map(|a| a[(o, 0)]).sum::<f64>() / count;
+            let total = actual
+                .iter()
+                .map(|a| (a[(o, 0)] - mean).powi(2))
+                .sum::<f64>();
+            let residual = predicted
+                .iter()
+                .zip(actual.iter())
+                .map(|(p, a)| (a[(o, 0)] - p[(o, 0)]).powi(2))
+                .sum::<f64>();
+            if total == 0.0 {
+                if residual == 0.0 {
+                    1.0
+                } else {
+                    0.0
+                }
+            } else {
+                1.0 - residual / total
+            }
+        })
+        .sum::<f64>()
+        / outputs as f64
+}
@@ -1,9 +1,12 @@
 use ndarray::Array2;
 
+use super::Activations;
+use crate::autodiff::{Tape, Var};
 use crate::traits::Activation;
 
 pub struct Sigmoid;
 pub struct Linear;
+pub struct Tanh;
 impl Activation for Sigmoid {
     fn function(&self, z: &Array2<f64>) -> Array2<f64> {
         Array2::from_shape_vec(
@@ -21,6 +24,14 @@ impl Activation for Sigmoid {
             &val * (1.0 - &val)
         }
     }
+
+    fn kind(&self) -> Activations {
+        Activations::Sigmoid
+    }
+
+    fn record(&self, tape: &mut Tape, z: Var) -> Var {
+        tape.sigmoid(z)
+    }
 }
 impl Activation for Linear {
     fn function(&self, z: &Array2<f64>) -> Array2<f64> {
@@ -30,4 +41,32 @@ impl Activation for Linear {
     fn derivative(&self, z: &Array2<f64>, _: Option<&Array2<f64>>) -> Array2<f64> {
         Array2::from_shape_fn(z.raw_dim(), |_| 1.)
     }
+
+    fn kind(&self) -> Activations {
+        Activations::Linear
+    }
+
+    fn record(&self, _tape: &mut Tape, z: Var) -> Var {
+        z
+    }
+}
+impl Activation for Tanh {
+    fn function(&self, z: &Array2<f64>) -> Array2<f64> {
+        z.mapv(f64::tanh)
+    }
+
+    fn derivative(&self, z: &Array2<f64>, val: Option<&Array2<f64>>) -> Array2<f64> {
+        match val {
+            Some(val) => val.mapv(|v| 1.0 - v * v),
+            None => z.mapv(|v| 1.0 - v.tanh().powi(2)),
+        }
+    }
+
+    fn kind(&self) -> Activations {
+        Activations::Tanh
+    }
+
+    fn record(&self, tape: &mut Tape, z: Var) -> Var {
+        tape.tanh(z)
+    }
 }
@@ -0,0 +1,151 @@
+use crate::enums::Mode;
+use crate::persistence::SavedLayer;
+use crate::traits::{Layer, Param, ParamKind};
+use ndarray::{Array2, Axis};
+
+/// Normalizes every feature over the batch (the columns of the input), then scales
+/// and shifts it by the learnable `gamma` and `beta`. Inference uses running estimates
+/// of the mean and variance gathered while training.
+#[derive(Debug, Clone)]
+pub struct BatchNorm {
+    pub gamma: Array2<f64>,
+    pub beta: Array2<f64>,
+    pub running_mean: Array2<f64>,
+    pub running_var: Array2<f64>,
+    /// Weight of the newest batch in the running statistics
+    pub momentum: f64,
+    pub eps: f64,
+    nabla_gamma: Array2<f64>,
+    nabla_beta: Array2<f64>,
+    mode: Mode,
+    normalized: Array2<f64>,
+    inv_std: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl BatchNorm {
+    pub fn new(features: usize) -> Self {
+        Self {
+            gamma: Array2::ones((features, 1)),
+            beta: Array2::zeros((features, 1)),
+            running_mean: Array2::zeros((features, 1)),
+            running_var: Array2::ones((features, 1)),
+            momentum: 0.1,
+            eps: 1e-5,
+            nabla_gamma: Array2::zeros((features, 1)),
+            nabla_beta: Array2::zeros((features, 1)),
+            mode: Mode::Train,
+            normalized: Array2::zeros((features, 1)),
+            inv_std: Array2::ones((features, 1)),
+            state: Array2::zeros((features, 1)),
+        }
+    }
+}
+
+impl Layer for BatchNorm {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        let (mean, var) = match self.mode {
+            Mode::Train => {
+                let batch = input.ncols() as f64;
+                let mean = input.mean_axis(Axis(1)).unwrap().insert_axis(Axis(1));
+                let var = (input - &mean)
+                    .mapv(|v| v * v)
+                    .mean_axis(Axis(1))
+                    .unwrap()
+                    .insert_axis(Axis(1));
+
+                // Running variance is kept unbiased
+                let unbiased = if batch > 1.0 {
+                    &var * (batch / (batch - 1.0))
+                } else {
+                    var.clone()
+                };
+                self.running_mean =
+                    &self.running_mean * (1.0 - self.momentum) + &mean * self.momentum;
+                self.running_var =
+                    &self.running_var * (1.0 - self.momentum) + &unbiased * self.momentum;
+                (mean, var)
+            }
+            Mode::Inference => (self.running_mean.clone(), self.running_var.clone()),
+        };
+
+        self.inv_std = var.mapv(|v| 1.0 / (v + self.eps).sqrt());
+        self.normalized = &(input - &mean) * &self.inv_std;
+        self.state = &(&self.normalized * &self.gamma) + &self.beta;
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        self.nabla_gamma += &(delta * &self.normalized)
+            .sum_axis(Axis(1))
+            .insert_axis(Axis(1));
+        self.nabla_beta += &delta.sum_axis(Axis(1)).insert_axis(Axis(1));
+
+        let d_normalized = delta * &self.gamma;
+        match self.mode {
+            // Statistics are constants in inference
+            Mode::Inference => d_normalized * &self.inv_std,
+            // Gradient also flows through the batch mean and variance
+            Mode::Train => {
+                let batch = delta.ncols() as f64;
+                let sum = d_normalized.sum_axis(Axis(1)).insert_axis(Axis(1));
+                let dot = (&d_normalized * &self.normalized)
+                    .sum_axis(Axis(1))
+                    .insert_axis(Axis(1));
+                let centered = &(&d_normalized * batch) - &sum - &(&self.normalized * &dot);
+                &centered * &(&self.inv_std / batch)
+            }
+        }
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.gamma, &self.beta]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_gamma, &self.nabla_beta]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Other,
+                value: &mut self.gamma,
+                grad: &self.nabla_gamma,
+            },
+            Param {
+                kind: ParamKind::Other,
+                value: &mut self.beta,
+                grad: &self.nabla_beta,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_gamma.fill(0.0);
+        self.nabla_beta.fill(0.0);
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::BatchNorm {
+            gamma: self.gamma.clone(),
+            beta: self.beta.clone(),
+            running_mean: self.running_mean.clone(),
+            running_var: self.running_var.clone(),
+            momentum: self.momentum,
+            eps: self.eps,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
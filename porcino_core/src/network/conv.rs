@@ -0,0 +1,408 @@
+use super::regularization::Regularization;
+use crate::persistence::SavedLayer;
+use crate::{
+    enums::InitializationMethods,
+    traits::{Activation, Layer, Param, ParamKind},
+};
+use ndarray::{Array2, Axis};
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Layout of a single sample laid out in a column. Values are stored channel by
+/// channel, each channel row by row. One dimensional signals have a height of 1.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Shape {
+    pub channels: usize,
+    pub height: usize,
+    pub width: usize,
+}
+
+impl Shape {
+    pub fn new(channels: usize, height: usize, width: usize) -> Self {
+        Self {
+            channels,
+            height,
+            width,
+        }
+    }
+
+    pub fn signal(channels: usize, length: usize) -> Self {
+        Self::new(channels, 1, length)
+    }
+
+    /// Number of rows a sample of this shape takes up
+    pub fn size(&self) -> usize {
+        self.channels * self.height * self.width
+    }
+
+    pub(crate) fn index(&self, channel: usize, y: usize, x: usize) -> usize {
+        (channel * self.height + y) * self.width + x
+    }
+}
+
+/// Number of window positions along one dimension, the input being padded on both sides
+pub(crate) fn output_len(input: usize, kernel: usize, stride: usize, padding: usize) -> usize {
+    assert!(
+        input + 2 * padding >= kernel,
+        "window of {} does not fit into an input of {}",
+        kernel,
+        input + 2 * padding
+    );
+    (input + 2 * padding - kernel) / stride + 1
+}
+
+/// Two dimensional convolution over a `Shape`d input, producing one channel per filter.
+/// Every filter is a row of `weights`, laid out the same way as the input window it covers.
+#[derive(Clone)]
+pub struct Conv2D {
+    pub input_shape: Shape,
+    pub kernel: (usize, usize),
+    pub stride: (usize, usize),
+    /// Zeroes added around every side of the input
+    pub padding: (usize, usize),
+    pub weights: Array2<f64>,
+    pub biases: Array2<f64>,
+    pub nabla_w: Array2<f64>,
+    pub nabla_b: Array2<f64>,
+    pub activation: &'static (dyn Activation + Send + Sync),
+    pub regularization: Regularization,
+    columns: Array2<f64>,
+    zs: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl std::fmt::Debug for Conv2D {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "Conv2D {:?} -> {:?} {:?}",
+            self.input_shape,
+            self.output_shape(),
+            self.weights
+        ))
+    }
+}
+
+impl Conv2D {
+    /// `Random` initialization draws the weights uniformly from ±1/√(window size)
+    pub fn new(
+        input_shape: Shape,
+        filters: usize,
+        kernel: (usize, usize),
+        weight_init: InitializationMethods,
+        activation: &'static (dyn Activation + Send + Sync),
+    ) -> Self {
+        let window = input_shape.channels * kernel.0 * kernel.1;
+        let weights = match weight_init {
+            InitializationMethods::Zero => Array2::zeros((filters, window)),
+            InitializationMethods::One => Array2::ones((filters, window)),
+            InitializationMethods::PseudoSpread => {
+                Array2::from_shape_fn((filters, window), |(i, j)| {
+                    ((i as f64 + 1.0) * (j as f64 + 2.0)).sin() / (window as f64).sqrt()
+                })
+            }
+            InitializationMethods::Random => {
+                let bound = 1.0 / (window as f64).sqrt();
+                let mut rng = StdRng::from_entropy();
+                Array2::from_shape_fn((filters, window), |_| rng.gen_range(-bound..bound))
+            }
+        };
+        Self {
+            input_shape,
+            kernel,
+            stride: (1, 1),
+            padding: (0, 0),
+            nabla_w: Array2::zeros(weights.raw_dim()),
+            weights,
+            biases: Array2::zeros((filters, 1)),
+            nabla_b: Array2::zeros((filters, 1)),
+            activation,
+            regularization: Regularization::default(),
+            columns: Array2::zeros((0, 0)),
+            zs: Array2::zeros((0, 0)),
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    pub fn with_stride(mut self, stride: (usize, usize)) -> Self {
+        self.stride = stride;
+        self
+    }
+
+    pub fn with_padding(mut self, padding: (usize, usize)) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn filters(&self) -> usize {
+        self.weights.nrows()
+    }
+
+    pub fn output_shape(&self) -> Shape {
+        Shape::new(
+            self.filters(),
+            output_len(
+                self.input_shape.height,
+                self.kernel.0,
+                self.stride.0,
+                self.padding.0,
+            ),
+            output_len(
+                self.input_shape.width,
+                self.kernel.1,
+                self.stride.1,
+                self.padding.1,
+            ),
+        )
+    }
+
+    // Source of the value at a window position, None when it falls into the padding
+    fn source(&self, window_row: usize, oy: usize, ox: usize) -> Option<usize> {
+        let (kh, kw) = self.kernel;
+        let channel = window_row / (kh * kw);
+        let ky = window_row / kw % kh;
+        let kx = window_row % kw;
+        let y = (oy * self.stride.0 + ky).checked_sub(self.padding.0)?;
+        let x = (ox * self.stride.1 + kx).checked_sub(self.padding.1)?;
+        if y < self.input_shape.height && x < self.input_shape.width {
+            Some(self.input_shape.index(channel, y, x))
+        } else {
+            None
+        }
+    }
+
+    // Unrolls every window of every sample into a column, so the convolution
+    // becomes a single matrix product. Columns are ordered by sample, then position.
+    fn im2col(&self, input: &Array2<f64>) -> Array2<f64> {
+        let out = self.output_shape();
+        let positions = out.height * out.width;
+        let mut columns = Array2::zeros((self.weights.ncols(), positions * input.ncols()));
+        for sample in 0..input.ncols() {
+            for oy in 0..out.height {
+                for ox in 0..out.width {
+                    let col = sample * positions + oy * out.width + ox;
+                    for row in 0..columns.nrows() {
+                        if let Some(src) = self.source(row, oy, ox) {
+                            columns[[row, col]] = input[[src, sample]];
+                        }
+                    }
+                }
+            }
+        }
+        columns
+    }
+
+    fn col2im(&self, columns: &Array2<f64>, samples: usize) -> Array2<f64> {
+        let out = self.output_shape();
+        let positions = out.height * out.width;
+        let mut input = Array2::zeros((self.input_shape.size(), samples));
+        for sample in 0..samples {
+            for oy in 0..out.height {
+                for ox in 0..out.width {
+                    let col = sample * positions + oy * out.width + ox;
+                    for row in 0..columns.nrows() {
+                        if let Some(src) = self.source(row, oy, ox) {
+                            input[[src, sample]] += columns[[row, col]];
+                        }
+                    }
+                }
+            }
+        }
+        input
+    }
+
+    // (filters, samples * positions) <-> (filters * positions, samples)
+    fn to_samples(values: &Array2<f64>, positions: usize) -> Array2<f64> {
+        let filters = values.nrows();
+        let samples = values.ncols() / positions;
+        Array2::from_shape_fn((filters * positions, samples), |(row, sample)| {
+            values[[row / positions, sample * positions + row % positions]]
+        })
+    }
+
+    fn from_samples(values: &Array2<f64>, filters: usize) -> Array2<f64> {
+        let positions = values.nrows() / filters;
+        let samples = values.ncols();
+        Array2::from_shape_fn((filters, samples * positions), |(filter, col)| {
+            values[[filter * positions + col % positions, col / positions]]
+        })
+    }
+}
+
+impl Layer for Conv2D {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        assert_eq!(
+            input.nrows(),
+            self.input_shape.size(),
+            "input does not match {:?}",
+            self.input_shape
+        );
+        let out = self.output_shape();
+        self.columns = self.im2col(input);
+        let zs = &self.weights.dot(&self.columns) + &self.biases;
+        self.zs = Self::to_samples(&zs, out.height * out.width);
+        self.state = self.activation.function(&self.zs);
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        let delta = delta * &self.activation.derivative(&self.zs, Some(&self.state));
+        let delta = Self::from_samples(&delta, self.filters());
+        self.nabla_w += &delta.dot(&self.columns.t());
+        self.nabla_b += &delta.sum_axis(Axis(1)).insert_axis(Axis(1));
+        self.col2im(&self.weights.t().dot(&delta), self.state.ncols())
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.weights, &self.biases]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_w, &self.nabla_b]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.weights,
+                grad: &self.nabla_w,
+            },
+            Param {
+                kind: ParamKind::Biases,
+                value: &mut self.biases,
+                grad: &self.nabla_b,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_w.fill(0.0);
+        self.nabla_b.fill(0.0);
+    }
+
+    fn regularization(&self) -> Regularization {
+        self.regularization
+    }
+
+    fn set_regularization(&mut self, regularization: Regularization) {
+        self.regularization = regularization;
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        let biases = if self.regularization.include_biases {
+            self.regularization.penalty(&self.biases)
+        } else {
+            0.0
+        };
+        self.regularization.penalty(&self.weights) + biases
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Conv {
+            input_shape: self.input_shape,
+            kernel: self.kernel,
+            stride: self.stride,
+            padding: self.padding,
+            weights: self.weights.clone(),
+            biases: self.biases.clone(),
+            activation: self.activation.kind(),
+            regularization: self.regularization,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Convolution along signals of `Shape::signal` layout. It is a `Conv2D` with
+/// kernels, strides and padding one row high.
+#[derive(Debug, Clone)]
+pub struct Conv1D(pub Conv2D);
+
+impl Conv1D {
+    pub fn new(
+        input_shape: Shape,
+        filters: usize,
+        kernel: usize,
+        weight_init: InitializationMethods,
+        activation: &'static (dyn Activation + Send + Sync),
+    ) -> Self {
+        assert_eq!(input_shape.height, 1, "Conv1D expects a signal shape");
+        Self(Conv2D::new(
+            input_shape,
+            filters,
+            (1, kernel),
+            weight_init,
+            activation,
+        ))
+    }
+
+    pub fn with_stride(mut self, stride: usize) -> Self {
+        self.0.stride = (1, stride);
+        self
+    }
+
+    pub fn with_padding(mut self, padding: usize) -> Self {
+        self.0.padding = (0, padding);
+        self
+    }
+
+    pub fn output_shape(&self) -> Shape {
+        self.0.output_shape()
+    }
+}
+
+impl Layer for Conv1D {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        self.0.feed_forward(input)
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        self.0.backward(delta)
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        self.0.output()
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        self.0.params()
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        self.0.grads()
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        self.0.params_mut()
+    }
+
+    fn zero_grads(&mut self) {
+        self.0.zero_grads()
+    }
+
+    fn regularization(&self) -> Regularization {
+        self.0.regularization()
+    }
+
+    fn set_regularization(&mut self, regularization: Regularization) {
+        self.0.set_regularization(regularization)
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.0.regularization_loss()
+    }
+
+    fn save(&self) -> SavedLayer {
+        self.0.save()
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
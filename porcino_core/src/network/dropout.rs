@@ -0,0 +1,80 @@
+use crate::enums::Mode;
+use crate::persistence::SavedLayer;
+use crate::traits::Layer;
+use ndarray::Array2;
+use rand::prelude::*;
+
+/// Zeroes each input with probability `rate` while training. Kept values are scaled
+/// by `1 / (1 - rate)`, so that the layer passes its input through unchanged in inference.
+#[derive(Debug, Clone)]
+pub struct Dropout {
+    pub rate: f64,
+    mode: Mode,
+    rng: StdRng,
+    mask: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl Dropout {
+    pub fn new(rate: f64) -> Self {
+        Self::with_rng(rate, StdRng::from_entropy())
+    }
+
+    /// Dropout with a reproducible sequence of masks
+    pub fn with_seed(rate: f64, seed: u64) -> Self {
+        Self::with_rng(rate, StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rate: f64, rng: StdRng) -> Self {
+        assert!((0.0..1.0).contains(&rate), "dropout rate must be in [0, 1)");
+        Self {
+            rate,
+            mode: Mode::Train,
+            rng,
+            mask: Array2::zeros((0, 0)),
+            state: Array2::zeros((0, 0)),
+        }
+    }
+}
+
+impl Layer for Dropout {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        self.mask = match self.mode {
+            Mode::Train if self.rate > 0.0 => {
+                let scale = 1.0 / (1.0 - self.rate);
+                let rng = &mut self.rng;
+                let rate = self.rate;
+                Array2::from_shape_fn(input.raw_dim(), |_| {
+                    if rng.gen::<f64>() < rate {
+                        0.0
+                    } else {
+                        scale
+                    }
+                })
+            }
+            _ => Array2::ones(input.raw_dim()),
+        };
+        self.state = input * &self.mask;
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        delta * &self.mask
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Dropout { rate: self.rate }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
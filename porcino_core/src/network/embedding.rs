@@ -0,0 +1,175 @@
+use super::regularization::Regularization;
+use crate::enums::InitializationMethods;
+use crate::persistence::SavedLayer;
+use crate::traits::{Layer, Param, ParamKind};
+use ndarray::{s, Array2};
+
+/// First layer of a network with categorical inputs. Every input row with a cardinality
+/// holds a label index, which is looked up in that column's table. The looked up vectors
+/// are concatenated in front of the numeric rows, which pass through unchanged.
+#[derive(Debug, Clone)]
+pub struct Embedding {
+    /// Cardinality of every input row, `None` for numeric ones
+    pub categories: Vec<Option<usize>>,
+    /// One table per categorical row, with a row of `dim` values per label
+    pub tables: Vec<Array2<f64>>,
+    pub nabla: Vec<Array2<f64>>,
+    pub regularization: Regularization,
+    indices: Array2<usize>,
+    state: Array2<f64>,
+}
+
+impl Embedding {
+    pub fn new(
+        categories: &[Option<usize>],
+        dim: usize,
+        weight_init: InitializationMethods,
+    ) -> Self {
+        let tables = categories
+            .iter()
+            .flatten()
+            .map(|cardinality| weight_init.scaled((*cardinality, dim), dim))
+            .collect::<Vec<_>>();
+        Self {
+            categories: categories.to_vec(),
+            nabla: tables.iter().map(|t| Array2::zeros(t.raw_dim())).collect(),
+            tables,
+            regularization: Regularization::default(),
+            indices: Array2::zeros((0, 0)),
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    pub fn dim(&self) -> usize {
+        self.tables.first().map_or(0, |t| t.ncols())
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.categories
+            .iter()
+            .map(|c| if c.is_some() { self.dim() } else { 1 })
+            .sum()
+    }
+
+    fn numeric_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.categories
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_none())
+            .map(|(row, _)| row)
+    }
+
+    fn categorical_rows(&self) -> impl Iterator<Item = usize> + '_ {
+        self.categories
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.is_some())
+            .map(|(row, _)| row)
+    }
+}
+
+impl Layer for Embedding {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        assert_eq!(input.nrows(), self.categories.len());
+        let dim = self.dim();
+        let embedded = self.tables.len() * dim;
+        let mut state = Array2::zeros((self.outputs(), input.ncols()));
+        // Labels outside of the table, like ones unseen while parsing, map to zeroes
+        let mut indices = Array2::from_elem((self.tables.len(), input.ncols()), usize::MAX);
+
+        for (table_idx, row) in self.categorical_rows().enumerate() {
+            let table = &self.tables[table_idx];
+            for (sample, value) in input.row(row).iter().enumerate() {
+                let label = value.round();
+                if label >= 0.0 && (label as usize) < table.nrows() {
+                    indices[[table_idx, sample]] = label as usize;
+                    state
+                        .slice_mut(s![table_idx * dim..(table_idx + 1) * dim, sample])
+                        .assign(&table.row(label as usize));
+                }
+            }
+        }
+        for (offset, row) in self.numeric_rows().enumerate() {
+            state.row_mut(embedded + offset).assign(&input.row(row));
+        }
+
+        self.indices = indices;
+        self.state = state;
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        let dim = self.dim();
+        let embedded = self.tables.len() * dim;
+        for ((table_idx, sample), label) in self.indices.indexed_iter() {
+            if *label != usize::MAX {
+                let mut row = self.nabla[table_idx].row_mut(*label);
+                row += &delta.slice(s![table_idx * dim..(table_idx + 1) * dim, sample]);
+            }
+        }
+
+        // Label indices are not differentiable, only numeric rows get a gradient
+        let mut input_delta = Array2::zeros((self.categories.len(), delta.ncols()));
+        for (offset, row) in self.numeric_rows().enumerate() {
+            input_delta
+                .row_mut(row)
+                .assign(&delta.row(embedded + offset));
+        }
+        input_delta
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        self.tables.iter().collect()
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        self.nabla.iter().collect()
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        self.tables
+            .iter_mut()
+            .zip(self.nabla.iter())
+            .map(|(value, grad)| Param {
+                kind: ParamKind::Weights,
+                value,
+                grad,
+            })
+            .collect()
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla.iter_mut().for_each(|n| n.fill(0.0));
+    }
+
+    fn regularization(&self) -> Regularization {
+        self.regularization
+    }
+
+    fn set_regularization(&mut self, regularization: Regularization) {
+        self.regularization = regularization;
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.tables
+            .iter()
+            .map(|t| self.regularization.penalty(t))
+            .sum()
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Embedding {
+            categories: self.categories.clone(),
+            tables: self.tables.clone(),
+            regularization: self.regularization,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
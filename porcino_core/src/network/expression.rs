@@ -0,0 +1,147 @@
+use super::regularization::Regularization;
+use crate::autodiff::{Recorded, Tape, Var};
+use crate::persistence::SavedLayer;
+use crate::traits::{Layer, Param, ParamKind};
+use ndarray::Array2;
+use std::sync::Mutex;
+
+/// Forward pass of an `ExprLayer`, recording its output from the input and the parameters
+pub type LayerExpr = fn(&mut Tape, Var, &[Var]) -> Var;
+
+static EXPRESSIONS: Mutex<Vec<(String, LayerExpr)>> = Mutex::new(Vec::new());
+
+/// Layer defined by its forward expression only, the backward pass differentiates
+/// the recorded expression. Saved layers keep the expression's name, which needs to be
+/// registered again before reading them in another program.
+#[derive(Debug, Clone)]
+pub struct ExprLayer {
+    pub name: String,
+    pub params: Vec<Array2<f64>>,
+    pub nabla: Vec<Array2<f64>>,
+    pub regularization: Regularization,
+    forward: LayerExpr,
+    recorded: Option<Recorded>,
+    state: Array2<f64>,
+}
+
+impl ExprLayer {
+    pub fn new(name: &str, forward: LayerExpr, params: Vec<Array2<f64>>) -> Self {
+        Self::register(name, forward);
+        Self {
+            name: name.to_string(),
+            nabla: params.iter().map(|p| Array2::zeros(p.raw_dim())).collect(),
+            params,
+            regularization: Regularization::default(),
+            forward,
+            recorded: None,
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    /// Makes `forward` known by `name`, replacing an expression registered before
+    pub fn register(name: &str, forward: LayerExpr) {
+        let mut expressions = EXPRESSIONS.lock().unwrap();
+        match expressions.iter_mut().find(|(known, _)| known == name) {
+            Some(entry) => entry.1 = forward,
+            None => expressions.push((name.to_string(), forward)),
+        }
+    }
+
+    pub fn registered(name: &str) -> Option<LayerExpr> {
+        EXPRESSIONS
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(known, _)| known == name)
+            .map(|(_, forward)| *forward)
+    }
+}
+
+impl Layer for ExprLayer {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        let mut tape = Tape::new();
+        let x = tape.var(input.clone());
+        let params = self
+            .params
+            .iter()
+            .map(|p| tape.var(p.clone()))
+            .collect::<Vec<_>>();
+        let output = (self.forward)(&mut tape, x, &params);
+
+        self.state = tape.value(output).clone();
+        self.recorded = Some(Recorded {
+            tape,
+            input: x,
+            params,
+            output,
+        });
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        let recorded = self
+            .recorded
+            .as_ref()
+            .expect("backward needs a forward pass first");
+        let (input_delta, grads) = recorded.backward(delta);
+        for (nabla, grad) in self.nabla.iter_mut().zip(grads) {
+            *nabla += &grad;
+        }
+        input_delta
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        self.params.iter().collect()
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        self.nabla.iter().collect()
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        self.params
+            .iter_mut()
+            .zip(self.nabla.iter())
+            .map(|(value, grad)| Param {
+                kind: ParamKind::Weights,
+                value,
+                grad,
+            })
+            .collect()
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla.iter_mut().for_each(|n| n.fill(0.0));
+    }
+
+    fn regularization(&self) -> Regularization {
+        self.regularization
+    }
+
+    fn set_regularization(&mut self, regularization: Regularization) {
+        self.regularization = regularization;
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        self.params
+            .iter()
+            .map(|p| self.regularization.penalty(p))
+            .sum()
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Expression {
+            name: self.name.clone(),
+            params: self.params.clone(),
+            regularization: self.regularization,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,123 @@
+use super::Network;
+use crate::traits::Layer;
+use serde::{Deserialize, Serialize};
+
+/// Position of a node in the network graph
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NodeId(pub usize);
+
+/// Vertex of the network graph. Nodes only take values from nodes before them,
+/// so their order is a topological one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Node {
+    /// Rows of the network input starting at `offset`. `None` takes all the rows that follow.
+    Input { offset: usize, size: Option<usize> },
+    /// Runs `Network::layers[layer]` on the value of `input`
+    Layer { layer: usize, input: NodeId },
+    /// Element-wise sum of equally shaped values, e.g. for residual connections
+    Add(Vec<NodeId>),
+    /// Values stacked on top of each other in the given order
+    Concat(Vec<NodeId>),
+}
+
+impl Node {
+    pub fn sources(&self) -> Vec<NodeId> {
+        match self {
+            Node::Input { .. } => Vec::new(),
+            Node::Layer { input, .. } => vec![*input],
+            Node::Add(from) | Node::Concat(from) => from.clone(),
+        }
+    }
+}
+
+// Nodes may only read nodes before them, every layer runs in at most one node and the
+// outputs have to exist
+pub(crate) fn check_graph(layers: usize, nodes: &[Node], outputs: &[NodeId]) -> Result<(), String> {
+    let mut used = vec![false; layers];
+    for (idx, node) in nodes.iter().enumerate() {
+        let sources = node.sources();
+        if sources.iter().any(|source| source.0 >= idx) {
+            return Err(format!(
+                "node {idx} reads a node that doesn't come before it"
+            ));
+        }
+        match node {
+            Node::Layer { layer, .. } => match used.get_mut(*layer) {
+                None => {
+                    return Err(format!(
+                        "node {idx} runs layer {layer}, which doesn't exist"
+                    ))
+                }
+                Some(true) => return Err(format!("layer {layer} is used by more than one node")),
+                Some(used) => *used = true,
+            },
+            Node::Add(_) | Node::Concat(_) if sources.is_empty() => {
+                return Err(format!("node {idx} joins no values"));
+            }
+            _ => {}
+        }
+    }
+    if outputs.is_empty() || outputs.iter().any(|output| output.0 >= nodes.len()) {
+        return Err("network needs outputs out of its nodes".to_string());
+    }
+    Ok(())
+}
+
+/// Builds networks that branch and merge. Every call returns the id of the new node,
+/// which later nodes take their values from.
+///
+/// With several inputs, the rows of a sample are split between them in the order they
+/// were added. With several outputs, their values are stacked in the order given to
+/// `build`, so both still fit into a single `TrainingSample`.
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    layers: Vec<Box<dyn Layer>>,
+    nodes: Vec<Node>,
+    input_rows: usize,
+}
+
+impl GraphBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn input(&mut self, size: usize) -> NodeId {
+        let node = Node::Input {
+            offset: self.input_rows,
+            size: Some(size),
+        };
+        self.input_rows += size;
+        self.push(node)
+    }
+
+    pub fn layer(&mut self, layer: Box<dyn Layer>, input: NodeId) -> NodeId {
+        self.layers.push(layer);
+        self.push(Node::Layer {
+            layer: self.layers.len() - 1,
+            input,
+        })
+    }
+
+    pub fn add(&mut self, from: &[NodeId]) -> NodeId {
+        self.push(Node::Add(from.to_vec()))
+    }
+
+    pub fn concat(&mut self, from: &[NodeId]) -> NodeId {
+        self.push(Node::Concat(from.to_vec()))
+    }
+
+    pub fn build(self, outputs: &[NodeId]) -> Network {
+        Network::from_graph(self.layers, self.nodes, outputs.to_vec())
+    }
+
+    fn push(&mut self, node: Node) -> NodeId {
+        assert!(
+            node.sources()
+                .iter()
+                .all(|source| source.0 < self.nodes.len()),
+            "nodes can only take values from nodes added before them"
+        );
+        self.nodes.push(node);
+        NodeId(self.nodes.len() - 1)
+    }
+}
@@ -0,0 +1,41 @@
+use crate::errors::Loss;
+use crate::network::Activations;
+use serde::{Deserialize, Serialize};
+
+/// Output of a multi-task network, predicting a group of target columns
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Head {
+    pub name: String,
+    /// Rows of the expected output the head predicts, in order
+    pub columns: Vec<usize>,
+    pub activation: Activations,
+    pub loss: Loss,
+    /// Share of the head in the total loss
+    pub weight: f64,
+}
+
+impl Head {
+    pub fn new(name: &str, columns: Vec<usize>) -> Self {
+        Self {
+            name: name.to_owned(),
+            columns,
+            activation: Activations::Linear,
+            loss: Loss::Sse,
+            weight: 1.0,
+        }
+    }
+}
+
+/// Row of the stacked head outputs each expected output row comes from
+pub(crate) fn target_rows(heads: &[Head]) -> Vec<usize> {
+    let columns = heads.iter().flat_map(|h| h.columns.iter()).copied();
+    let mut rows = vec![usize::MAX; heads.iter().map(|h| h.columns.len()).sum()];
+    for (stacked, column) in columns.enumerate() {
+        assert!(
+            column < rows.len() && rows[column] == usize::MAX,
+            "heads must split the target columns between them"
+        );
+        rows[column] = stacked;
+    }
+    rows
+}
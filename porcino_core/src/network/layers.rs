@@ -1,6 +1,9 @@
+use super::regularization::Regularization;
+use crate::autodiff::{Recorded, Tape, Var};
+use crate::persistence::SavedLayer;
 use crate::{
-    enums::InitializationMethods,
-    traits::{Activation, Layer},
+    enums::{InitializationMethods, Mode},
+    traits::{Activation, Layer, Param, ParamKind},
 };
 use ndarray::Array2;
 use rand::distributions::Standard;
@@ -11,12 +14,20 @@ fn local_sig(x: f64) -> f64 {
     1. / (1. + (-x).exp())
 }
 
+#[derive(Clone)]
 pub struct FFLayer {
     pub weights: Array2<f64>,
     pub biases: Array2<f64>,
+    pub nabla_w: Array2<f64>,
+    pub nabla_b: Array2<f64>,
+    pub input: Array2<f64>,
     pub zs: Array2<f64>,
     pub state: Array2<f64>,
     pub activation: &'static (dyn Activation + Send + Sync),
+    pub regularization: Regularization,
+    mode: Mode,
+    // Forward pass of the last `feed_forward` in training mode
+    recorded: Option<Recorded>,
 }
 impl Debug for FFLayer {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
@@ -31,47 +42,154 @@ impl FFLayer {
         weight_init: InitializationMethods,
         activation: &'static (dyn Activation + Send + Sync),
     ) -> Self {
-        match weight_init {
-            InitializationMethods::Zero => Self {
-                weights: Array2::zeros((neurons, inputs)),
-                biases: Array2::zeros((neurons, 1)),
-                zs: Array2::zeros((neurons, 1)),
-                state: Array2::zeros((neurons, 1)),
-                activation,
-            },
-            InitializationMethods::One => Self {
-                weights: Array2::ones((neurons, inputs)),
-                biases: Array2::zeros((neurons, 1)),
-                zs: Array2::zeros((neurons, 1)),
-                state: Array2::zeros((neurons, 1)),
-                activation,
-            },
-            InitializationMethods::PseudoSpread => Self {
-                weights: Array2::from_shape_fn((neurons, inputs), |(i, j)| {
+        let weights = match weight_init {
+            InitializationMethods::Zero => Array2::zeros((neurons, inputs)),
+            InitializationMethods::One => Array2::ones((neurons, inputs)),
+            InitializationMethods::PseudoSpread => {
+                Array2::from_shape_fn((neurons, inputs), |(i, j)| {
                     local_sig((i as f64 + 1.0).exp() * (j as f64 + 2.0).ln()) - 0.5
-                }),
-                biases: Array2::zeros((neurons, 1)),
-                zs: Array2::zeros((neurons, 1)),
-                state: Array2::zeros((neurons, 1)),
-                activation,
-            },
-            InitializationMethods::Random => Self {
-                weights: Array2::from_shape_fn((neurons, inputs), |_| {
-                    StdRng::from_entropy().sample(Standard)
-                }),
-                biases: Array2::zeros((neurons, 1)),
-                zs: Array2::zeros((neurons, 1)),
-                state: Array2::zeros((neurons, 1)),
-                activation,
-            },
+                })
+            }
+            InitializationMethods::Random => Array2::from_shape_fn((neurons, inputs), |_| {
+                StdRng::from_entropy().sample(Standard)
+            }),
+        };
+        Self {
+            nabla_w: Array2::zeros(weights.raw_dim()),
+            weights,
+            biases: Array2::zeros((neurons, 1)),
+            nabla_b: Array2::zeros((neurons, 1)),
+            input: Array2::zeros((inputs, 1)),
+            zs: Array2::zeros((neurons, 1)),
+            state: Array2::zeros((neurons, 1)),
+            activation,
+            regularization: Regularization::default(),
+            mode: Mode::Train,
+            recorded: None,
         }
     }
+
+    // Records the pass over the last input on a tape, returning it with the weighted input
+    fn record(&self) -> (Recorded, Var) {
+        let mut tape = Tape::new();
+        let x = tape.var(self.input.clone());
+        let w = tape.var(self.weights.clone());
+        let b = tape.var(self.biases.clone());
+        let weighted = tape.matmul(w, x);
+        let z = tape.add(weighted, b);
+        let output = self.activation.record(&mut tape, z);
+        let recorded = Recorded {
+            tape,
+            input: x,
+            params: vec![w, b],
+            output,
+        };
+        (recorded, z)
+    }
 }
 
 impl Layer for FFLayer {
     fn feed_forward(&mut self, input: &ndarray::Array2<f64>) -> &Array2<f64> {
-        self.zs = &self.weights.dot(input) + &self.biases;
-        self.state = self.activation.function(&self.zs);
+        self.input = input.clone();
+        match self.mode {
+            Mode::Train => {
+                let (recorded, z) = self.record();
+                self.zs = recorded.tape.value(z).clone();
+                self.state = recorded.tape.value(recorded.output).clone();
+                self.recorded = Some(recorded);
+            }
+            Mode::Inference => {
+                self.zs = &self.weights.dot(input) + &self.biases;
+                self.state = self.activation.function(&self.zs);
+                self.recorded = None;
+            }
+        }
         &self.state
     }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        // Passes in inference mode are not recorded, which is rare enough to do here
+        let fresh;
+        let recorded = match &self.recorded {
+            Some(recorded) => recorded,
+            None => {
+                fresh = self.record().0;
+                &fresh
+            }
+        };
+        let (input_delta, grads) = recorded.backward(delta);
+        self.nabla_w += &grads[0];
+        self.nabla_b += &grads[1];
+        input_delta
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.weights, &self.biases]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_w, &self.nabla_b]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.weights,
+                grad: &self.nabla_w,
+            },
+            Param {
+                kind: ParamKind::Biases,
+                value: &mut self.biases,
+                grad: &self.nabla_b,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_w.fill(0.0);
+        self.nabla_b.fill(0.0);
+    }
+
+    fn is_dense(&self) -> bool {
+        true
+    }
+
+    fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+    }
+
+    fn regularization(&self) -> Regularization {
+        self.regularization
+    }
+
+    fn set_regularization(&mut self, regularization: Regularization) {
+        self.regularization = regularization;
+    }
+
+    fn regularization_loss(&self) -> f64 {
+        let biases = if self.regularization.include_biases {
+            self.regularization.penalty(&self.biases)
+        } else {
+            0.0
+        };
+        self.regularization.penalty(&self.weights) + biases
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Dense {
+            weights: self.weights.clone(),
+            biases: self.biases.clone(),
+            activation: self.activation.kind(),
+            regularization: self.regularization,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
 }
@@ -1,17 +1,54 @@
-use crate::network::activations::Linear;
-use ndarray::Array2;
+use crate::autodiff::{loss_gradient, Tape, Var};
+use crate::data::stack_samples;
+use crate::enums::Mode;
+use crate::errors::Loss;
+use crate::traits::{Activation, Param, Predictor};
+use ndarray::{concatenate, s, Array1, Array2, Axis};
 use porcino_data::parse::TrainingSample;
+use serde::{Deserialize, Serialize};
 
 use crate::traits::Layer;
 
-use self::{activations::Sigmoid, layers::FFLayer};
-
 mod activations;
+mod batch_norm;
+mod conv;
+mod dropout;
+mod embedding;
+mod expression;
+mod graph;
+mod heads;
 mod layers;
+mod pooling;
+mod rbf;
+mod regularization;
+
+pub use activations::{Linear, Sigmoid, Tanh};
+pub use batch_norm::BatchNorm;
+pub use conv::{Conv1D, Conv2D, Shape};
+pub use dropout::Dropout;
+pub use embedding::Embedding;
+pub use expression::{ExprLayer, LayerExpr};
+use graph::check_graph;
+pub use graph::{GraphBuilder, Node, NodeId};
+pub use heads::Head;
+pub use layers::FFLayer;
+pub use pooling::{AvgPool, Flatten, MaxPool};
+pub use rbf::{Rbf, RbfKernel};
+pub use regularization::Regularization;
 
-#[derive(Debug)]
+/// Layers wired together by a graph of `Node`s. Networks built with `new` or
+/// `from_layers` are plain chains, `GraphBuilder` builds anything else.
+#[derive(Debug, Clone)]
 pub struct Network {
-    pub layers: Vec<FFLayer>,
+    pub layers: Vec<Box<dyn Layer>>,
+    nodes: Vec<Node>,
+    outputs: Vec<NodeId>,
+    heads: Vec<Head>,
+    // Row of the stacked outputs every target row is taken from
+    head_rows: Vec<usize>,
+    values: Vec<Array2<f64>>,
+    output: Array2<f64>,
+    mode: Mode,
 }
 
 pub struct LayerSettings {
@@ -19,150 +56,466 @@ pub struct LayerSettings {
     pub activation: Activations,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum Activations {
     Sigmoid,
     Linear,
+    Tanh,
+}
+
+impl Activations {
+    pub fn function(&self) -> &'static (dyn Activation + Send + Sync) {
+        match self {
+            Activations::Sigmoid => &Sigmoid,
+            Activations::Linear => &Linear,
+            Activations::Tanh => &Tanh,
+        }
+    }
 }
 
 impl Network {
     pub fn new(neurons: Vec<LayerSettings>, init: crate::enums::InitializationMethods) -> Self {
-        Self {
-            layers: neurons
+        Self::from_layers(
+            neurons
                 .windows(2)
                 .map(|window| {
-                    FFLayer::new(
+                    Box::new(FFLayer::new(
                         window[0].neurons,
                         window[1].neurons,
                         init,
-                        match window[1].activation {
-                            Activations::Sigmoid => &Sigmoid,
-                            Activations::Linear => &Linear,
-                        },
-                    )
+                        window[1].activation.function(),
+                    )) as Box<dyn Layer>
                 })
                 .collect(),
-        }
+        )
+    }
+
+    /// Builds a network out of arbitrary layers, each one feeding the next
+    pub fn from_layers(layers: Vec<Box<dyn Layer>>) -> Self {
+        let mut nodes = vec![Node::Input {
+            offset: 0,
+            size: None,
+        }];
+        nodes.extend((0..layers.len()).map(|layer| Node::Layer {
+            layer,
+            input: NodeId(layer),
+        }));
+        let outputs = vec![NodeId(nodes.len() - 1)];
+        Self::from_graph(layers, nodes, outputs)
+    }
+
+    /// Network running `layers` as laid out by `nodes`, see `GraphBuilder`. Panics if
+    /// the graph is malformed.
+    pub fn from_graph(layers: Vec<Box<dyn Layer>>, nodes: Vec<Node>, outputs: Vec<NodeId>) -> Self {
+        Self::try_from_graph(layers, nodes, outputs).unwrap_or_else(|error| panic!("{error}"))
+    }
+
+    /// Like `from_graph`, but tells what is wrong with a malformed graph instead of
+    /// panicking, e.g. for graphs read from a file
+    pub fn try_from_graph(
+        layers: Vec<Box<dyn Layer>>,
+        nodes: Vec<Node>,
+        outputs: Vec<NodeId>,
+    ) -> Result<Self, String> {
+        check_graph(layers.len(), &nodes, &outputs)?;
+        Ok(Self {
+            layers,
+            nodes,
+            outputs,
+            heads: Vec::new(),
+            head_rows: Vec::new(),
+            values: Vec::new(),
+            output: Array2::zeros((0, 0)),
+            mode: Mode::Train,
+        })
+    }
+
+    /// Trunk of layers, each feeding the next, shared by a dense output layer per head
+    pub fn with_heads(
+        trunk: Vec<Box<dyn Layer>>,
+        trunk_outputs: usize,
+        heads: Vec<Head>,
+        init: crate::enums::InitializationMethods,
+    ) -> Self {
+        assert!(!heads.is_empty(), "a network needs at least one head");
+        let mut network = Self::from_layers(trunk);
+        let trunk_end = network.outputs[0];
+        network.outputs = heads
+            .iter()
+            .map(|head| {
+                network.layers.push(Box::new(FFLayer::new(
+                    trunk_outputs,
+                    head.columns.len(),
+                    init,
+                    head.activation.function(),
+                )));
+                network.nodes.push(Node::Layer {
+                    layer: network.layers.len() - 1,
+                    input: trunk_end,
+                });
+                NodeId(network.nodes.len() - 1)
+            })
+            .collect();
+        network.set_heads(heads);
+        network
+    }
+
+    /// Assigns the outputs, in order, to heads. The output of the network is then
+    /// arranged like the target columns and every head is scored with its own loss.
+    pub fn set_heads(&mut self, heads: Vec<Head>) {
+        assert_eq!(heads.len(), self.outputs.len(), "every output needs a head");
+        self.head_rows = heads::target_rows(&heads);
+        self.heads = heads;
+    }
+
+    pub fn heads(&self) -> &[Head] {
+        &self.heads
+    }
+
+    pub fn nodes(&self) -> &[Node] {
+        &self.nodes
+    }
+
+    pub fn outputs(&self) -> &[NodeId] {
+        &self.outputs
     }
 
     pub fn process_data(&mut self, input: &Array2<f64>) {
-        let mut input = self.layers[0].feed_forward(input).clone();
-        for i in 1..self.layers.len() {
-            input = self.layers[i].feed_forward(&input).clone();
+        let mut values: Vec<Array2<f64>> = Vec::with_capacity(self.nodes.len());
+        for node in self.nodes.iter() {
+            let value = match node {
+                Node::Input { offset, size } => {
+                    let end = size.map_or(input.nrows(), |size| offset + size);
+                    input.slice(s![*offset..end, ..]).to_owned()
+                }
+                Node::Layer { layer, input } => {
+                    self.layers[*layer].feed_forward(&values[input.0]).clone()
+                }
+                Node::Add(from) => from[1..]
+                    .iter()
+                    .fold(values[from[0].0].clone(), |sum, id| sum + &values[id.0]),
+                Node::Concat(from) => join(from, &values),
+            };
+            values.push(value);
+        }
+        self.output = join(&self.outputs, &values);
+        if !self.heads.is_empty() {
+            self.output = self.output.select(Axis(0), &self.head_rows);
         }
+        self.values = values;
     }
 
-    pub fn gradient_descent(&mut self, training_data: &Vec<TrainingSample>, eta: f64) {
-        // Allocation of gradient vectors
-        let mut nabla_b = self
-            .layers
-            .iter()
-            .map(|layer| &layer.biases)
-            .map(|b| Array2::zeros(b.raw_dim()))
-            .collect::<Vec<Array2<f64>>>();
-        let mut nabla_w = self
-            .layers
+    /// Result of the last `process_data`, the values of all outputs stacked in order.
+    /// With heads, the rows follow the target columns instead.
+    pub fn output(&self) -> &Array2<f64> {
+        &self.output
+    }
+
+    /// Value of a node in the last `process_data`
+    pub(crate) fn node_value(&self, id: NodeId) -> &Array2<f64> {
+        &self.values[id.0]
+    }
+
+    /// Targets arranged like the stacked values of the outputs, undoing the head order
+    pub(crate) fn output_targets(&self, reference_set: &Array2<f64>) -> Array2<f64> {
+        if self.heads.is_empty() {
+            return reference_set.clone();
+        }
+        let mut targets = Array2::zeros(reference_set.raw_dim());
+        for (target, row) in self.head_rows.iter().enumerate() {
+            targets.row_mut(*row).assign(&reference_set.row(target));
+        }
+        targets
+    }
+
+    /// Error of the last output, the weighted sum of the head losses when there are heads
+    pub fn loss(&self, reference_set: &Array2<f64>) -> f64 {
+        if self.heads.is_empty() {
+            return Loss::Sse.value(self.output(), reference_set);
+        }
+        self.heads
             .iter()
-            .map(|layer| &layer.weights)
-            .map(|w| Array2::zeros(w.raw_dim()))
-            .collect::<Vec<Array2<f64>>>();
-
-        // Loop performing learning iteration over all mini_batches
-        for sample in training_data {
-            // Getting updated gradients from backpropagation algorithm
-            self.process_data(&sample.input);
-            let (delta_nabla_b, delta_nabla_w) =
-                self.calculate_gradient(&sample.input, &sample.expected_output);
-
-            // Calculating new gradients with respect to ones created in first steps and also newly calculated ones
-            nabla_b = nabla_b
-                .iter()
-                .zip(delta_nabla_b.iter())
-                .map(|(nb, dnb)| nb + dnb)
-                .collect();
+            .map(|head| {
+                head.weight
+                    * head.loss.value(
+                        &self.output.select(Axis(0), &head.columns),
+                        &reference_set.select(Axis(0), &head.columns),
+                    )
+            })
+            .sum()
+    }
 
-            nabla_w = nabla_w
-                .iter()
-                .zip(delta_nabla_w.iter())
-                .map(|(nw, dnw)| nw + dnw)
-                .collect();
+    /// Gradient of `loss` with respect to the last output, halved like the SSE one
+    pub fn loss_gradient(&self, reference_set: &Array2<f64>) -> Array2<f64> {
+        if self.heads.is_empty() {
+            return Loss::Sse.gradient(self.output(), reference_set);
+        }
+        let mut gradient = Array2::zeros(self.output.raw_dim());
+        for head in self.heads.iter() {
+            let head_gradient = head.loss.gradient(
+                &self.output.select(Axis(0), &head.columns),
+                &reference_set.select(Axis(0), &head.columns),
+            ) * head.weight;
+            for (row, column) in head.columns.iter().enumerate() {
+                gradient.row_mut(*column).assign(&head_gradient.row(row));
+            }
         }
+        gradient
+    }
 
-        // Calculating new values for weights and biases based on recieved gradients with respect to batch size and learning rate
-        self.layers
-            .iter_mut()
-            .map(|layer| &mut layer.weights)
-            .zip(nabla_w.iter())
-            .for_each(|(w, nw)| {
-                *w = w.clone() - nw * eta;
-            });
+    pub fn mode(&self) -> Mode {
+        self.mode
+    }
 
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
         self.layers
             .iter_mut()
-            .map(|layer| &mut layer.biases)
-            .zip(nabla_b.iter())
-            .for_each(|(w, nw)| {
-                *w = w.clone() - nw * eta;
-            });
+            .for_each(|layer| layer.set_mode(mode));
+    }
+
+    /// Performs a single full-batch update, returning the summed error of the batch
+    pub fn gradient_descent(&mut self, training_data: &[TrainingSample], eta: f64) -> f64 {
+        let error = self.accumulate_gradient(training_data);
+        self.apply_gradients(eta);
+        error
+    }
+
+    /// Sums the gradients of all samples into the layers, returning the summed error
+    /// of the batch including the regularization penalty
+    pub fn accumulate_gradient(&mut self, training_data: &[TrainingSample]) -> f64 {
+        self.zero_grads();
+        if training_data.is_empty() {
+            return self.regularization_loss();
+        }
+
+        // Whole batch goes through at once, one sample per column, so that layers
+        // like batch normalization see the batch statistics
+        let (inputs, references) = stack_samples(training_data);
+        self.process_data(&inputs);
+        let error = self.loss(&references);
+        self.backpropagate(&references);
+
+        // Penalty is measured on the weights the gradients were computed for
+        error + self.regularization_loss()
+    }
+
+    /// Like `accumulate_gradient`, scoring the batch with a loss given as an expression of
+    /// the network's output and the reference instead of the heads' losses
+    pub fn accumulate_gradient_with(
+        &mut self,
+        training_data: &[TrainingSample],
+        loss: impl Fn(&mut Tape, Var, Var) -> Var,
+    ) -> f64 {
+        self.zero_grads();
+        if training_data.is_empty() {
+            return self.regularization_loss();
+        }
+
+        let (inputs, references) = stack_samples(training_data);
+        self.process_data(&inputs);
+        let (error, gradient) = loss_gradient(loss, self.output(), &references);
+        self.backpropagate_delta(&gradient);
+        error + self.regularization_loss()
+    }
+
+    /// Steps every parameter against its accumulated gradient, applying the layers' regularization
+    pub fn apply_gradients(&mut self, eta: f64) {
+        for layer in self.layers.iter_mut() {
+            let reg = layer.regularization();
+            step_params(layer.params_mut(), &reg, eta);
+        }
+    }
+
+    pub fn zero_grads(&mut self) {
+        self.layers.iter_mut().for_each(|layer| layer.zero_grads());
     }
+
+    /// Propagates the error of the last forward pass back through all layers,
+    /// returning the gradient with respect to the network's input
+    pub fn backpropagate(&mut self, reference_set: &Array2<f64>) -> Array2<f64> {
+        let delta = self.loss_gradient(reference_set);
+        self.backpropagate_delta(&delta)
+    }
+
+    /// Propagates a gradient with respect to the output back through all layers,
+    /// visiting the nodes in reverse topological order
+    pub fn backpropagate_delta(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        // Back from the target order into the order of the outputs
+        let stacked;
+        let delta = if self.heads.is_empty() {
+            delta
+        } else {
+            let columns = self
+                .heads
+                .iter()
+                .flat_map(|head| head.columns.iter().copied())
+                .collect::<Vec<_>>();
+            stacked = delta.select(Axis(0), &columns);
+            &stacked
+        };
+        let mut deltas: Vec<Option<Array2<f64>>> = vec![None; self.nodes.len()];
+        let mut offset = 0;
+        for output in self.outputs.iter() {
+            let rows = self.values[output.0].nrows();
+            accumulate(
+                &mut deltas[output.0],
+                delta.slice(s![offset..offset + rows, ..]).to_owned(),
+            );
+            offset += rows;
+        }
+
+        let input_rows = self
+            .nodes
+            .iter()
+            .zip(self.values.iter())
+            .filter_map(|(node, value)| match node {
+                Node::Input { offset, .. } => Some(offset + value.nrows()),
+                _ => None,
+            })
+            .max()
+            .unwrap_or(0);
+        let mut input_delta = Array2::zeros((input_rows, delta.ncols()));
+
+        for idx in (0..self.nodes.len()).rev() {
+            let Some(delta) = deltas[idx].take() else {
+                continue;
+            };
+            match &self.nodes[idx] {
+                Node::Input { offset, .. } => {
+                    let mut rows = input_delta.slice_mut(s![*offset..offset + delta.nrows(), ..]);
+                    rows += &delta;
+                }
+                Node::Layer { layer, input } => {
+                    let delta = self.layers[*layer].backward(&delta);
+                    accumulate(&mut deltas[input.0], delta);
+                }
+                Node::Add(from) => {
+                    for id in from {
+                        accumulate(&mut deltas[id.0], delta.clone());
+                    }
+                }
+                Node::Concat(from) => {
+                    let mut offset = 0;
+                    for id in from {
+                        let rows = self.values[id.0].nrows();
+                        accumulate(
+                            &mut deltas[id.0],
+                            delta.slice(s![offset..offset + rows, ..]).to_owned(),
+                        );
+                        offset += rows;
+                    }
+                }
+            }
+        }
+        input_delta
+    }
+
+    /// Gradient of the error of a single sample, per layer in the order of `Layer::params`
     pub fn calculate_gradient(
-        &self,
+        &mut self,
         input_set: &Array2<f64>,
         reference_set: &Array2<f64>,
-    ) -> (Vec<Array2<f64>>, Vec<Array2<f64>>) {
-        let mut nabla_b = self
-            .layers
-            .iter()
-            .map(|layer| &layer.biases)
-            .map(|b| Array2::zeros(b.raw_dim()))
-            .collect::<Vec<Array2<f64>>>();
-        let mut nabla_w = self
-            .layers
+    ) -> Vec<Vec<Array2<f64>>> {
+        self.zero_grads();
+        self.process_data(input_set);
+        self.backpropagate(reference_set);
+
+        self.layers
             .iter()
-            .map(|layer| &layer.weights)
-            .map(|w| Array2::zeros(w.raw_dim()))
-            .collect::<Vec<Array2<f64>>>();
-
-        // Last layer
-        let mut delta = (&self.layers.last().unwrap().state - reference_set)
-            * &self
-                .layers
-                .last()
-                .unwrap()
-                .activation
-                .derivative(&self.layers.last().unwrap().zs, None);
-
-        *nabla_b.last_mut().unwrap() = delta.clone();
-        *nabla_w.last_mut().unwrap() = if self.layers.len() >= 2 {
-            delta.dot(&self.layers[self.layers.len() - 2].state.t())
-        } else {
-            delta.dot(&input_set.t())
-        };
+            .map(|layer| layer.grads().into_iter().cloned().collect())
+            .collect()
+    }
 
-        let b_len = nabla_b.len();
-        let w_len = nabla_w.len();
+    /// Number of trainable values over all layers
+    pub fn param_count(&self) -> usize {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.params())
+            .map(|param| param.len())
+            .sum()
+    }
 
-        // Middle layers
-        for (idx, lrs) in self.layers.windows(3).rev().enumerate() {
-            let derivative = lrs[1].activation.derivative(&lrs[1].zs, None);
-            delta = &lrs[2].weights.t().dot(&delta) * &derivative;
+    /// All parameters in one vector, layer by layer in the order of `Layer::params`
+    pub fn params_vector(&self) -> Array1<f64> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.params())
+            .flat_map(|param| param.iter().copied())
+            .collect()
+    }
 
-            nabla_b[b_len - idx - 2] = delta.clone();
-            nabla_w[w_len - idx - 2] = delta.dot(&lrs[0].state.t());
+    /// Overwrites all parameters from a vector laid out like `params_vector`
+    pub fn set_params_vector(&mut self, values: &Array1<f64>) {
+        assert_eq!(values.len(), self.param_count(), "one value per parameter");
+        let mut values = values.iter();
+        for layer in self.layers.iter_mut() {
+            for param in layer.params_mut() {
+                param
+                    .value
+                    .iter_mut()
+                    .zip(values.by_ref())
+                    .for_each(|(value, new)| *value = *new);
+            }
         }
+    }
+
+    /// Accumulated gradients laid out like `params_vector`
+    pub fn gradient_vector(&self) -> Array1<f64> {
+        self.layers
+            .iter()
+            .flat_map(|layer| layer.grads())
+            .flat_map(|grad| grad.iter().copied())
+            .collect()
+    }
+
+    /// Sum of the regularization penalties of all layers
+    pub fn regularization_loss(&self) -> f64 {
+        self.layers
+            .iter()
+            .map(|layer| layer.regularization_loss())
+            .sum()
+    }
 
-        // First layer, if there is more than 1 layer
-        if self.layers.len() >= 2 {
-            let derivative = self
-                .layers
-                .first()
-                .unwrap()
-                .activation
-                .derivative(&self.layers[0].zs, None);
-            delta = &self.layers[1].weights.t().dot(&delta) * derivative;
-            nabla_b[0] = delta.clone();
-            nabla_w[0] = delta.dot(&input_set.t());
+    pub fn set_regularization(&mut self, regularization: Regularization) {
+        self.layers
+            .iter_mut()
+            .for_each(|layer| layer.set_regularization(regularization));
+    }
+}
+
+impl Predictor for Network {
+    fn predict(&mut self, input: &Array2<f64>) -> Array2<f64> {
+        self.process_data(input);
+        self.output().clone()
+    }
+}
+
+fn join(ids: &[NodeId], values: &[Array2<f64>]) -> Array2<f64> {
+    match ids {
+        [] => Array2::zeros((0, values.first().map_or(0, |value| value.ncols()))),
+        [id] => values[id.0].clone(),
+        _ => {
+            let views = ids.iter().map(|id| values[id.0].view()).collect::<Vec<_>>();
+            concatenate(Axis(0), &views).unwrap()
         }
+    }
+}
+
+// Gradients of values used more than once add up
+fn accumulate(slot: &mut Option<Array2<f64>>, delta: Array2<f64>) {
+    match slot {
+        Some(sum) => *sum += &delta,
+        None => *slot = Some(delta),
+    }
+}
 
-        (nabla_b, nabla_w)
+/// Plain gradient step over the parameters, with the penalty gradient, decay and
+/// max-norm of `reg` applied to the weights (and the biases, if it says so)
+pub(crate) fn step_params(params: Vec<Param<'_>>, reg: &Regularization, eta: f64) {
+    for param in params {
+        let grad = reg.regularized_gradient(&param);
+        *param.value = &*param.value - &(grad * eta);
+        reg.after_step(param.kind, param.value, eta);
     }
 }
@@ -0,0 +1,249 @@
+use super::conv::{output_len, Shape};
+use crate::persistence::SavedLayer;
+use crate::traits::Layer;
+use ndarray::Array2;
+
+#[derive(Debug, Clone, Copy)]
+struct Windows {
+    input_shape: Shape,
+    size: (usize, usize),
+    stride: (usize, usize),
+}
+
+impl Windows {
+    fn output_shape(&self) -> Shape {
+        Shape::new(
+            self.input_shape.channels,
+            output_len(self.input_shape.height, self.size.0, self.stride.0, 0),
+            output_len(self.input_shape.width, self.size.1, self.stride.1, 0),
+        )
+    }
+
+    // Calls `f` with the output index and the input indices of every window
+    fn for_each(&self, mut f: impl FnMut(usize, &mut dyn Iterator<Item = usize>)) {
+        let out = self.output_shape();
+        for channel in 0..out.channels {
+            for oy in 0..out.height {
+                for ox in 0..out.width {
+                    let mut window = (0..self.size.0).flat_map(|ky| {
+                        (0..self.size.1).map(move |kx| {
+                            self.input_shape.index(
+                                channel,
+                                oy * self.stride.0 + ky,
+                                ox * self.stride.1 + kx,
+                            )
+                        })
+                    });
+                    f(out.index(channel, oy, ox), &mut window);
+                }
+            }
+        }
+    }
+}
+
+/// Takes the largest value of every window, channel by channel. Windows don't overlap
+/// unless the stride is set lower than the window size.
+#[derive(Debug, Clone)]
+pub struct MaxPool {
+    windows: Windows,
+    // Input row the maximum of every output came from
+    argmax: Array2<usize>,
+    state: Array2<f64>,
+}
+
+impl MaxPool {
+    pub fn new(input_shape: Shape, size: (usize, usize)) -> Self {
+        Self::with_stride(input_shape, size, size)
+    }
+
+    pub fn with_stride(input_shape: Shape, size: (usize, usize), stride: (usize, usize)) -> Self {
+        Self {
+            windows: Windows {
+                input_shape,
+                size,
+                stride,
+            },
+            argmax: Array2::zeros((0, 0)),
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    pub fn input_shape(&self) -> Shape {
+        self.windows.input_shape
+    }
+
+    pub fn output_shape(&self) -> Shape {
+        self.windows.output_shape()
+    }
+}
+
+impl Layer for MaxPool {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        let rows = self.output_shape().size();
+        self.argmax = Array2::zeros((rows, input.ncols()));
+        self.state = Array2::zeros((rows, input.ncols()));
+        for (sample, column) in input.columns().into_iter().enumerate() {
+            self.windows.for_each(|out, window| {
+                let best = window
+                    .max_by(|a, b| column[*a].total_cmp(&column[*b]))
+                    .unwrap();
+                self.argmax[[out, sample]] = best;
+                self.state[[out, sample]] = column[best];
+            });
+        }
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        let mut input = Array2::zeros((self.windows.input_shape.size(), delta.ncols()));
+        for ((out, sample), value) in delta.indexed_iter() {
+            input[[self.argmax[[out, sample]], sample]] += value;
+        }
+        input
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::MaxPool {
+            input_shape: self.windows.input_shape,
+            size: self.windows.size,
+            stride: self.windows.stride,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Averages every window, channel by channel
+#[derive(Debug, Clone)]
+pub struct AvgPool {
+    windows: Windows,
+    state: Array2<f64>,
+}
+
+impl AvgPool {
+    pub fn new(input_shape: Shape, size: (usize, usize)) -> Self {
+        Self::with_stride(input_shape, size, size)
+    }
+
+    pub fn with_stride(input_shape: Shape, size: (usize, usize), stride: (usize, usize)) -> Self {
+        Self {
+            windows: Windows {
+                input_shape,
+                size,
+                stride,
+            },
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    pub fn input_shape(&self) -> Shape {
+        self.windows.input_shape
+    }
+
+    pub fn output_shape(&self) -> Shape {
+        self.windows.output_shape()
+    }
+
+    fn area(&self) -> f64 {
+        (self.windows.size.0 * self.windows.size.1) as f64
+    }
+}
+
+impl Layer for AvgPool {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        let area = self.area();
+        self.state = Array2::zeros((self.output_shape().size(), input.ncols()));
+        for (sample, column) in input.columns().into_iter().enumerate() {
+            self.windows.for_each(|out, window| {
+                self.state[[out, sample]] = window.map(|i| column[i]).sum::<f64>() / area;
+            });
+        }
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        let area = self.area();
+        let mut input = Array2::zeros((self.windows.input_shape.size(), delta.ncols()));
+        for sample in 0..delta.ncols() {
+            self.windows.for_each(|out, window| {
+                let share = delta[[out, sample]] / area;
+                window.for_each(|i| input[[i, sample]] += share);
+            });
+        }
+        input
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::AvgPool {
+            input_shape: self.windows.input_shape,
+            size: self.windows.size,
+            stride: self.windows.stride,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
+
+/// Marks the move from shaped to plain feature data. Samples are already stored as
+/// flat columns, so values pass through unchanged, only their size is checked.
+#[derive(Debug, Clone)]
+pub struct Flatten {
+    pub input_shape: Shape,
+    state: Array2<f64>,
+}
+
+impl Flatten {
+    pub fn new(input_shape: Shape) -> Self {
+        Self {
+            input_shape,
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.input_shape.size()
+    }
+}
+
+impl Layer for Flatten {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        assert_eq!(
+            input.nrows(),
+            self.input_shape.size(),
+            "input does not match {:?}",
+            self.input_shape
+        );
+        self.state = input.clone();
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        delta.clone()
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Flatten {
+            input_shape: self.input_shape,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
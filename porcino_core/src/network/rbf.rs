@@ -0,0 +1,142 @@
+use crate::clustering::{kmeans, squared_distance};
+use crate::data::stack_samples;
+use crate::persistence::SavedLayer;
+use crate::traits::Layer;
+use ndarray::{Array1, Array2, Axis};
+use porcino_data::parse::TrainingSample;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum RbfKernel {
+    /// `exp(-r² / 2σ²)`
+    #[default]
+    Gaussian,
+    /// `√(r² + σ²)`
+    Multiquadric,
+}
+
+/// Hidden layer of a radial basis function network. Every unit responds to the distance
+/// of the input from its center, scaled by its width. Centers and widths are fixed, so
+/// training only moves the layers after it, e.g. a linear output layer solved by
+/// `ExtremeLearningMachine` or trained by gradient descent.
+#[derive(Debug, Clone)]
+pub struct Rbf {
+    /// One center per row
+    pub centers: Array2<f64>,
+    /// Width of every center, one per row
+    pub widths: Array2<f64>,
+    pub kernel: RbfKernel,
+    input: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl Rbf {
+    pub fn new(centers: Array2<f64>, widths: Array2<f64>, kernel: RbfKernel) -> Self {
+        assert_eq!(widths.dim(), (centers.nrows(), 1), "one width per center");
+        Self {
+            centers,
+            widths,
+            kernel,
+            input: Array2::zeros((0, 0)),
+            state: Array2::zeros((0, 0)),
+        }
+    }
+
+    /// Places `count` centers on the inputs of `data` by k-means. The width of a center
+    /// is the RMS distance to its two nearest other centers, or the RMS distance of its
+    /// samples when it is the only one.
+    pub fn fit(data: &[TrainingSample], count: usize, kernel: RbfKernel, seed: u64) -> Self {
+        let (inputs, _) = stack_samples(data);
+        let clusters = kmeans(&inputs, count, 100, seed);
+        let centers = &clusters.centers;
+
+        let widths = clusters
+            .members()
+            .iter()
+            .enumerate()
+            .map(|(center, members)| {
+                let mut distances = centers
+                    .rows()
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(other, _)| *other != center)
+                    .map(|(_, other)| squared_distance(other, centers.row(center)))
+                    .collect::<Vec<_>>();
+                if distances.is_empty() {
+                    distances = members
+                        .iter()
+                        .map(|&sample| squared_distance(inputs.column(sample), centers.row(center)))
+                        .collect();
+                } else {
+                    distances.sort_by(f64::total_cmp);
+                    distances.truncate(2);
+                }
+                let width = (distances.iter().sum::<f64>() / distances.len() as f64).sqrt();
+                if width > 0.0 {
+                    width
+                } else {
+                    1.0
+                }
+            })
+            .collect::<Array1<f64>>();
+
+        Self::new(
+            clusters.centers.clone(),
+            widths.insert_axis(Axis(1)),
+            kernel,
+        )
+    }
+
+    pub fn outputs(&self) -> usize {
+        self.centers.nrows()
+    }
+}
+
+impl Layer for Rbf {
+    fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64> {
+        assert_eq!(input.nrows(), self.centers.ncols());
+        let mut state = Array2::zeros((self.outputs(), input.ncols()));
+        for ((center, sample), value) in state.indexed_iter_mut() {
+            let r2 = squared_distance(input.column(sample), self.centers.row(center));
+            let width = self.widths[[center, 0]];
+            *value = match self.kernel {
+                RbfKernel::Gaussian => (-r2 / (2.0 * width * width)).exp(),
+                RbfKernel::Multiquadric => (r2 + width * width).sqrt(),
+            };
+        }
+        self.input = input.clone();
+        self.state = state;
+        &self.state
+    }
+
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64> {
+        // ∂φ/∂x is a multiple of (c - x) for both kernels
+        let mut coefficients = delta.clone();
+        for ((center, sample), coefficient) in coefficients.indexed_iter_mut() {
+            let value = self.state[[center, sample]];
+            let width = self.widths[[center, 0]];
+            *coefficient *= match self.kernel {
+                RbfKernel::Gaussian => value / (width * width),
+                RbfKernel::Multiquadric => -1.0 / value,
+            };
+        }
+        let totals = coefficients.sum_axis(Axis(0));
+        self.centers.t().dot(&coefficients) - &self.input * &totals
+    }
+
+    fn output(&self) -> &Array2<f64> {
+        &self.state
+    }
+
+    fn save(&self) -> SavedLayer {
+        SavedLayer::Rbf {
+            centers: self.centers.clone(),
+            widths: self.widths.clone(),
+            kernel: self.kernel,
+        }
+    }
+
+    fn box_clone(&self) -> Box<dyn Layer> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,80 @@
+use crate::traits::{Param, ParamKind};
+use ndarray::{Array2, Axis};
+use serde::{Deserialize, Serialize};
+
+/// Penalties and constraints applied to a layer's parameters during the update.
+/// The L2 penalty is `l2 / 2 * sum(w^2)`, so that its gradient is `l2 * w`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Regularization {
+    pub l1: f64,
+    pub l2: f64,
+    /// Applies L2 as weight decay directly to the weights after each update, shrinking
+    /// them by `eta * l2`, instead of through the gradient and the loss
+    pub decoupled: bool,
+    /// Upper bound on the norm of every neuron's incoming weight vector
+    pub max_norm: Option<f64>,
+    /// L1 and L2 leave biases alone unless this is set
+    pub include_biases: bool,
+}
+
+impl Regularization {
+    pub fn penalty(&self, param: &Array2<f64>) -> f64 {
+        let l2 = if self.decoupled { 0.0 } else { self.l2 };
+        self.l1 * param.iter().map(|w| w.abs()).sum::<f64>()
+            + l2 / 2.0 * param.iter().map(|w| w * w).sum::<f64>()
+    }
+
+    pub fn gradient(&self, param: &Array2<f64>) -> Array2<f64> {
+        let l2 = if self.decoupled { 0.0 } else { self.l2 };
+        param.mapv(|w| {
+            let sign = if w == 0.0 { 0.0 } else { w.signum() };
+            self.l1 * sign + l2 * w
+        })
+    }
+
+    /// Whether L1 and L2 apply to parameters of this kind
+    pub fn applies_to(&self, kind: ParamKind) -> bool {
+        match kind {
+            ParamKind::Weights => true,
+            ParamKind::Biases => self.include_biases,
+            ParamKind::Other => false,
+        }
+    }
+
+    /// Gradient of the parameter's loss plus the one of its penalty, if it applies
+    pub fn regularized_gradient(&self, param: &Param<'_>) -> Array2<f64> {
+        if self.applies_to(param.kind) {
+            param.grad + &self.gradient(param.value)
+        } else {
+            param.grad.clone()
+        }
+    }
+
+    /// Decoupled decay and the max-norm constraint, to apply after every update
+    pub fn after_step(&self, kind: ParamKind, param: &mut Array2<f64>, eta: f64) {
+        if self.applies_to(kind) {
+            self.decay(param, eta);
+        }
+        if kind == ParamKind::Weights {
+            self.constrain(param);
+        }
+    }
+
+    pub fn decay(&self, param: &mut Array2<f64>, eta: f64) {
+        if self.decoupled && self.l2 > 0.0 {
+            *param *= 1.0 - eta * self.l2;
+        }
+    }
+
+    /// Rescales every row of the weight matrix whose norm exceeds `max_norm`
+    pub fn constrain(&self, weights: &mut Array2<f64>) {
+        if let Some(max_norm) = self.max_norm {
+            weights.axis_iter_mut(Axis(0)).for_each(|mut row| {
+                let norm = row.iter().map(|w| w * w).sum::<f64>().sqrt();
+                if norm > max_norm {
+                    row *= max_norm / norm;
+                }
+            });
+        }
+    }
+}
@@ -0,0 +1,317 @@
+//! Probabilistic and general regression neural networks. Both keep the training samples
+//! as the centers of Gaussian kernels and predict from Parzen-window estimates, so there
+//! is nothing to train but the smoothing parameter σ, which is chosen by leave-one-out.
+use crate::data::stack_samples;
+use crate::traits::Predictor;
+use ndarray::{Array1, Array2, ArrayView1, Axis};
+use porcino_data::parse::{get_sampled_data, TaggedData, TrainingSample};
+
+// Candidates of the first σ search, spread geometrically around the typical distance
+const COARSE_STEPS: usize = 40;
+const FINE_STEPS: usize = 20;
+
+/// Kernel centers and what the samples they came from were labelled with
+#[derive(Debug, Clone)]
+struct Centers {
+    inputs: Array2<f64>,
+    targets: Array2<f64>,
+}
+
+impl Centers {
+    fn new(data: &[TrainingSample]) -> Self {
+        assert!(!data.is_empty(), "kernel models need samples to memorize");
+        let (inputs, targets) = stack_samples(data);
+        Self { inputs, targets }
+    }
+
+    fn len(&self) -> usize {
+        self.inputs.ncols()
+    }
+
+    // Squared distances from every center to `input`
+    fn distances(&self, input: ArrayView1<f64>) -> Array1<f64> {
+        self.inputs
+            .columns()
+            .into_iter()
+            .map(|center| {
+                center
+                    .iter()
+                    .zip(input.iter())
+                    .map(|(c, x)| (c - x) * (c - x))
+                    .sum()
+            })
+            .collect()
+    }
+
+    // All pairs of training samples, for leave-one-out
+    fn pairwise(&self) -> Array2<f64> {
+        let mut distances = Array2::zeros((self.len(), self.len()));
+        for (i, column) in self.inputs.columns().into_iter().enumerate() {
+            distances.row_mut(i).assign(&self.distances(column));
+        }
+        distances
+    }
+}
+
+// Kernel weights of the centers, scaled so that the closest one weighs 1. Centers for
+// which `skip` holds are left out with a weight of 0.
+fn weights(distances: ArrayView1<f64>, sigma: f64, skip: Option<usize>) -> Array1<f64> {
+    let closest = distances
+        .iter()
+        .enumerate()
+        .filter(|(idx, _)| Some(*idx) != skip)
+        .map(|(_, d)| *d)
+        .fold(f64::INFINITY, f64::min);
+    let spread = 2.0 * sigma * sigma;
+    distances
+        .iter()
+        .enumerate()
+        .map(|(idx, d)| {
+            if Some(idx) == skip {
+                0.0
+            } else {
+                (-(d - closest) / spread).exp()
+            }
+        })
+        .collect()
+}
+
+// Picks the σ with the lowest leave-one-out score, first over a coarse geometric grid
+// around the median distance between samples, then over a finer one around the best
+fn tune(pairwise: &Array2<f64>, score: impl Fn(f64) -> (f64, f64)) -> f64 {
+    let mut off_diagonal = pairwise
+        .indexed_iter()
+        .filter(|((i, j), d)| i != j && **d > 0.0)
+        .map(|(_, d)| d.sqrt())
+        .collect::<Vec<_>>();
+    if off_diagonal.is_empty() {
+        return 1.0;
+    }
+    off_diagonal.sort_by(f64::total_cmp);
+    let median = off_diagonal[off_diagonal.len() / 2];
+
+    let search = |low: f64, high: f64, steps: usize| {
+        (0..steps)
+            .map(|step| low * (high / low).powf(step as f64 / (steps - 1) as f64))
+            .map(|sigma| (sigma, score(sigma)))
+            .min_by(|a, b| a.1 .0.total_cmp(&b.1 .0).then(a.1 .1.total_cmp(&b.1 .1)))
+            .map(|(sigma, _)| sigma)
+            .unwrap()
+    };
+    let (low, high) = (median * 1e-3, median * 10.0);
+    let coarse = search(low, high, COARSE_STEPS);
+    let ratio = (high / low).powf(1.0 / (COARSE_STEPS - 1) as f64);
+    search(coarse / ratio, coarse * ratio, FINE_STEPS)
+}
+
+/// Probabilistic neural network, a Bayes classifier over Parzen estimates of the class
+/// densities. Targets are read like the metrics read them, one-hot when they have more
+/// than one row and as the label index otherwise, and predictions come in the same form:
+/// class probabilities or the most probable label.
+#[derive(Debug, Clone)]
+pub struct Pnn {
+    pub sigma: f64,
+    centers: Centers,
+    // Class of every center
+    labels: Vec<usize>,
+    classes: usize,
+    one_hot: bool,
+}
+
+impl Pnn {
+    /// Memorizes `data` and tunes σ by leave-one-out
+    pub fn fit(data: &[TrainingSample]) -> Self {
+        let mut pnn = Self::with_sigma(data, 1.0);
+        pnn.tune();
+        pnn
+    }
+
+    pub fn from_tagged(data: &TaggedData) -> Self {
+        Self::fit(&get_sampled_data(data))
+    }
+
+    /// Memorizes `data` with a fixed σ
+    pub fn with_sigma(data: &[TrainingSample], sigma: f64) -> Self {
+        let centers = Centers::new(data);
+        let one_hot = centers.targets.nrows() > 1;
+        let labels: Vec<usize> = centers
+            .targets
+            .columns()
+            .into_iter()
+            .map(|target| label(target, one_hot))
+            .collect();
+        let classes = if one_hot {
+            centers.targets.nrows()
+        } else {
+            labels.iter().max().map_or(1, |max| max + 1)
+        };
+        Self {
+            sigma,
+            centers,
+            labels,
+            classes,
+            one_hot,
+        }
+    }
+
+    pub fn classes(&self) -> usize {
+        self.classes
+    }
+
+    /// Probability of every class for one input
+    pub fn probabilities(&self, input: ArrayView1<f64>) -> Array1<f64> {
+        let distances = self.centers.distances(input);
+        self.posterior(weights(distances.view(), self.sigma, None))
+    }
+
+    fn posterior(&self, weights: Array1<f64>) -> Array1<f64> {
+        let mut sums = Array1::<f64>::zeros(self.classes);
+        for (weight, label) in weights.iter().zip(self.labels.iter()) {
+            sums[*label] += weight;
+        }
+        let total = sums.sum();
+        if total > 0.0 {
+            sums / total
+        } else {
+            Array1::from_elem(self.classes, 1.0 / self.classes as f64)
+        }
+    }
+
+    /// Leave-one-out misclassifications and mean negative log probability of the true class
+    pub fn leave_one_out(&self, sigma: f64) -> (f64, f64) {
+        self.leave_one_out_with(&self.centers.pairwise(), sigma)
+    }
+
+    fn leave_one_out_with(&self, pairwise: &Array2<f64>, sigma: f64) -> (f64, f64) {
+        if self.centers.len() < 2 {
+            return (0.0, 0.0);
+        }
+        let mut errors = 0.0;
+        let mut log_loss = 0.0;
+        for (sample, distances) in pairwise.axis_iter(Axis(0)).enumerate() {
+            let probabilities = self.posterior(weights(distances, sigma, Some(sample)));
+            let actual = self.labels[sample];
+            if argmax(probabilities.view()) != actual {
+                errors += 1.0;
+            }
+            log_loss -= probabilities[actual].max(1e-15).ln();
+        }
+        (errors, log_loss / self.centers.len() as f64)
+    }
+
+    /// Sets σ to the one with the fewest leave-one-out misclassifications, the lower log
+    /// loss breaking ties
+    pub fn tune(&mut self) -> f64 {
+        let pairwise = self.centers.pairwise();
+        self.sigma = tune(&pairwise, |sigma| self.leave_one_out_with(&pairwise, sigma));
+        self.sigma
+    }
+}
+
+impl Predictor for Pnn {
+    fn predict(&mut self, input: &Array2<f64>) -> Array2<f64> {
+        let rows = if self.one_hot { self.classes } else { 1 };
+        let mut output = Array2::zeros((rows, input.ncols()));
+        for (sample, column) in input.columns().into_iter().enumerate() {
+            let probabilities = self.probabilities(column);
+            if self.one_hot {
+                output.column_mut(sample).assign(&probabilities);
+            } else {
+                output[[0, sample]] = argmax(probabilities.view()) as f64;
+            }
+        }
+        output
+    }
+}
+
+/// General regression neural network, the Nadaraya–Watson kernel regression: the
+/// prediction is the mean of the training targets weighted by their kernels.
+#[derive(Debug, Clone)]
+pub struct Grnn {
+    pub sigma: f64,
+    centers: Centers,
+}
+
+impl Grnn {
+    /// Memorizes `data` and tunes σ by leave-one-out
+    pub fn fit(data: &[TrainingSample]) -> Self {
+        let mut grnn = Self::with_sigma(data, 1.0);
+        grnn.tune();
+        grnn
+    }
+
+    pub fn from_tagged(data: &TaggedData) -> Self {
+        Self::fit(&get_sampled_data(data))
+    }
+
+    /// Memorizes `data` with a fixed σ
+    pub fn with_sigma(data: &[TrainingSample], sigma: f64) -> Self {
+        Self {
+            sigma,
+            centers: Centers::new(data),
+        }
+    }
+
+    fn estimate(&self, weights: Array1<f64>) -> Array1<f64> {
+        let total = weights.sum();
+        self.centers.targets.dot(&weights) / total
+    }
+
+    /// Leave-one-out mean squared error of all targets
+    pub fn leave_one_out(&self, sigma: f64) -> f64 {
+        self.leave_one_out_with(&self.centers.pairwise(), sigma)
+    }
+
+    fn leave_one_out_with(&self, pairwise: &Array2<f64>, sigma: f64) -> f64 {
+        if self.centers.len() < 2 {
+            return 0.0;
+        }
+        let mut error = 0.0;
+        for (sample, distances) in pairwise.axis_iter(Axis(0)).enumerate() {
+            let estimate = self.estimate(weights(distances, sigma, Some(sample)));
+            let difference = estimate - self.centers.targets.column(sample);
+            error += difference.dot(&difference);
+        }
+        error / self.centers.len() as f64
+    }
+
+    /// Sets σ to the one with the lowest leave-one-out error
+    pub fn tune(&mut self) -> f64 {
+        let pairwise = self.centers.pairwise();
+        self.sigma = tune(&pairwise, |sigma| {
+            (self.leave_one_out_with(&pairwise, sigma), 0.0)
+        });
+        self.sigma
+    }
+}
+
+impl Predictor for Grnn {
+    fn predict(&mut self, input: &Array2<f64>) -> Array2<f64> {
+        let mut output = Array2::zeros((self.centers.targets.nrows(), input.ncols()));
+        for (sample, column) in input.columns().into_iter().enumerate() {
+            let distances = self.centers.distances(column);
+            let estimate = self.estimate(weights(distances.view(), self.sigma, None));
+            output.column_mut(sample).assign(&estimate);
+        }
+        output
+    }
+}
+
+fn label(target: ArrayView1<f64>, one_hot: bool) -> usize {
+    if one_hot {
+        argmax(target)
+    } else {
+        target
+            .iter()
+            .next()
+            .map_or(0, |value| value.round().max(0.0) as usize)
+    }
+}
+
+fn argmax(values: ArrayView1<f64>) -> usize {
+    values
+        .iter()
+        .enumerate()
+        .max_by(|a, b| a.1.total_cmp(b.1))
+        .map_or(0, |(index, _)| index)
+}
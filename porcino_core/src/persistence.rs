@@ -0,0 +1,255 @@
+use crate::enums::InitializationMethods;
+use crate::network::{
+    Activations, AvgPool, BatchNorm, Conv2D, Dropout, Embedding, ExprLayer, FFLayer, Flatten, Head,
+    MaxPool, Network, Node, NodeId, Rbf, RbfKernel, Regularization, Shape,
+};
+use crate::traits::Layer;
+use ndarray::Array2;
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fs::File;
+use std::io::{BufReader, BufWriter};
+use std::path::PathBuf;
+
+/// Everything needed to rebuild a layer. Values that only live during training,
+/// like gradients or dropout masks, are left out.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SavedLayer {
+    Dense {
+        weights: Array2<f64>,
+        biases: Array2<f64>,
+        activation: Activations,
+        regularization: Regularization,
+    },
+    Dropout {
+        rate: f64,
+    },
+    BatchNorm {
+        gamma: Array2<f64>,
+        beta: Array2<f64>,
+        running_mean: Array2<f64>,
+        running_var: Array2<f64>,
+        momentum: f64,
+        eps: f64,
+    },
+    /// Both `Conv2D` and `Conv1D`, the latter is restored as the equivalent `Conv2D`
+    Conv {
+        input_shape: Shape,
+        kernel: (usize, usize),
+        stride: (usize, usize),
+        padding: (usize, usize),
+        weights: Array2<f64>,
+        biases: Array2<f64>,
+        activation: Activations,
+        regularization: Regularization,
+    },
+    MaxPool {
+        input_shape: Shape,
+        size: (usize, usize),
+        stride: (usize, usize),
+    },
+    AvgPool {
+        input_shape: Shape,
+        size: (usize, usize),
+        stride: (usize, usize),
+    },
+    Flatten {
+        input_shape: Shape,
+    },
+    Embedding {
+        categories: Vec<Option<usize>>,
+        tables: Vec<Array2<f64>>,
+        regularization: Regularization,
+    },
+    /// `ExprLayer` with the name its expression is registered by
+    Expression {
+        name: String,
+        params: Vec<Array2<f64>>,
+        regularization: Regularization,
+    },
+    Rbf {
+        centers: Array2<f64>,
+        widths: Array2<f64>,
+        kernel: RbfKernel,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedNetwork {
+    pub layers: Vec<SavedLayer>,
+    /// Networks saved without a graph are chains of their layers
+    #[serde(default)]
+    pub nodes: Vec<Node>,
+    #[serde(default)]
+    pub outputs: Vec<NodeId>,
+    #[serde(default)]
+    pub heads: Vec<Head>,
+}
+
+impl SavedLayer {
+    /// Fails on expression layers whose name isn't registered
+    pub fn into_layer(self) -> Result<Box<dyn Layer>, String> {
+        Ok(match self {
+            SavedLayer::Dense {
+                weights,
+                biases,
+                activation,
+                regularization,
+            } => {
+                let mut layer = FFLayer::new(
+                    weights.ncols(),
+                    weights.nrows(),
+                    InitializationMethods::Zero,
+                    activation.function(),
+                );
+                layer.weights = weights;
+                layer.biases = biases;
+                layer.regularization = regularization;
+                Box::new(layer)
+            }
+            SavedLayer::Dropout { rate } => Box::new(Dropout::new(rate)),
+            SavedLayer::BatchNorm {
+                gamma,
+                beta,
+                running_mean,
+                running_var,
+                momentum,
+                eps,
+            } => {
+                let mut layer = BatchNorm::new(gamma.nrows());
+                layer.gamma = gamma;
+                layer.beta = beta;
+                layer.running_mean = running_mean;
+                layer.running_var = running_var;
+                layer.momentum = momentum;
+                layer.eps = eps;
+                Box::new(layer)
+            }
+            SavedLayer::Conv {
+                input_shape,
+                kernel,
+                stride,
+                padding,
+                weights,
+                biases,
+                activation,
+                regularization,
+            } => {
+                let mut layer = Conv2D::new(
+                    input_shape,
+                    weights.nrows(),
+                    kernel,
+                    InitializationMethods::Zero,
+                    activation.function(),
+                )
+                .with_stride(stride)
+                .with_padding(padding);
+                layer.weights = weights;
+                layer.biases = biases;
+                layer.regularization = regularization;
+                Box::new(layer)
+            }
+            SavedLayer::MaxPool {
+                input_shape,
+                size,
+                stride,
+            } => Box::new(MaxPool::with_stride(input_shape, size, stride)),
+            SavedLayer::AvgPool {
+                input_shape,
+                size,
+                stride,
+            } => Box::new(AvgPool::with_stride(input_shape, size, stride)),
+            SavedLayer::Flatten { input_shape } => Box::new(Flatten::new(input_shape)),
+            SavedLayer::Embedding {
+                categories,
+                tables,
+                regularization,
+            } => {
+                let dim = tables.first().map_or(0, |t| t.ncols());
+                let mut layer = Embedding::new(&categories, dim, InitializationMethods::Zero);
+                layer.tables = tables;
+                layer.regularization = regularization;
+                Box::new(layer)
+            }
+            SavedLayer::Expression {
+                name,
+                params,
+                regularization,
+            } => {
+                let forward = ExprLayer::registered(&name).ok_or_else(|| {
+                    format!("expression `{name}` needs to be registered before reading the layer")
+                })?;
+                let mut layer = ExprLayer::new(&name, forward, params);
+                layer.regularization = regularization;
+                Box::new(layer)
+            }
+            SavedLayer::Rbf {
+                centers,
+                widths,
+                kernel,
+            } => Box::new(Rbf::new(centers, widths, kernel)),
+        })
+    }
+}
+
+impl From<&Network> for SavedNetwork {
+    fn from(network: &Network) -> Self {
+        Self {
+            layers: network.layers.iter().map(|layer| layer.save()).collect(),
+            nodes: network.nodes().to_vec(),
+            outputs: network.outputs().to_vec(),
+            heads: network.heads().to_vec(),
+        }
+    }
+}
+
+/// Fails on graphs that can't be run, e.g. of a hand-edited file, and on unregistered
+/// expression layers
+impl TryFrom<SavedNetwork> for Network {
+    type Error = Box<dyn Error>;
+
+    fn try_from(saved: SavedNetwork) -> Result<Self, Self::Error> {
+        let layers = saved
+            .layers
+            .into_iter()
+            .map(|layer| layer.into_layer())
+            .collect::<Result<_, _>>()?;
+        let mut network = if saved.nodes.is_empty() {
+            Network::from_layers(layers)
+        } else {
+            Network::try_from_graph(layers, saved.nodes, saved.outputs)?
+        };
+        if !saved.heads.is_empty() {
+            let mut columns: Vec<usize> = saved
+                .heads
+                .iter()
+                .flat_map(|head| head.columns.iter().copied())
+                .collect();
+            columns.sort_unstable();
+            if saved.heads.len() != network.outputs().len()
+                || columns
+                    .iter()
+                    .enumerate()
+                    .any(|(idx, column)| idx != *column)
+            {
+                return Err("heads must match the outputs and split the target columns".into());
+            }
+            network.set_heads(saved.heads);
+        }
+        Ok(network)
+    }
+}
+
+pub fn save(network: &Network, path: &PathBuf) -> Result<(), Box<dyn Error>> {
+    let file = File::create(path)?;
+    let writer = BufWriter::new(file);
+    serde_json::to_writer(writer, &SavedNetwork::from(network))?;
+    Ok(())
+}
+
+pub fn read(path: &PathBuf) -> Result<Network, Box<dyn Error>> {
+    let file = File::open(path)?;
+    let reader = BufReader::new(file);
+
+    serde_json::from_reader::<_, SavedNetwork>(reader)?.try_into()
+}
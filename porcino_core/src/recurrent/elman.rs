@@ -0,0 +1,144 @@
+use crate::enums::InitializationMethods;
+use crate::traits::{Activation, Param, ParamKind, RecurrentLayer};
+use ndarray::{Array2, Axis};
+use std::fmt::{Debug, Formatter};
+
+/// Simple recurrent layer, h_t = act(W·x_t + U·h_{t-1} + b)
+#[derive(Clone)]
+pub struct Elman {
+    pub weights: Array2<f64>,
+    pub recurrent_weights: Array2<f64>,
+    pub biases: Array2<f64>,
+    pub nabla_w: Array2<f64>,
+    pub nabla_u: Array2<f64>,
+    pub nabla_b: Array2<f64>,
+    pub activation: &'static (dyn Activation + Send + Sync),
+    hidden: Option<Array2<f64>>,
+    steps: Vec<Step>,
+}
+
+#[derive(Clone)]
+struct Step {
+    input: Array2<f64>,
+    previous: Array2<f64>,
+    zs: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl Debug for Elman {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_fmt(format_args!(
+            "Elman {:?} {:?}",
+            self.weights, self.recurrent_weights
+        ))
+    }
+}
+
+impl Elman {
+    pub fn new(
+        inputs: usize,
+        hidden: usize,
+        weight_init: InitializationMethods,
+        activation: &'static (dyn Activation + Send + Sync),
+    ) -> Self {
+        Self {
+            weights: weight_init.scaled((hidden, inputs), hidden),
+            recurrent_weights: weight_init.scaled((hidden, hidden), hidden),
+            biases: Array2::zeros((hidden, 1)),
+            nabla_w: Array2::zeros((hidden, inputs)),
+            nabla_u: Array2::zeros((hidden, hidden)),
+            nabla_b: Array2::zeros((hidden, 1)),
+            activation,
+            hidden: None,
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl RecurrentLayer for Elman {
+    fn reset_state(&mut self) {
+        self.hidden = None;
+    }
+
+    fn forward(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        self.steps.clear();
+        for input in inputs {
+            let previous = self
+                .hidden
+                .take()
+                .unwrap_or_else(|| Array2::zeros((self.hidden_size(), input.ncols())));
+            let zs =
+                &(&self.weights.dot(input) + &self.recurrent_weights.dot(&previous)) + &self.biases;
+            let state = self.activation.function(&zs);
+            self.hidden = Some(state.clone());
+            self.steps.push(Step {
+                input: input.clone(),
+                previous,
+                zs,
+                state,
+            });
+        }
+        self.steps.iter().map(|step| step.state.clone()).collect()
+    }
+
+    fn backward(&mut self, deltas: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let mut next: Option<Array2<f64>> = None;
+        let mut input_deltas = Vec::with_capacity(self.steps.len());
+        for (step, delta) in self.steps.iter().zip(deltas).rev() {
+            let delta = match next {
+                Some(next) => delta + &next,
+                None => delta.clone(),
+            };
+            let delta = delta * &self.activation.derivative(&step.zs, Some(&step.state));
+            self.nabla_w += &delta.dot(&step.input.t());
+            self.nabla_u += &delta.dot(&step.previous.t());
+            self.nabla_b += &delta.sum_axis(Axis(1)).insert_axis(Axis(1));
+            input_deltas.push(self.weights.t().dot(&delta));
+            next = Some(self.recurrent_weights.t().dot(&delta));
+        }
+        input_deltas.reverse();
+        input_deltas
+    }
+
+    fn hidden_size(&self) -> usize {
+        self.biases.nrows()
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.weights, &self.recurrent_weights, &self.biases]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_w, &self.nabla_u, &self.nabla_b]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.weights,
+                grad: &self.nabla_w,
+            },
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.recurrent_weights,
+                grad: &self.nabla_u,
+            },
+            Param {
+                kind: ParamKind::Biases,
+                value: &mut self.biases,
+                grad: &self.nabla_b,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_w.fill(0.0);
+        self.nabla_u.fill(0.0);
+        self.nabla_b.fill(0.0);
+    }
+
+    fn box_clone(&self) -> Box<dyn RecurrentLayer> {
+        Box::new(self.clone())
+    }
+}
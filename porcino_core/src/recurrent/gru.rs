@@ -0,0 +1,180 @@
+use crate::enums::InitializationMethods;
+use crate::traits::{Param, ParamKind, RecurrentLayer};
+use ndarray::{s, Array2, Axis};
+
+fn sigmoid(v: f64) -> f64 {
+    1.0 / (1.0 + (-v).exp())
+}
+
+/// Gated recurrent unit. The rows of the weights are split into three blocks, for the
+/// reset gate, the update gate and the candidate state in that order. The reset gate
+/// is applied to the previous state before it is multiplied by the candidate weights.
+#[derive(Debug, Clone)]
+pub struct Gru {
+    pub weights: Array2<f64>,
+    pub recurrent_weights: Array2<f64>,
+    pub biases: Array2<f64>,
+    pub nabla_w: Array2<f64>,
+    pub nabla_u: Array2<f64>,
+    pub nabla_b: Array2<f64>,
+    hidden: Option<Array2<f64>>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    input: Array2<f64>,
+    previous: Array2<f64>,
+    // Activated gates and candidate, stacked like the weights
+    gates: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl Gru {
+    pub fn new(inputs: usize, hidden: usize, weight_init: InitializationMethods) -> Self {
+        Self {
+            weights: weight_init.scaled((3 * hidden, inputs), hidden),
+            recurrent_weights: weight_init.scaled((3 * hidden, hidden), hidden),
+            biases: Array2::zeros((3 * hidden, 1)),
+            nabla_w: Array2::zeros((3 * hidden, inputs)),
+            nabla_u: Array2::zeros((3 * hidden, hidden)),
+            nabla_b: Array2::zeros((3 * hidden, 1)),
+            hidden: None,
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl RecurrentLayer for Gru {
+    fn reset_state(&mut self) {
+        self.hidden = None;
+    }
+
+    fn forward(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let h = self.hidden_size();
+        self.steps.clear();
+        for input in inputs {
+            let previous = self
+                .hidden
+                .take()
+                .unwrap_or_else(|| Array2::zeros((h, input.ncols())));
+            let mut gates = &self.weights.dot(input) + &self.biases;
+
+            let gate_weights = self.recurrent_weights.slice(s![..2 * h, ..]);
+            let mut gate_values = gates.slice_mut(s![..2 * h, ..]);
+            gate_values += &gate_weights.dot(&previous);
+            gate_values.mapv_inplace(sigmoid);
+
+            let reset = gates.slice(s![..h, ..]).to_owned();
+            let candidate_weights = self.recurrent_weights.slice(s![2 * h.., ..]);
+            let mut candidate = gates.slice_mut(s![2 * h.., ..]);
+            candidate += &candidate_weights.dot(&(&reset * &previous));
+            candidate.mapv_inplace(f64::tanh);
+
+            let update = gates.slice(s![h..2 * h, ..]);
+            let candidate = gates.slice(s![2 * h.., ..]);
+            let state = &candidate + &(&update * &(&previous - &candidate));
+
+            self.hidden = Some(state.clone());
+            self.steps.push(Step {
+                input: input.clone(),
+                previous,
+                gates,
+                state,
+            });
+        }
+        self.steps.iter().map(|step| step.state.clone()).collect()
+    }
+
+    fn backward(&mut self, deltas: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let h = self.hidden_size();
+        let mut next: Option<Array2<f64>> = None;
+        let mut input_deltas = Vec::with_capacity(self.steps.len());
+        for (step, delta) in self.steps.iter().zip(deltas).rev() {
+            let delta = match next {
+                Some(next) => delta + &next,
+                None => delta.clone(),
+            };
+            let reset = step.gates.slice(s![..h, ..]);
+            let update = step.gates.slice(s![h..2 * h, ..]);
+            let candidate = step.gates.slice(s![2 * h.., ..]);
+            let reset_previous = &reset * &step.previous;
+
+            let candidate_delta =
+                &delta * &update.mapv(|v| 1.0 - v) * &candidate.mapv(|v| 1.0 - v * v);
+            let candidate_weights = self.recurrent_weights.slice(s![2 * h.., ..]);
+            let reset_previous_delta = candidate_weights.t().dot(&candidate_delta);
+
+            let mut gate_deltas = Array2::zeros(step.gates.raw_dim());
+            gate_deltas
+                .slice_mut(s![..h, ..])
+                .assign(&(&reset_previous_delta * &step.previous * &reset.mapv(|v| v * (1.0 - v))));
+            gate_deltas.slice_mut(s![h..2 * h, ..]).assign(
+                &(&delta * &(&step.previous - &candidate) * &update.mapv(|v| v * (1.0 - v))),
+            );
+            gate_deltas
+                .slice_mut(s![2 * h.., ..])
+                .assign(&candidate_delta);
+
+            self.nabla_w += &gate_deltas.dot(&step.input.t());
+            // The candidate block sees the previous state through the reset gate
+            let mut nabla_u = self.nabla_u.slice_mut(s![..2 * h, ..]);
+            nabla_u += &gate_deltas.slice(s![..2 * h, ..]).dot(&step.previous.t());
+            let mut nabla_u = self.nabla_u.slice_mut(s![2 * h.., ..]);
+            nabla_u += &candidate_delta.dot(&reset_previous.t());
+            self.nabla_b += &gate_deltas.sum_axis(Axis(1)).insert_axis(Axis(1));
+            input_deltas.push(self.weights.t().dot(&gate_deltas));
+
+            let gate_weights = self.recurrent_weights.slice(s![..2 * h, ..]);
+            next = Some(
+                &delta * &update
+                    + &reset_previous_delta * &reset
+                    + gate_weights.t().dot(&gate_deltas.slice(s![..2 * h, ..])),
+            );
+        }
+        input_deltas.reverse();
+        input_deltas
+    }
+
+    fn hidden_size(&self) -> usize {
+        self.biases.nrows() / 3
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.weights, &self.recurrent_weights, &self.biases]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_w, &self.nabla_u, &self.nabla_b]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.weights,
+                grad: &self.nabla_w,
+            },
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.recurrent_weights,
+                grad: &self.nabla_u,
+            },
+            Param {
+                kind: ParamKind::Biases,
+                value: &mut self.biases,
+                grad: &self.nabla_b,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_w.fill(0.0);
+        self.nabla_u.fill(0.0);
+        self.nabla_b.fill(0.0);
+    }
+
+    fn box_clone(&self) -> Box<dyn RecurrentLayer> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,180 @@
+use crate::enums::InitializationMethods;
+use crate::traits::{Param, ParamKind, RecurrentLayer};
+use ndarray::{s, Array2, Axis};
+
+fn sigmoid(v: f64) -> f64 {
+    1.0 / (1.0 + (-v).exp())
+}
+
+/// Long short-term memory layer. The rows of the weights are split into four blocks,
+/// for the input, forget, cell and output gates in that order.
+#[derive(Debug, Clone)]
+pub struct Lstm {
+    pub weights: Array2<f64>,
+    pub recurrent_weights: Array2<f64>,
+    pub biases: Array2<f64>,
+    pub nabla_w: Array2<f64>,
+    pub nabla_u: Array2<f64>,
+    pub nabla_b: Array2<f64>,
+    hidden: Option<(Array2<f64>, Array2<f64>)>,
+    steps: Vec<Step>,
+}
+
+#[derive(Debug, Clone)]
+struct Step {
+    input: Array2<f64>,
+    previous: Array2<f64>,
+    previous_cell: Array2<f64>,
+    // Activated gates, stacked like the weights
+    gates: Array2<f64>,
+    cell_tanh: Array2<f64>,
+    state: Array2<f64>,
+}
+
+impl Lstm {
+    /// Forget gate biases start at 1, so the cell remembers by default
+    pub fn new(inputs: usize, hidden: usize, weight_init: InitializationMethods) -> Self {
+        let mut biases = Array2::zeros((4 * hidden, 1));
+        biases.slice_mut(s![hidden..2 * hidden, ..]).fill(1.0);
+        Self {
+            weights: weight_init.scaled((4 * hidden, inputs), hidden),
+            recurrent_weights: weight_init.scaled((4 * hidden, hidden), hidden),
+            biases,
+            nabla_w: Array2::zeros((4 * hidden, inputs)),
+            nabla_u: Array2::zeros((4 * hidden, hidden)),
+            nabla_b: Array2::zeros((4 * hidden, 1)),
+            hidden: None,
+            steps: Vec::new(),
+        }
+    }
+}
+
+impl RecurrentLayer for Lstm {
+    fn reset_state(&mut self) {
+        self.hidden = None;
+    }
+
+    fn forward(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let h = self.hidden_size();
+        self.steps.clear();
+        for input in inputs {
+            let (previous, previous_cell) = self.hidden.take().unwrap_or_else(|| {
+                (
+                    Array2::zeros((h, input.ncols())),
+                    Array2::zeros((h, input.ncols())),
+                )
+            });
+            let mut gates =
+                &(&self.weights.dot(input) + &self.recurrent_weights.dot(&previous)) + &self.biases;
+            gates.slice_mut(s![..2 * h, ..]).mapv_inplace(sigmoid);
+            gates
+                .slice_mut(s![2 * h..3 * h, ..])
+                .mapv_inplace(f64::tanh);
+            gates.slice_mut(s![3 * h.., ..]).mapv_inplace(sigmoid);
+
+            let input_gate = gates.slice(s![..h, ..]);
+            let forget_gate = gates.slice(s![h..2 * h, ..]);
+            let candidate = gates.slice(s![2 * h..3 * h, ..]);
+            let output_gate = gates.slice(s![3 * h.., ..]);
+            let cell = &forget_gate * &previous_cell + &input_gate * &candidate;
+            let cell_tanh = cell.mapv(f64::tanh);
+            let state = &output_gate * &cell_tanh;
+
+            self.hidden = Some((state.clone(), cell));
+            self.steps.push(Step {
+                input: input.clone(),
+                previous,
+                previous_cell,
+                gates,
+                cell_tanh,
+                state,
+            });
+        }
+        self.steps.iter().map(|step| step.state.clone()).collect()
+    }
+
+    fn backward(&mut self, deltas: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let h = self.hidden_size();
+        let mut next: Option<(Array2<f64>, Array2<f64>)> = None;
+        let mut input_deltas = Vec::with_capacity(self.steps.len());
+        for (step, delta) in self.steps.iter().zip(deltas).rev() {
+            let (delta, cell_delta) = match next {
+                Some((next, next_cell)) => (delta + &next, next_cell),
+                None => (delta.clone(), Array2::zeros(delta.raw_dim())),
+            };
+            let input_gate = step.gates.slice(s![..h, ..]);
+            let forget_gate = step.gates.slice(s![h..2 * h, ..]);
+            let candidate = step.gates.slice(s![2 * h..3 * h, ..]);
+            let output_gate = step.gates.slice(s![3 * h.., ..]);
+
+            let cell_delta =
+                cell_delta + &delta * &output_gate * &step.cell_tanh.mapv(|v| 1.0 - v * v);
+            let mut gate_deltas = Array2::zeros(step.gates.raw_dim());
+            gate_deltas
+                .slice_mut(s![..h, ..])
+                .assign(&(&cell_delta * &candidate * &input_gate.mapv(|v| v * (1.0 - v))));
+            gate_deltas.slice_mut(s![h..2 * h, ..]).assign(
+                &(&cell_delta * &step.previous_cell * &forget_gate.mapv(|v| v * (1.0 - v))),
+            );
+            gate_deltas
+                .slice_mut(s![2 * h..3 * h, ..])
+                .assign(&(&cell_delta * &input_gate * &candidate.mapv(|v| 1.0 - v * v)));
+            gate_deltas
+                .slice_mut(s![3 * h.., ..])
+                .assign(&(&delta * &step.cell_tanh * &output_gate.mapv(|v| v * (1.0 - v))));
+
+            self.nabla_w += &gate_deltas.dot(&step.input.t());
+            self.nabla_u += &gate_deltas.dot(&step.previous.t());
+            self.nabla_b += &gate_deltas.sum_axis(Axis(1)).insert_axis(Axis(1));
+            input_deltas.push(self.weights.t().dot(&gate_deltas));
+            next = Some((
+                self.recurrent_weights.t().dot(&gate_deltas),
+                &cell_delta * &forget_gate,
+            ));
+        }
+        input_deltas.reverse();
+        input_deltas
+    }
+
+    fn hidden_size(&self) -> usize {
+        self.biases.nrows() / 4
+    }
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        vec![&self.weights, &self.recurrent_weights, &self.biases]
+    }
+
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        vec![&self.nabla_w, &self.nabla_u, &self.nabla_b]
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        vec![
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.weights,
+                grad: &self.nabla_w,
+            },
+            Param {
+                kind: ParamKind::Weights,
+                value: &mut self.recurrent_weights,
+                grad: &self.nabla_u,
+            },
+            Param {
+                kind: ParamKind::Biases,
+                value: &mut self.biases,
+                grad: &self.nabla_b,
+            },
+        ]
+    }
+
+    fn zero_grads(&mut self) {
+        self.nabla_w.fill(0.0);
+        self.nabla_u.fill(0.0);
+        self.nabla_b.fill(0.0);
+    }
+
+    fn box_clone(&self) -> Box<dyn RecurrentLayer> {
+        Box::new(self.clone())
+    }
+}
@@ -0,0 +1,155 @@
+use crate::data::stack_sequences;
+use crate::enums::Mode;
+use crate::network::{step_params, Network, Regularization};
+use crate::traits::RecurrentLayer;
+use ndarray::{concatenate, s, Array2, Axis};
+use porcino_data::sequence::{SequenceMode, SequenceSample};
+
+mod elman;
+mod gru;
+mod lstm;
+
+pub use elman::Elman;
+pub use gru::Gru;
+pub use lstm::Lstm;
+
+/// Stack of recurrent layers followed by a feed forward head, which maps the hidden
+/// state of the last recurrent layer to the outputs at every scored step.
+#[derive(Debug, Clone)]
+pub struct SequenceNetwork {
+    pub recurrent: Vec<Box<dyn RecurrentLayer>>,
+    pub head: Network,
+    pub mode: SequenceMode,
+    /// Steps the error flows back through before it is cut off (truncated BPTT).
+    /// The whole sequence when `None`.
+    pub truncation: Option<usize>,
+}
+
+impl SequenceNetwork {
+    pub fn new(recurrent: Vec<Box<dyn RecurrentLayer>>, head: Network, mode: SequenceMode) -> Self {
+        Self {
+            recurrent,
+            head,
+            mode,
+            truncation: None,
+        }
+    }
+
+    pub fn with_truncation(mut self, steps: usize) -> Self {
+        self.truncation = Some(steps);
+        self
+    }
+
+    pub fn reset_state(&mut self) {
+        self.recurrent
+            .iter_mut()
+            .for_each(|layer| layer.reset_state());
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.head.set_mode(mode);
+    }
+
+    /// Runs a sequence from a fresh state. Steps may hold a batch of sequences, one per
+    /// column. Returns the output after every step in many-to-many mode and only the
+    /// last one in many-to-one mode.
+    pub fn process_sequence(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        self.reset_state();
+        let hidden = self.forward_recurrent(inputs);
+        match self.mode {
+            SequenceMode::ManyToOne => self.apply_head(&hidden[hidden.len() - 1..]),
+            SequenceMode::ManyToMany => self.apply_head(&hidden),
+        }
+    }
+
+    /// Performs a single update over the batch, returning the summed error of all scored steps
+    pub fn gradient_descent(&mut self, training_data: &[SequenceSample], eta: f64) -> f64 {
+        let error = self.accumulate_gradient(training_data);
+        self.apply_gradients(eta);
+        error
+    }
+
+    /// Sums the gradients of the batch into the layers, returning the summed error of all
+    /// scored steps. The sequences of a batch must be equally long.
+    pub fn accumulate_gradient(&mut self, training_data: &[SequenceSample]) -> f64 {
+        self.zero_grads();
+        if training_data.is_empty() {
+            return 0.0;
+        }
+
+        let (inputs, references) = stack_sequences(training_data);
+        let chunk_len = self.truncation.unwrap_or(inputs.len()).max(1);
+        self.reset_state();
+
+        let mut error = 0.0;
+        for (chunk, chunk_inputs) in inputs.chunks(chunk_len).enumerate() {
+            let start = chunk * chunk_len;
+            let end = start + chunk_inputs.len();
+            // Hidden state carries over between chunks, the gradient does not
+            let hidden = self.forward_recurrent(chunk_inputs);
+            let mut deltas = hidden
+                .iter()
+                .map(|h| Array2::zeros(h.raw_dim()))
+                .collect::<Vec<_>>();
+
+            let (scored, reference) = match self.mode {
+                SequenceMode::ManyToMany => (0..hidden.len(), join(&references[start..end])),
+                SequenceMode::ManyToOne if end == inputs.len() => {
+                    (hidden.len() - 1..hidden.len(), references[0].clone())
+                }
+                SequenceMode::ManyToOne => continue,
+            };
+            self.head.process_data(&join(&hidden[scored.clone()]));
+            error += self.head.loss(&reference);
+            let head_delta = self.head.backpropagate(&reference);
+            let batch = hidden[0].ncols();
+            for (i, step) in scored.enumerate() {
+                deltas[step] = head_delta
+                    .slice(s![.., i * batch..(i + 1) * batch])
+                    .to_owned();
+            }
+
+            for layer in self.recurrent.iter_mut().rev() {
+                deltas = layer.backward(&deltas);
+            }
+        }
+        error + self.head.regularization_loss()
+    }
+
+    /// Steps every parameter against its accumulated gradient. The head keeps its layers'
+    /// regularization, the recurrent layers are not regularized.
+    pub fn apply_gradients(&mut self, eta: f64) {
+        self.head.apply_gradients(eta);
+        for layer in self.recurrent.iter_mut() {
+            step_params(layer.params_mut(), &Regularization::default(), eta);
+        }
+    }
+
+    pub fn zero_grads(&mut self) {
+        self.head.zero_grads();
+        self.recurrent
+            .iter_mut()
+            .for_each(|layer| layer.zero_grads());
+    }
+
+    fn forward_recurrent(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        self.recurrent
+            .iter_mut()
+            .fold(inputs.to_vec(), |steps, layer| layer.forward(&steps))
+    }
+
+    // The head runs over all the steps at once and its output is split back into steps
+    fn apply_head(&mut self, hidden: &[Array2<f64>]) -> Vec<Array2<f64>> {
+        let batch = hidden[0].ncols();
+        self.head.process_data(&join(hidden));
+        let output = self.head.output();
+        (0..hidden.len())
+            .map(|i| output.slice(s![.., i * batch..(i + 1) * batch]).to_owned())
+            .collect()
+    }
+}
+
+fn join(steps: &[Array2<f64>]) -> Array2<f64> {
+    let views = steps.iter().map(|step| step.view()).collect::<Vec<_>>();
+    concatenate(Axis(1), &views).unwrap()
+}
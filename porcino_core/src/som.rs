@@ -0,0 +1,237 @@
+//! Kohonen self-organizing maps, trained without targets
+use crate::clustering::nearest;
+use crate::data::stack_samples;
+use ndarray::{Array2, ArrayView1, Axis};
+use porcino_data::parse::TrainingSample;
+use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Arrangement of the units of a map
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Topology {
+    /// Every unit touches the four next to it
+    #[default]
+    Rectangular,
+    /// Odd rows are shifted by half a unit, every unit touches six others
+    Hexagonal,
+}
+
+/// How a map is trained. The learning rate and the neighbourhood radius decay
+/// exponentially from their initial to their final values over all steps.
+#[derive(Debug, Clone, Copy)]
+pub struct SomTraining {
+    pub epochs: usize,
+    pub initial_rate: f64,
+    pub final_rate: f64,
+    /// Radius of the Gaussian neighbourhood, in units of the grid. `None` starts
+    /// at half the larger side of the map.
+    pub initial_radius: Option<f64>,
+    pub final_radius: f64,
+    pub seed: u64,
+}
+
+impl Default for SomTraining {
+    fn default() -> Self {
+        Self {
+            epochs: 100,
+            initial_rate: 0.5,
+            final_rate: 0.01,
+            initial_radius: None,
+            final_radius: 0.5,
+            seed: 0,
+        }
+    }
+}
+
+/// Grid of units, each holding a weight vector in input space. Training pulls the best
+/// matching unit of every sample and its grid neighbours towards the sample, so that
+/// neighbouring units end up describing similar inputs.
+#[derive(Debug, Clone)]
+pub struct Som {
+    pub rows: usize,
+    pub cols: usize,
+    pub topology: Topology,
+    /// Weights of every unit, one unit per row in row-major order of the grid
+    pub weights: Array2<f64>,
+}
+
+impl Som {
+    pub fn new(rows: usize, cols: usize, inputs: usize, topology: Topology) -> Self {
+        assert!(rows > 0 && cols > 0, "map needs at least one unit");
+        Self {
+            rows,
+            cols,
+            topology,
+            weights: Array2::zeros((rows * cols, inputs)),
+        }
+    }
+
+    /// Map of the inputs of `data`, initialized from its samples and trained on them
+    pub fn fit(
+        rows: usize,
+        cols: usize,
+        topology: Topology,
+        data: &[TrainingSample],
+        training: &SomTraining,
+    ) -> Self {
+        let inputs = data.first().map_or(0, |sample| sample.input.nrows());
+        let mut som = Self::new(rows, cols, inputs, topology);
+        som.initialize(data, training.seed);
+        som.train(data, training);
+        som
+    }
+
+    pub fn units(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    /// Grid row and column of a unit
+    pub fn coords(&self, unit: usize) -> (usize, usize) {
+        (unit / self.cols, unit % self.cols)
+    }
+
+    /// Position of a unit on the plane, neighbours being one apart
+    pub fn position(&self, unit: usize) -> (f64, f64) {
+        let (row, col) = self.coords(unit);
+        match self.topology {
+            Topology::Rectangular => (col as f64, row as f64),
+            Topology::Hexagonal => (
+                col as f64 + 0.5 * (row % 2) as f64,
+                row as f64 * 3f64.sqrt() / 2.0,
+            ),
+        }
+    }
+
+    pub fn grid_distance(&self, a: usize, b: usize) -> f64 {
+        let (ax, ay) = self.position(a);
+        let (bx, by) = self.position(b);
+        ((ax - bx).powi(2) + (ay - by).powi(2)).sqrt()
+    }
+
+    /// Units next to `unit` on the grid
+    pub fn neighbours(&self, unit: usize) -> Vec<usize> {
+        (0..self.units())
+            .filter(|other| *other != unit && self.grid_distance(unit, *other) < 1.0 + 1e-9)
+            .collect()
+    }
+
+    /// Sets the weights to randomly picked samples of `data`
+    pub fn initialize(&mut self, data: &[TrainingSample], seed: u64) {
+        if data.is_empty() {
+            return;
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        for mut unit in self.weights.axis_iter_mut(Axis(0)) {
+            let sample = &data[rng.gen_range(0..data.len())];
+            unit.assign(&sample.input.column(0));
+        }
+    }
+
+    /// Best matching unit, the one whose weights are closest to `input`
+    pub fn bmu(&self, input: ArrayView1<f64>) -> usize {
+        nearest(&self.weights, input).0
+    }
+
+    /// Best matching unit of every sample of `data`
+    pub fn map(&self, data: &[TrainingSample]) -> Vec<usize> {
+        data.iter()
+            .map(|sample| self.bmu(sample.input.column(0)))
+            .collect()
+    }
+
+    /// Moves every unit towards `input` by `rate`, weighted by a Gaussian of its grid
+    /// distance from the best matching unit
+    pub fn step(&mut self, input: ArrayView1<f64>, rate: f64, radius: f64) {
+        let bmu = self.bmu(input);
+        let spread = 2.0 * radius * radius;
+        for unit in 0..self.units() {
+            let distance = self.grid_distance(bmu, unit);
+            let influence = rate * (-distance * distance / spread).exp();
+            if influence > 1e-12 {
+                let mut weights = self.weights.row_mut(unit);
+                let change = (&input - &weights) * influence;
+                weights += &change;
+            }
+        }
+    }
+
+    /// Online training, visiting the samples in a new random order every epoch
+    pub fn train(&mut self, data: &[TrainingSample], training: &SomTraining) {
+        self.train_reporting(data, training, |_| {});
+    }
+
+    /// Like `train`, calling `report` with the number of finished epochs after every epoch
+    pub fn train_reporting(
+        &mut self,
+        data: &[TrainingSample],
+        training: &SomTraining,
+        mut report: impl FnMut(usize),
+    ) {
+        let total = (training.epochs * data.len()).max(1) as f64;
+        let initial_radius = training
+            .initial_radius
+            .unwrap_or(self.rows.max(self.cols) as f64 / 2.0)
+            .max(training.final_radius);
+        let mut rng = StdRng::seed_from_u64(training.seed);
+        let mut order: Vec<usize> = (0..data.len()).collect();
+        let mut t = 0.0;
+        for epoch in 0..training.epochs {
+            order.shuffle(&mut rng);
+            for &sample in order.iter() {
+                let progress = t / total;
+                let rate = decay(training.initial_rate, training.final_rate, progress);
+                let radius = decay(initial_radius, training.final_radius, progress);
+                self.step(data[sample].input.column(0), rate, radius);
+                t += 1.0;
+            }
+            report(epoch + 1);
+        }
+    }
+
+    /// Mean distance of the samples from the weights of their best matching units
+    pub fn quantization_error(&self, data: &[TrainingSample]) -> f64 {
+        if data.is_empty() {
+            return 0.0;
+        }
+        let (inputs, _) = stack_samples(data);
+        inputs
+            .columns()
+            .into_iter()
+            .map(|input| nearest(&self.weights, input).1.sqrt())
+            .sum::<f64>()
+            / data.len() as f64
+    }
+
+    /// Mean distance of every unit's weights from the weights of its grid neighbours,
+    /// laid out like the grid. High values mark borders between clusters.
+    pub fn u_matrix(&self) -> Array2<f64> {
+        Array2::from_shape_fn((self.rows, self.cols), |(row, col)| {
+            let unit = row * self.cols + col;
+            let neighbours = self.neighbours(unit);
+            neighbours
+                .iter()
+                .map(|other| {
+                    let difference = &self.weights.row(unit) - &self.weights.row(*other);
+                    difference.dot(&difference).sqrt()
+                })
+                .sum::<f64>()
+                / neighbours.len().max(1) as f64
+        })
+    }
+
+    /// Weight of every unit for one input feature, laid out like the grid
+    pub fn component_plane(&self, feature: usize) -> Array2<f64> {
+        self.weights
+            .column(feature)
+            .to_owned()
+            .into_shape((self.rows, self.cols))
+            .unwrap()
+    }
+}
+
+fn decay(initial: f64, last: f64, progress: f64) -> f64 {
+    if initial <= 0.0 || last <= 0.0 {
+        return initial + (last - initial) * progress;
+    }
+    initial * (last / initial).powf(progress)
+}
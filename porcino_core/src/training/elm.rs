@@ -0,0 +1,96 @@
+use super::Trainer;
+use crate::data::stack_samples;
+use crate::linalg::solve_spd;
+use crate::network::{Network, Node};
+use ndarray::{concatenate, s, Array2, Axis};
+use porcino_data::parse::TrainingSample;
+
+/// Extreme learning machine. The hidden layers keep their initial weights and act as a
+/// fixed random feature map, while the weights of every dense output layer are solved in
+/// closed form by ridge regression of the targets on the features. A single epoch trains
+/// the network, the output activations are not taken into account by the solve so linear
+/// outputs fit best. Outputs that aren't dense layers, like a concatenation, are left as
+/// they are. The learning rate is not used.
+#[derive(Debug, Clone, Copy)]
+pub struct ExtremeLearningMachine {
+    /// Penalty on the squared output weights and biases
+    pub ridge: f64,
+    solved: bool,
+}
+
+impl Default for ExtremeLearningMachine {
+    fn default() -> Self {
+        Self::new(1e-6)
+    }
+}
+
+impl ExtremeLearningMachine {
+    pub fn new(ridge: f64) -> Self {
+        Self {
+            ridge,
+            solved: false,
+        }
+    }
+}
+
+impl Trainer for ExtremeLearningMachine {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        if data.is_empty() {
+            return network.regularization_loss();
+        }
+        let (inputs, references) = stack_samples(data);
+        network.process_data(&inputs);
+        let targets = network.output_targets(&references);
+
+        let mut row = 0;
+        for output in network.outputs().to_vec() {
+            let size = network.node_value(output).nrows();
+            let target = targets.slice(s![row..row + size, ..]);
+            row += size;
+
+            // Only dense layers are solved, other outputs keep their parameters
+            let Node::Layer { layer, input } = network.nodes()[output.0] else {
+                continue;
+            };
+            if !network.layers[layer].is_dense() {
+                continue;
+            }
+
+            // Features with a row of ones for the biases
+            let features = network.node_value(input);
+            let inputs = features.nrows();
+            let augmented = concatenate![
+                Axis(0),
+                features.view(),
+                Array2::ones((1, features.ncols())).view()
+            ];
+            let mut system = augmented.dot(&augmented.t());
+            system.diag_mut().map_inplace(|value| *value += self.ridge);
+            let rhs = augmented.dot(&target.t());
+            // A singular system leaves the layer as it was, a larger ridge fixes it
+            let Some(solution) = solve_spd(&system, &rhs) else {
+                continue;
+            };
+
+            let mut params = network.layers[layer].params_mut();
+            params[0]
+                .value
+                .assign(&solution.slice(s![..inputs, ..]).t());
+            params[1]
+                .value
+                .assign(&solution.slice(s![inputs.., ..]).t());
+        }
+        self.solved = true;
+
+        network.process_data(&inputs);
+        network.loss(&references) + network.regularization_loss()
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+
+    fn converged(&self) -> bool {
+        self.solved
+    }
+}
@@ -0,0 +1,221 @@
+use super::Trainer;
+use crate::data::stack_samples;
+use crate::errors::Sse;
+use crate::network::Network;
+use crate::traits::ErrorFn;
+use ndarray::{Array1, Array2};
+use porcino_data::parse::TrainingSample;
+use rand::prelude::*;
+use std::fmt;
+use std::marker::PhantomData;
+use std::thread;
+
+/// Genetic algorithm evolving a population of weight sets, each epoch being one
+/// generation. Fitness is the error `E` measures on the whole batch, so neither the
+/// error nor the layers need to be differentiable. Parents are picked by tournament,
+/// recombined by uniform crossover and mutated with Gaussian noise, while the best
+/// individuals pass on unchanged. The network always holds the best weights found in
+/// the last generation. Regularization and the learning rate are not used.
+pub struct Neuroevolution<E: ErrorFn = Sse> {
+    pub population_size: usize,
+    /// Individuals competing for every parent slot
+    pub tournament_size: usize,
+    /// Probability that a child mixes two parents instead of copying one
+    pub crossover_rate: f64,
+    /// Probability of every weight of a child being mutated
+    pub mutation_rate: f64,
+    /// Standard deviation of the mutations
+    pub mutation_std: f64,
+    /// Standard deviation of the first population around the weights of the network
+    pub initial_spread: f64,
+    /// Best individuals copied into the next generation as they are
+    pub elitism: usize,
+    /// Threads evaluating the population
+    pub threads: usize,
+    population: Vec<Array1<f64>>,
+    // Weights written into the network by the last generation
+    best: Option<Array1<f64>>,
+    rng: StdRng,
+    error: PhantomData<fn() -> E>,
+}
+
+impl<E: ErrorFn> Neuroevolution<E> {
+    pub fn new() -> Self {
+        Self::with_rng(StdRng::from_entropy())
+    }
+
+    /// Evolution with a reproducible sequence of random choices
+    pub fn with_seed(seed: u64) -> Self {
+        Self::with_rng(StdRng::seed_from_u64(seed))
+    }
+
+    fn with_rng(rng: StdRng) -> Self {
+        Self {
+            population_size: 50,
+            tournament_size: 3,
+            crossover_rate: 0.9,
+            mutation_rate: 0.1,
+            mutation_std: 0.1,
+            initial_spread: 0.5,
+            elitism: 2,
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            population: Vec::new(),
+            best: None,
+            rng,
+            error: PhantomData,
+        }
+    }
+
+    // The network's own weights and variations of them
+    fn seed_population(&mut self, params: Array1<f64>) {
+        let size = self.population_size.max(1);
+        let spread = self.initial_spread;
+        self.population = Vec::with_capacity(size);
+        for _ in 1..size {
+            let rng = &mut self.rng;
+            self.population
+                .push(params.mapv(|w| w + spread * gaussian(rng)));
+        }
+        self.population.insert(0, params);
+    }
+
+    fn evaluate(
+        &self,
+        network: &Network,
+        inputs: &Array2<f64>,
+        references: &Array2<f64>,
+    ) -> Vec<f64> {
+        let chunk = self.population.len().div_ceil(self.threads.max(1)).max(1);
+        thread::scope(|scope| {
+            let workers: Vec<_> = self
+                .population
+                .chunks(chunk)
+                .map(|individuals| {
+                    let mut network = network.clone();
+                    scope.spawn(move || {
+                        individuals
+                            .iter()
+                            .map(|params| {
+                                network.set_params_vector(params);
+                                network.process_data(inputs);
+                                E::cost_function(network.output(), references)
+                            })
+                            .collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap())
+                .collect()
+        })
+    }
+
+    // Index of the fittest of a few random individuals
+    fn tournament(&mut self, errors: &[f64]) -> usize {
+        (0..self.tournament_size.max(1))
+            .map(|_| self.rng.gen_range(0..errors.len()))
+            .min_by(|&a, &b| errors[a].total_cmp(&errors[b]))
+            .unwrap()
+    }
+
+    fn breed(&mut self, errors: &[f64]) -> Array1<f64> {
+        let first = self.tournament(errors);
+        let mut child = self.population[first].clone();
+        if self.rng.gen_bool(self.crossover_rate.clamp(0.0, 1.0)) {
+            let second = self.tournament(errors);
+            for (w, &other) in child.iter_mut().zip(self.population[second].iter()) {
+                if self.rng.gen_bool(0.5) {
+                    *w = other;
+                }
+            }
+        }
+        let rate = self.mutation_rate.clamp(0.0, 1.0);
+        for w in child.iter_mut() {
+            if self.rng.gen_bool(rate) {
+                *w += self.mutation_std * gaussian(&mut self.rng);
+            }
+        }
+        child
+    }
+}
+
+impl<E: ErrorFn> Default for Neuroevolution<E> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E: ErrorFn> Clone for Neuroevolution<E> {
+    fn clone(&self) -> Self {
+        Self {
+            population: self.population.clone(),
+            best: self.best.clone(),
+            rng: self.rng.clone(),
+            ..*self
+        }
+    }
+}
+
+impl<E: ErrorFn> fmt::Debug for Neuroevolution<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Neuroevolution")
+            .field("population_size", &self.population_size)
+            .field("tournament_size", &self.tournament_size)
+            .field("crossover_rate", &self.crossover_rate)
+            .field("mutation_rate", &self.mutation_rate)
+            .field("mutation_std", &self.mutation_std)
+            .field("initial_spread", &self.initial_spread)
+            .field("elitism", &self.elitism)
+            .field("threads", &self.threads)
+            .finish_non_exhaustive()
+    }
+}
+
+impl<E: ErrorFn> Trainer for Neuroevolution<E> {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        let current = network.params_vector();
+        let size = self.population_size.max(1);
+        if self.best.as_ref() != Some(&current) || self.population.len() != size {
+            // Weights changed outside of the trainer, e.g. restored by early stopping
+            self.seed_population(current);
+        }
+        if data.is_empty() {
+            return 0.0;
+        }
+
+        let (inputs, references) = stack_samples(data);
+        let errors = self.evaluate(network, &inputs, &references);
+        let mut ranking: Vec<usize> = (0..errors.len()).collect();
+        ranking.sort_by(|&a, &b| errors[a].total_cmp(&errors[b]));
+
+        let best = self.population[ranking[0]].clone();
+        network.set_params_vector(&best);
+        // Leaves the last output and the layer state at the best weights
+        network.process_data(&inputs);
+
+        let mut next: Vec<_> = ranking
+            .iter()
+            .take(self.elitism.min(errors.len()))
+            .map(|&i| self.population[i].clone())
+            .collect();
+        while next.len() < self.population.len() {
+            let child = self.breed(&errors);
+            next.push(child);
+        }
+        self.population = next;
+        self.best = Some(best);
+        errors[ranking[0]]
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+}
+
+// Standard normal sample, Box–Muller
+fn gaussian(rng: &mut StdRng) -> f64 {
+    let u: f64 = 1.0 - rng.gen::<f64>();
+    let v: f64 = rng.gen();
+    (-2.0 * u.ln()).sqrt() * (2.0 * std::f64::consts::PI * v).cos()
+}
@@ -0,0 +1,120 @@
+use super::trainer::Objective;
+use super::Trainer;
+use crate::network::Network;
+use ndarray::Array1;
+use porcino_data::parse::TrainingSample;
+use std::collections::VecDeque;
+
+/// Limited-memory BFGS on the flattened parameters, for full-batch training. Every epoch
+/// is one quasi-Newton step, its length found by a backtracking line search. The learning
+/// rate is not used.
+#[derive(Debug, Clone)]
+pub struct Lbfgs {
+    /// Number of recent steps the curvature is estimated from
+    pub memory: usize,
+    /// Sufficient decrease the line search asks for, relative to the slope
+    pub armijo: f64,
+    /// Halvings of the step the line search tries before giving up
+    pub max_backtracks: usize,
+    // Steps and gradient changes, oldest first
+    history: VecDeque<(Array1<f64>, Array1<f64>)>,
+    // Point of the last epoch, so that the next one can skip evaluating it again
+    last: Option<(Array1<f64>, Objective)>,
+}
+
+impl Default for Lbfgs {
+    fn default() -> Self {
+        Self {
+            memory: 10,
+            armijo: 1e-4,
+            max_backtracks: 30,
+            history: VecDeque::new(),
+            last: None,
+        }
+    }
+}
+
+impl Lbfgs {
+    // Two-loop recursion, the quasi-Newton direction for the gradient
+    fn direction(&self, gradient: &Array1<f64>) -> Array1<f64> {
+        let mut q = gradient.clone();
+        let mut alphas = Vec::with_capacity(self.history.len());
+        for (s, y) in self.history.iter().rev() {
+            let alpha = s.dot(&q) / y.dot(s);
+            q.scaled_add(-alpha, y);
+            alphas.push(alpha);
+        }
+        if let Some((s, y)) = self.history.back() {
+            q *= s.dot(y) / y.dot(y);
+        }
+        for ((s, y), alpha) in self.history.iter().zip(alphas.into_iter().rev()) {
+            let beta = y.dot(&q) / y.dot(s);
+            q.scaled_add(alpha - beta, s);
+        }
+        -q
+    }
+}
+
+impl Trainer for Lbfgs {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        let start = network.params_vector();
+        let current = match self.last.take() {
+            Some((params, objective)) if params == start => objective,
+            // Weights changed outside of the trainer, e.g. restored by early stopping
+            _ => {
+                self.history.clear();
+                Objective::at(network, data)
+            }
+        };
+
+        let mut direction = self.direction(&current.gradient);
+        let mut slope = current.gradient.dot(&direction);
+        if slope >= 0.0 {
+            self.history.clear();
+            direction = -&current.gradient;
+            slope = current.gradient.dot(&direction);
+        }
+        if slope == 0.0 {
+            let error = current.error;
+            self.last = Some((start, current));
+            return error;
+        }
+
+        // Without curvature information the first step is kept short
+        let mut step = if self.history.is_empty() {
+            (1.0 / direction.dot(&direction).sqrt()).min(1.0)
+        } else {
+            1.0
+        };
+        for _ in 0..self.max_backtracks {
+            let params = &start + &(&direction * step);
+            network.set_params_vector(&params);
+            let trial = Objective::at(network, data);
+            if trial.value <= current.value + self.armijo * step * slope {
+                let s = &params - &start;
+                let y = &trial.gradient - &current.gradient;
+                if s.dot(&y) > 1e-10 {
+                    self.history.push_back((s, y));
+                    if self.history.len() > self.memory {
+                        self.history.pop_front();
+                    }
+                }
+                let error = trial.error;
+                self.last = Some((params, trial));
+                return error;
+            }
+            step /= 2.0;
+        }
+
+        // No step decreased the objective, start over from steepest descent
+        network.set_params_vector(&start);
+        self.history.clear();
+        let error = current.error;
+        self.last = Some((start, current));
+        error
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+}
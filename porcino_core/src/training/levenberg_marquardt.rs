@@ -0,0 +1,136 @@
+use super::Trainer;
+use crate::data::stack_samples;
+use crate::linalg::solve_spd;
+use crate::network::Network;
+use ndarray::Array2;
+use porcino_data::parse::TrainingSample;
+
+/// Levenberg–Marquardt, for small networks trained on the whole data set. Every epoch
+/// linearizes the residuals `output - reference` of all samples and solves the damped
+/// normal equations `(JᵀJ + μI) δ = Jᵀe`. Steps that lower the error are taken and
+/// decrease `mu`, others are undone and increase it until a step succeeds.
+///
+/// The summed squared error is minimized whatever loss the heads use, with the residuals
+/// of every head scaled by the square root of its weight. The layers' regularization
+/// is not taken into account.
+#[derive(Debug, Clone, Copy)]
+pub struct LevenbergMarquardt {
+    /// Damping, large values step along the gradient, small ones like Gauss–Newton
+    pub mu: f64,
+    /// Factor `mu` grows by after a failed step, needs to be larger than 1
+    pub mu_inc: f64,
+    pub mu_dec: f64,
+    /// Damping at which an epoch gives up looking for a better step
+    pub mu_max: f64,
+}
+
+impl Default for LevenbergMarquardt {
+    fn default() -> Self {
+        Self {
+            mu: 1e-3,
+            mu_inc: 10.0,
+            mu_dec: 0.1,
+            mu_max: 1e10,
+        }
+    }
+}
+
+impl Trainer for LevenbergMarquardt {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        assert!(
+            self.mu_inc > 1.0,
+            "damping needs to increase after failed steps"
+        );
+        if data.is_empty() {
+            return 0.0;
+        }
+        let (inputs, references) = stack_samples(data);
+        let weights = residual_weights(network, references.nrows());
+        let (jacobian, residuals) = linearize(network, &inputs, &references, &weights);
+        let error = squared_sum(&residuals);
+
+        let hessian = jacobian.t().dot(&jacobian);
+        let gradient = jacobian.t().dot(&residuals);
+        let start = network.params_vector();
+
+        // A damping of zero would never grow past `mu_max`
+        self.mu = self.mu.max(f64::MIN_POSITIVE);
+        while self.mu <= self.mu_max {
+            let mut damped = hessian.clone();
+            damped.diag_mut().map_inplace(|d| *d += self.mu);
+            if let Some(step) = solve_spd(&damped, &gradient) {
+                network.set_params_vector(&(&start - &step.column(0)));
+                network.process_data(&inputs);
+                let trial = weighted_error(network.output(), &references, &weights);
+                if trial < error {
+                    self.mu = (self.mu * self.mu_dec).max(f64::MIN_POSITIVE);
+                    return trial;
+                }
+            }
+            self.mu *= self.mu_inc;
+        }
+
+        // No step helped even at the largest damping, so the weights are left as they were
+        self.mu = self.mu_max;
+        network.set_params_vector(&start);
+        error
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+}
+
+// Scale of every output row, the square root of its head's weight
+fn residual_weights(network: &Network, rows: usize) -> Array2<f64> {
+    let mut weights = Array2::ones((rows, 1));
+    for head in network.heads() {
+        for column in head.columns.iter() {
+            weights[[*column, 0]] = head.weight.sqrt();
+        }
+    }
+    weights
+}
+
+fn weighted_error(output: &Array2<f64>, references: &Array2<f64>, weights: &Array2<f64>) -> f64 {
+    squared_sum(&((output - references) * weights))
+}
+
+fn squared_sum(values: &Array2<f64>) -> f64 {
+    values.iter().map(|v| v * v).sum()
+}
+
+// Jacobian of the weighted residuals of the batch with respect to all parameters, with
+// a row per output of every sample, and the residuals as a column in the same order.
+// The batch goes through at once, so that layers like batch normalization see all of it.
+fn linearize(
+    network: &mut Network,
+    inputs: &Array2<f64>,
+    references: &Array2<f64>,
+    weights: &Array2<f64>,
+) -> (Array2<f64>, Array2<f64>) {
+    network.process_data(inputs);
+    let output = network.output().clone();
+    let (outputs, samples) = output.dim();
+    let residuals = (&output - references) * weights;
+
+    let mut jacobian = Array2::zeros((outputs * samples, network.param_count()));
+    let mut seed = Array2::zeros(output.raw_dim());
+    for sample in 0..samples {
+        for row in 0..outputs {
+            seed[[row, sample]] = weights[[row, 0]];
+            network.zero_grads();
+            network.backpropagate_delta(&seed);
+            seed[[row, sample]] = 0.0;
+            jacobian
+                .row_mut(sample * outputs + row)
+                .assign(&network.gradient_vector());
+        }
+    }
+    network.zero_grads();
+
+    let residuals = Array2::from_shape_fn((outputs * samples, 1), |(i, _)| {
+        residuals[[i % outputs, i / outputs]]
+    });
+    (jacobian, residuals)
+}
@@ -0,0 +1,22 @@
+mod elm;
+mod genetic;
+mod lbfgs;
+mod levenberg_marquardt;
+mod rprop;
+mod scg;
+mod schedule;
+mod stopping;
+mod trainer;
+
+pub use elm::ExtremeLearningMachine;
+pub use genetic::Neuroevolution;
+pub use lbfgs::Lbfgs;
+pub use levenberg_marquardt::LevenbergMarquardt;
+pub use rprop::{Rprop, RpropVariant};
+pub use scg::Scg;
+pub use schedule::{
+    Constant, CosineAnnealing, ExponentialDecay, LinearWarmup, LrSchedule, ReduceOnPlateau,
+    StepDecay,
+};
+pub use stopping::{EarlyStopping, Monitor, Observation, StopConditions, StopReason};
+pub use trainer::{GradientDescent, Trainer};
@@ -0,0 +1,145 @@
+use super::Trainer;
+use crate::network::Network;
+use ndarray::{Array2, Zip};
+use porcino_data::parse::TrainingSample;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RpropVariant {
+    /// Reverts the previous step of a weight whose gradient changed sign
+    RpropPlus,
+    /// No reverting, only the step sizes adapt
+    RpropMinus,
+    /// Like `RpropPlus`, reverting only when the error went up
+    IRpropPlus,
+    /// Like `RpropMinus`, skipping the update of a weight whose gradient changed sign
+    IRpropMinus,
+}
+
+/// Resilient propagation. Every weight moves against the sign of its full-batch gradient
+/// by a step size of its own, which grows by `eta_plus` while the sign stays and shrinks
+/// by `eta_minus` when it flips. The learning rate is not used, so decoupled weight decay
+/// shrinks the weights by the whole `l2` every epoch.
+#[derive(Debug, Clone)]
+pub struct Rprop {
+    pub variant: RpropVariant,
+    pub eta_plus: f64,
+    pub eta_minus: f64,
+    pub initial_step: f64,
+    pub min_step: f64,
+    pub max_step: f64,
+    steps: Vec<Array2<f64>>,
+    previous_grads: Vec<Array2<f64>>,
+    previous_changes: Vec<Array2<f64>>,
+    previous_error: f64,
+}
+
+impl Rprop {
+    pub fn new(variant: RpropVariant) -> Self {
+        Self {
+            variant,
+            eta_plus: 1.2,
+            eta_minus: 0.5,
+            initial_step: 0.1,
+            min_step: 1e-6,
+            max_step: 50.0,
+            steps: Vec::new(),
+            previous_grads: Vec::new(),
+            previous_changes: Vec::new(),
+            previous_error: f64::INFINITY,
+        }
+    }
+
+    // Step sizes and history start over for parameters not seen before
+    fn prepare(&mut self, index: usize, shape: (usize, usize)) {
+        if self.steps.len() <= index {
+            self.steps.resize(index + 1, Array2::zeros((0, 0)));
+            self.previous_grads.resize(index + 1, Array2::zeros((0, 0)));
+            self.previous_changes
+                .resize(index + 1, Array2::zeros((0, 0)));
+        }
+        if self.steps[index].dim() != shape {
+            self.steps[index] = Array2::from_elem(shape, self.initial_step);
+            self.previous_grads[index] = Array2::zeros(shape);
+            self.previous_changes[index] = Array2::zeros(shape);
+        }
+    }
+
+    fn update(&mut self, index: usize, value: &mut Array2<f64>, grad: &Array2<f64>, worse: bool) {
+        let variant = self.variant;
+        let (plus, minus) = (self.eta_plus, self.eta_minus);
+        let (min_step, max_step) = (self.min_step, self.max_step);
+        Zip::from(value)
+            .and(grad)
+            .and(&mut self.steps[index])
+            .and(&mut self.previous_grads[index])
+            .and(&mut self.previous_changes[index])
+            .for_each(|w, &g, step, previous_grad, previous_change| {
+                let product = g * *previous_grad;
+                if product > 0.0 {
+                    *step = (*step * plus).min(max_step);
+                } else if product < 0.0 {
+                    *step = (*step * minus).max(min_step);
+                }
+
+                let mut g = g;
+                if product < 0.0 {
+                    match variant {
+                        RpropVariant::RpropPlus | RpropVariant::IRpropPlus => {
+                            if variant == RpropVariant::RpropPlus || worse {
+                                *w -= *previous_change;
+                            }
+                            // Next epoch steps without looking at this gradient's sign
+                            *previous_change = 0.0;
+                            *previous_grad = 0.0;
+                            return;
+                        }
+                        RpropVariant::IRpropMinus => g = 0.0,
+                        RpropVariant::RpropMinus => {}
+                    }
+                }
+                *previous_change = -sign(g) * *step;
+                *w += *previous_change;
+                *previous_grad = g;
+            });
+    }
+}
+
+impl Default for Rprop {
+    fn default() -> Self {
+        Self::new(RpropVariant::IRpropPlus)
+    }
+}
+
+impl Trainer for Rprop {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        let error = network.accumulate_gradient(data);
+        let worse = error > self.previous_error;
+
+        let mut index = 0;
+        for layer in network.layers.iter_mut() {
+            let reg = layer.regularization();
+            for param in layer.params_mut() {
+                let grad = reg.regularized_gradient(&param);
+                self.prepare(index, grad.dim());
+                self.update(index, param.value, &grad, worse);
+                reg.after_step(param.kind, param.value, 1.0);
+                index += 1;
+            }
+        }
+
+        self.previous_error = error;
+        error
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+}
+
+fn sign(value: f64) -> f64 {
+    if value == 0.0 {
+        0.0
+    } else {
+        value.signum()
+    }
+}
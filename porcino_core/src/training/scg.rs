@@ -0,0 +1,135 @@
+use super::trainer::Objective;
+use super::Trainer;
+use crate::network::Network;
+use ndarray::Array1;
+use porcino_data::parse::TrainingSample;
+
+/// Møller's scaled conjugate gradient on the flattened parameters, for full-batch training.
+/// Instead of a line search, every epoch estimates the curvature along the search direction
+/// from a finite difference of gradients and regulates the step like Levenberg–Marquardt
+/// does. The learning rate is not used.
+#[derive(Debug, Clone)]
+pub struct Scg {
+    /// Distance of the gradient difference estimating the curvature, relative to the direction
+    pub sigma: f64,
+    /// Initial scale of the trust region term
+    pub lambda: f64,
+    state: Option<State>,
+}
+
+#[derive(Debug, Clone)]
+struct State {
+    params: Array1<f64>,
+    objective: Objective,
+    direction: Array1<f64>,
+    lambda: f64,
+    lambda_bar: f64,
+    success: bool,
+    // Curvature along the direction, kept while steps fail
+    curvature: f64,
+    steps: usize,
+}
+
+impl Default for Scg {
+    fn default() -> Self {
+        Self {
+            sigma: 1e-4,
+            lambda: 1e-6,
+            state: None,
+        }
+    }
+}
+
+impl Scg {
+    fn restart(&self, network: &mut Network, data: &[TrainingSample]) -> State {
+        let objective = Objective::at(network, data);
+        State {
+            params: network.params_vector(),
+            direction: -&objective.gradient,
+            objective,
+            lambda: self.lambda,
+            lambda_bar: 0.0,
+            success: true,
+            curvature: 0.0,
+            steps: 0,
+        }
+    }
+}
+
+impl Trainer for Scg {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], _eta: f64) -> f64 {
+        let start = network.params_vector();
+        let mut state = match self.state.take() {
+            Some(state) if state.params == start => state,
+            // Weights changed outside of the trainer, e.g. restored by early stopping
+            _ => self.restart(network, data),
+        };
+
+        let p = state.direction.clone();
+        let p_norm2 = p.dot(&p);
+        if p_norm2 == 0.0 {
+            let error = state.objective.error;
+            self.state = Some(state);
+            return error;
+        }
+        let r = -&state.objective.gradient;
+
+        // Second order information along p
+        if state.success {
+            let sigma = self.sigma / p_norm2.sqrt();
+            network.set_params_vector(&(&start + &(&p * sigma)));
+            let shifted = Objective::at(network, data);
+            let s = (&shifted.gradient - &state.objective.gradient) / sigma;
+            state.curvature = p.dot(&s);
+        }
+
+        // Scale the curvature, making the Hessian approximation positive definite if needed
+        let mut delta = state.curvature + (state.lambda - state.lambda_bar) * p_norm2;
+        if delta <= 0.0 {
+            state.lambda_bar = 2.0 * (state.lambda - delta / p_norm2);
+            delta = -delta + state.lambda * p_norm2;
+            state.lambda = state.lambda_bar;
+        }
+
+        let mu = p.dot(&r);
+        let alpha = mu / delta;
+        let params = &start + &(&p * alpha);
+        network.set_params_vector(&params);
+        let trial = Objective::at(network, data);
+
+        // Ratio of the actual to the predicted decrease
+        let comparison = 2.0 * delta * (state.objective.value - trial.value) / (mu * mu);
+        if comparison >= 0.0 {
+            let new_r = -&trial.gradient;
+            state.steps += 1;
+            state.direction = if state.steps % params.len().max(1) == 0 {
+                new_r.clone()
+            } else {
+                let beta = (new_r.dot(&new_r) - new_r.dot(&r)) / mu;
+                &new_r + &(&p * beta)
+            };
+            state.params = params;
+            state.objective = trial;
+            state.lambda_bar = 0.0;
+            state.success = true;
+            if comparison >= 0.75 {
+                state.lambda /= 4.0;
+            }
+        } else {
+            network.set_params_vector(&start);
+            state.lambda_bar = state.lambda;
+            state.success = false;
+        }
+        if comparison < 0.25 {
+            state.lambda += delta * (1.0 - comparison) / p_norm2;
+        }
+
+        let error = state.objective.error;
+        self.state = Some(state);
+        error
+    }
+
+    fn uses_learning_rate(&self) -> bool {
+        false
+    }
+}
@@ -0,0 +1,154 @@
+use std::f64::consts::PI;
+use std::fmt::Debug;
+
+/// Source of the learning rate used by the training loop.
+pub trait LrSchedule: Debug + Send {
+    /// Learning rate of the `step`-th update, counted from 0. With full-batch training
+    /// a step is an epoch. `loss` is the newest validation loss, only present when one
+    /// was measured since the previous call.
+    fn learning_rate(&mut self, step: usize, loss: Option<f64>) -> f64;
+
+    /// Whether the schedule reacts to the loss, so that it has to be measured every step
+    fn uses_loss(&self) -> bool {
+        false
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Constant {
+    pub eta: f64,
+}
+
+/// Multiplies the rate by `factor` every `step_size` steps.
+#[derive(Debug, Clone, Copy)]
+pub struct StepDecay {
+    pub initial: f64,
+    pub factor: f64,
+    pub step_size: usize,
+}
+
+/// `initial * gamma^step`
+#[derive(Debug, Clone, Copy)]
+pub struct ExponentialDecay {
+    pub initial: f64,
+    pub gamma: f64,
+}
+
+/// Cosine annealing with warm restarts (SGDR). Every cycle is `period_mult` times
+/// longer than the previous one.
+#[derive(Debug, Clone, Copy)]
+pub struct CosineAnnealing {
+    pub max: f64,
+    pub min: f64,
+    pub period: usize,
+    pub period_mult: usize,
+}
+
+/// Ramps the rate linearly from zero up to the wrapped schedule over `steps` steps,
+/// after which the wrapped schedule starts from its own step 0.
+#[derive(Debug)]
+pub struct LinearWarmup {
+    pub steps: usize,
+    pub schedule: Box<dyn LrSchedule>,
+}
+
+/// Multiplies the rate by `factor` once the loss did not improve for `patience` observations.
+#[derive(Debug, Clone, Copy)]
+pub struct ReduceOnPlateau {
+    pub factor: f64,
+    pub patience: usize,
+    pub min_delta: f64,
+    pub min_rate: f64,
+    current: f64,
+    best: Option<f64>,
+    wait: usize,
+}
+
+impl LrSchedule for Constant {
+    fn learning_rate(&mut self, _: usize, _: Option<f64>) -> f64 {
+        self.eta
+    }
+}
+
+impl LrSchedule for StepDecay {
+    fn learning_rate(&mut self, step: usize, _: Option<f64>) -> f64 {
+        self.initial * self.factor.powf((step / self.step_size.max(1)) as f64)
+    }
+}
+
+impl LrSchedule for ExponentialDecay {
+    fn learning_rate(&mut self, step: usize, _: Option<f64>) -> f64 {
+        self.initial * self.gamma.powf(step as f64)
+    }
+}
+
+impl LrSchedule for CosineAnnealing {
+    fn learning_rate(&mut self, step: usize, _: Option<f64>) -> f64 {
+        let mut position = step;
+        let mut period = self.period.max(1);
+        if self.period_mult <= 1 {
+            position %= period;
+        } else {
+            // Cycles grow geometrically, so this takes a logarithmic number of rounds
+            while position >= period {
+                position -= period;
+                period = period.saturating_mul(self.period_mult);
+            }
+        }
+        let progress = position as f64 / period as f64;
+        self.min + 0.5 * (self.max - self.min) * (1.0 + (PI * progress).cos())
+    }
+}
+
+impl LrSchedule for LinearWarmup {
+    fn learning_rate(&mut self, step: usize, loss: Option<f64>) -> f64 {
+        if step < self.steps {
+            self.schedule.learning_rate(0, None) * (step + 1) as f64 / self.steps as f64
+        } else {
+            self.schedule.learning_rate(step - self.steps, loss)
+        }
+    }
+
+    fn uses_loss(&self) -> bool {
+        self.schedule.uses_loss()
+    }
+}
+
+impl ReduceOnPlateau {
+    pub fn new(initial: f64, factor: f64, patience: usize, min_delta: f64, min_rate: f64) -> Self {
+        Self {
+            factor,
+            patience,
+            min_delta,
+            min_rate,
+            current: initial,
+            best: None,
+            wait: 0,
+        }
+    }
+}
+
+impl LrSchedule for ReduceOnPlateau {
+    fn learning_rate(&mut self, _: usize, loss: Option<f64>) -> f64 {
+        if let Some(loss) = loss {
+            match self.best {
+                Some(best) if loss >= best - self.min_delta => {
+                    self.wait += 1;
+                    if self.wait >= self.patience {
+                        self.current = (self.current * self.factor).max(self.min_rate);
+                        self.wait = 0;
+                    }
+                }
+                _ => {
+                    self.best = Some(loss);
+                    self.wait = 0;
+                }
+            }
+        }
+        self.current
+    }
+
+    fn uses_loss(&self) -> bool {
+        true
+    }
+}
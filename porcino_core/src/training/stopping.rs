@@ -0,0 +1,231 @@
+use crate::metrics::EvaluationReport;
+use crate::network::Network;
+use std::fmt::{Display, Formatter};
+use std::time::Duration;
+
+/// Value watched by early stopping.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Monitor {
+    TrainingLoss,
+    ValidationLoss,
+    /// Accuracy for classification, R² for regression
+    ValidationScore,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StopReason {
+    EpochsCompleted,
+    EarlyStopping { best_epoch: usize, best_value: f64 },
+    TargetErrorReached { error: f64 },
+    TimeLimit { elapsed: Duration },
+    Converged,
+}
+
+/// What the training loop knows after an epoch. Validation values are only present
+/// on epochs in which the validation set was evaluated.
+#[derive(Debug, Clone, Copy)]
+pub struct Observation<'a> {
+    pub epoch: usize,
+    pub elapsed: Duration,
+    pub training_loss: Option<f64>,
+    pub validation_loss: Option<f64>,
+    /// Whether there is a validation set at all, even if this epoch did not evaluate it
+    pub has_validation_set: bool,
+    pub report: Option<&'a EvaluationReport>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EarlyStopping {
+    pub monitor: Monitor,
+    /// Number of observations of the monitored value without improvement before stopping
+    pub patience: usize,
+    /// Smallest change of the monitored value that counts as an improvement
+    pub min_delta: f64,
+    pub restore_best: bool,
+    best: Option<(usize, f64)>,
+    best_network: Option<Network>,
+    wait: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct StopConditions {
+    pub early_stopping: Option<EarlyStopping>,
+    /// Stop once the validation loss (or training loss without a validation set) drops below this
+    pub target_error: Option<f64>,
+    /// Stop once this much time was spent training
+    pub max_duration: Option<Duration>,
+    /// Epochs between validation set evaluations, 0 disables them
+    pub validation_interval: usize,
+}
+
+impl Monitor {
+    pub fn is_maximized(&self) -> bool {
+        matches!(self, Monitor::ValidationScore)
+    }
+
+    pub fn value(&self, observation: &Observation<'_>) -> Option<f64> {
+        match self {
+            Monitor::TrainingLoss => observation.training_loss,
+            Monitor::ValidationLoss => observation.validation_loss,
+            Monitor::ValidationScore => observation.report.map(|report| match report {
+                EvaluationReport::Classification(report) => report.accuracy,
+                EvaluationReport::Regression(report) => report.r2,
+            }),
+        }
+    }
+
+    pub fn needs_validation(&self) -> bool {
+        !matches!(self, Monitor::TrainingLoss)
+    }
+}
+
+impl EarlyStopping {
+    pub fn new(monitor: Monitor, patience: usize, min_delta: f64, restore_best: bool) -> Self {
+        Self {
+            monitor,
+            patience,
+            min_delta,
+            restore_best,
+            best: None,
+            best_network: None,
+            wait: 0,
+        }
+    }
+
+    pub fn reset(&mut self) {
+        self.best = None;
+        self.best_network = None;
+        self.wait = 0;
+    }
+
+    pub fn best(&self) -> Option<(usize, f64)> {
+        self.best
+    }
+
+    /// Returns `true` once the monitored value did not improve for `patience` observations.
+    pub fn update(&mut self, observation: &Observation<'_>, network: &Network) -> bool {
+        let value = match self.monitor.value(observation) {
+            Some(value) if value.is_finite() => value,
+            _ => return false,
+        };
+        let improved = match self.best {
+            None => true,
+            Some((_, best)) if self.monitor.is_maximized() => value > best + self.min_delta,
+            Some((_, best)) => value < best - self.min_delta,
+        };
+
+        if improved {
+            self.best = Some((observation.epoch, value));
+            self.wait = 0;
+            if self.restore_best {
+                self.best_network = Some(network.clone());
+            }
+            false
+        } else {
+            self.wait += 1;
+            self.wait >= self.patience
+        }
+    }
+
+    /// Replaces the network with the best one seen, if weights were being kept.
+    pub fn restore(&mut self, network: &mut Network) {
+        if let Some(best) = self.best_network.take() {
+            *network = best;
+        }
+    }
+}
+
+impl Default for StopConditions {
+    fn default() -> Self {
+        Self {
+            early_stopping: None,
+            target_error: None,
+            max_duration: None,
+            validation_interval: 1,
+        }
+    }
+}
+
+impl StopConditions {
+    pub fn reset(&mut self) {
+        if let Some(early_stopping) = &mut self.early_stopping {
+            early_stopping.reset();
+        }
+    }
+
+    /// Whether the validation set should be evaluated after the given epoch.
+    pub fn validation_due(&self, epoch: usize) -> bool {
+        let needed = self.target_error.is_some()
+            || self
+                .early_stopping
+                .as_ref()
+                .is_some_and(|early_stopping| early_stopping.monitor.needs_validation());
+        needed && epoch.checked_rem(self.validation_interval) == Some(0)
+    }
+
+    /// Checks every configured condition, restoring the best weights when early stopping fires.
+    pub fn check(
+        &mut self,
+        observation: &Observation<'_>,
+        network: &mut Network,
+    ) -> Option<StopReason> {
+        if let Some(early_stopping) = &mut self.early_stopping {
+            if early_stopping.update(observation, network) {
+                let (best_epoch, best_value) = early_stopping.best().unwrap();
+                if early_stopping.restore_best {
+                    early_stopping.restore(network);
+                }
+                return Some(StopReason::EarlyStopping {
+                    best_epoch,
+                    best_value,
+                });
+            }
+        }
+
+        if let Some(target) = self.target_error {
+            // With a validation set only its loss counts, epochs without it are skipped
+            let error = if observation.has_validation_set {
+                observation.validation_loss
+            } else {
+                observation.training_loss
+            };
+            if let Some(error) = error {
+                if error <= target {
+                    return Some(StopReason::TargetErrorReached { error });
+                }
+            }
+        }
+
+        match self.max_duration {
+            Some(max_duration) if observation.elapsed >= max_duration => {
+                Some(StopReason::TimeLimit {
+                    elapsed: observation.elapsed,
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Display for StopReason {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StopReason::EpochsCompleted => write!(f, "All scheduled epochs completed"),
+            StopReason::EarlyStopping {
+                best_epoch,
+                best_value,
+            } => write!(
+                f,
+                "Early stopping, best value {:.6} at epoch {}",
+                best_value, best_epoch
+            ),
+            StopReason::TargetErrorReached { error } => {
+                write!(f, "Target error reached ({:.6})", error)
+            }
+            StopReason::TimeLimit { elapsed } => {
+                write!(f, "Time limit reached after {:.1}s", elapsed.as_secs_f64())
+            }
+            StopReason::Converged => write!(f, "Training algorithm converged"),
+        }
+    }
+}
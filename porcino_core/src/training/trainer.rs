@@ -0,0 +1,74 @@
+use crate::network::Network;
+use ndarray::{s, Array1};
+use porcino_data::parse::TrainingSample;
+use std::fmt::Debug;
+
+/// Algorithm updating the weights of a network, one epoch at a time.
+pub trait Trainer: Debug + Send {
+    /// Trains on the whole data set once, returning the summed error of the batch.
+    /// `eta` is the learning rate of the current epoch, trainers adapting their own
+    /// step sizes ignore it.
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], eta: f64) -> f64;
+
+    /// Whether the learning rate schedule has any effect
+    fn uses_learning_rate(&self) -> bool {
+        true
+    }
+
+    /// Whether more epochs would leave the weights as they are, e.g. after a closed-form solve
+    fn converged(&self) -> bool {
+        false
+    }
+}
+
+/// Full-batch gradient descent, see `Network::gradient_descent`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct GradientDescent;
+
+impl Trainer for GradientDescent {
+    fn epoch(&mut self, network: &mut Network, data: &[TrainingSample], eta: f64) -> f64 {
+        network.gradient_descent(data, eta)
+    }
+}
+
+/// What the trainers working on the flattened parameters minimize: half the summed loss,
+/// whose gradient backpropagation computes, plus the regularization penalties
+#[derive(Debug, Clone)]
+pub(crate) struct Objective {
+    /// Summed error of the batch including the penalties, like `Network::accumulate_gradient`
+    pub error: f64,
+    pub value: f64,
+    /// Gradient of `value`, laid out like `Network::params_vector`
+    pub gradient: Array1<f64>,
+}
+
+impl Objective {
+    /// Evaluates the network at its current parameters. Decoupled weight decay and
+    /// max-norm constraints are not part of the objective and have no effect.
+    pub fn at(network: &mut Network, data: &[TrainingSample]) -> Self {
+        let error = network.accumulate_gradient(data);
+        let loss = error - network.regularization_loss();
+
+        let mut gradient = network.gradient_vector();
+        let mut penalty = 0.0;
+        let mut offset = 0;
+        for layer in network.layers.iter_mut() {
+            let reg = layer.regularization();
+            for param in layer.params_mut() {
+                let len = param.value.len();
+                if reg.applies_to(param.kind) {
+                    penalty += reg.penalty(param.value);
+                    let mut slice = gradient.slice_mut(s![offset..offset + len]);
+                    slice.assign(&Array1::from_iter(reg.regularized_gradient(&param)));
+                }
+                offset += len;
+            }
+        }
+
+        Self {
+            error,
+            value: loss / 2.0 + penalty,
+            gradient,
+        }
+    }
+}
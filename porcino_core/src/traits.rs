@@ -1,11 +1,133 @@
+use crate::autodiff::{Tape, Var};
+use crate::enums::Mode;
+use crate::network::{Activations, Regularization};
+use crate::persistence::SavedLayer;
 use ndarray::Array2;
+use std::fmt::Debug;
+
 pub trait Activation {
     fn function(&self, z: &Array2<f64>) -> Array2<f64>;
     fn derivative(&self, z: &Array2<f64>, val: Option<&Array2<f64>>) -> Array2<f64>;
+    fn kind(&self) -> Activations;
+
+    /// Records the activation of `z` on a tape. Activations without an operation of
+    /// their own are recorded through `function` and `derivative`.
+    fn record(&self, tape: &mut Tape, z: Var) -> Var {
+        let value = self.function(tape.value(z));
+        let derivative = self.derivative(tape.value(z), Some(&value));
+        tape.map(z, value, derivative)
+    }
 }
 
-pub trait Layer {
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParamKind {
+    Weights,
+    Biases,
+    Other,
+}
+
+/// Trainable parameter of a layer together with its accumulated gradient
+pub struct Param<'a> {
+    pub kind: ParamKind,
+    pub value: &'a mut Array2<f64>,
+    pub grad: &'a Array2<f64>,
+}
+
+/// Building block of a `Network`. Inputs and outputs hold one sample per column.
+pub trait Layer: Debug + Send {
     fn feed_forward(&mut self, input: &Array2<f64>) -> &Array2<f64>;
+
+    /// Takes the gradient of the loss with respect to the last output of the layer,
+    /// adds the resulting parameter gradients to the accumulated ones and returns
+    /// the gradient with respect to the layer's input.
+    fn backward(&mut self, delta: &Array2<f64>) -> Array2<f64>;
+
+    /// Result of the last `feed_forward`
+    fn output(&self) -> &Array2<f64>;
+
+    fn params(&self) -> Vec<&Array2<f64>> {
+        Vec::new()
+    }
+
+    /// Accumulated gradients, in the same order as `params`
+    fn grads(&self) -> Vec<&Array2<f64>> {
+        Vec::new()
+    }
+
+    fn params_mut(&mut self) -> Vec<Param<'_>> {
+        Vec::new()
+    }
+
+    fn zero_grads(&mut self) {}
+
+    /// Whether the layer is a fully connected one, with weights and biases only
+    fn is_dense(&self) -> bool {
+        false
+    }
+
+    fn set_mode(&mut self, _mode: Mode) {}
+
+    fn regularization(&self) -> Regularization {
+        Regularization::default()
+    }
+
+    fn set_regularization(&mut self, _regularization: Regularization) {}
+
+    /// Penalty added to the loss by the layer's regularization
+    fn regularization_loss(&self) -> f64 {
+        0.0
+    }
+
+    fn save(&self) -> SavedLayer;
+
+    fn box_clone(&self) -> Box<dyn Layer>;
+}
+
+impl Clone for Box<dyn Layer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Layer keeping a hidden state between the steps of a sequence. Every step is a
+/// matrix with one sample per column.
+pub trait RecurrentLayer: Debug + Send {
+    /// Forgets the hidden state, so that the next step starts a new sequence
+    fn reset_state(&mut self);
+
+    /// Runs the steps on from the current hidden state, returning the hidden state
+    /// after each of them. Only the steps of the last call are kept for `backward`.
+    fn forward(&mut self, inputs: &[Array2<f64>]) -> Vec<Array2<f64>>;
+
+    /// Backpropagation through the steps of the last `forward`. Takes the gradient of
+    /// the loss with respect to every returned hidden state, accumulates the parameter
+    /// gradients and returns the gradient with respect to every input. The gradient
+    /// reaching the state the steps started from is dropped.
+    fn backward(&mut self, deltas: &[Array2<f64>]) -> Vec<Array2<f64>>;
+
+    fn hidden_size(&self) -> usize;
+
+    fn params(&self) -> Vec<&Array2<f64>>;
+
+    /// Accumulated gradients, in the same order as `params`
+    fn grads(&self) -> Vec<&Array2<f64>>;
+
+    fn params_mut(&mut self) -> Vec<Param<'_>>;
+
+    fn zero_grads(&mut self);
+
+    fn box_clone(&self) -> Box<dyn RecurrentLayer>;
+}
+
+impl Clone for Box<dyn RecurrentLayer> {
+    fn clone(&self) -> Self {
+        self.box_clone()
+    }
+}
+
+/// Anything mapping inputs to outputs, one sample per column
+pub trait Predictor {
+    fn predict(&mut self, input: &Array2<f64>) -> Array2<f64>;
 }
 
 pub trait ErrorFn {
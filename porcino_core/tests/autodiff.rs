@@ -0,0 +1,206 @@
+use ndarray::{array, Array2};
+use porcino_core::autodiff::{loss_gradient, Tape, Var};
+use porcino_core::data::stack_samples;
+use porcino_core::enums::InitializationMethods;
+use porcino_core::errors::Loss;
+use porcino_core::gradcheck::check_gradients;
+use porcino_core::network::{ExprLayer, FFLayer, Network, Sigmoid};
+use porcino_core::persistence::SavedNetwork;
+use porcino_data::parse::TrainingSample;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const EPSILON: f64 = 1e-6;
+const TOLERANCE: f64 = 1e-6;
+
+fn random(rng: &mut StdRng, rows: usize, cols: usize) -> Array2<f64> {
+    Array2::from_shape_fn((rows, cols), |_| rng.gen_range(0.1..1.0))
+}
+
+fn close(a: &Array2<f64>, b: &Array2<f64>, tolerance: f64) -> bool {
+    a.iter().zip(b).all(|(a, b)| (a - b).abs() <= tolerance)
+}
+
+// Compares the tape's gradient of `expression` summed up against central differences
+fn check(inputs: Vec<Array2<f64>>, expression: impl Fn(&mut Tape, &[Var]) -> Var) {
+    let evaluate = |values: &[Array2<f64>]| {
+        let mut tape = Tape::new();
+        let vars = values
+            .iter()
+            .map(|v| tape.var(v.clone()))
+            .collect::<Vec<_>>();
+        let output = expression(&mut tape, &vars);
+        let grads = tape.backward(output);
+        let analytic = vars
+            .iter()
+            .map(|v| {
+                grads
+                    .of(*v)
+                    .cloned()
+                    .unwrap_or_else(|| Array2::zeros(tape.value(*v).raw_dim()))
+            })
+            .collect::<Vec<_>>();
+        (tape.value(output).sum(), analytic)
+    };
+
+    let (_, analytic) = evaluate(&inputs);
+    for (input, grad) in analytic.iter().enumerate() {
+        for (index, expected) in grad.indexed_iter() {
+            let mut nudged = inputs.clone();
+            nudged[input][index] += EPSILON;
+            let above = evaluate(&nudged).0;
+            nudged[input][index] -= 2.0 * EPSILON;
+            let below = evaluate(&nudged).0;
+            let numeric = (above - below) / (2.0 * EPSILON);
+            assert!(
+                (numeric - expected).abs() <= TOLERANCE * numeric.abs().max(1.0),
+                "input {input} at {index:?}: {numeric} != {expected}"
+            );
+        }
+    }
+}
+
+#[test]
+fn operations() {
+    let mut rng = StdRng::seed_from_u64(3);
+    let (a, b) = (random(&mut rng, 3, 4), random(&mut rng, 3, 4));
+    let column = random(&mut rng, 3, 1);
+    let square = random(&mut rng, 4, 3);
+
+    check(vec![a.clone(), square], |t, v| t.matmul(v[0], v[1]));
+    check(vec![a.clone(), b.clone()], |t, v| t.add(v[0], v[1]));
+    check(vec![a.clone(), column.clone()], |t, v| t.add(v[0], v[1]));
+    check(vec![a.clone(), column.clone()], |t, v| t.sub(v[0], v[1]));
+    check(vec![a.clone(), b.clone()], |t, v| t.mul(v[0], v[1]));
+    check(vec![a.clone(), column], |t, v| t.div(v[0], v[1]));
+    check(vec![a.clone(), b.clone()], |t, v| t.concat(&[v[0], v[1]]));
+
+    let unary: [fn(&mut Tape, Var) -> Var; 17] = [
+        |t, x| t.neg(x),
+        |t, x| t.scale(x, 3.0),
+        |t, x| t.offset(x, 2.0),
+        |t, x| t.powi(x, 3),
+        |t, x| t.square(x),
+        |t, x| t.exp(x),
+        |t, x| t.ln(x),
+        |t, x| t.sqrt(x),
+        |t, x| t.sigmoid(x),
+        |t, x| t.tanh(x),
+        |t, x| t.relu(x),
+        |t, x| t.transpose(x),
+        |t, x| t.sum(x),
+        |t, x| t.sum_cols(x),
+        |t, x| t.mean(x),
+        |t, x| t.rows(x, 1, 2),
+        // Used several times, so gradients have to add up
+        |t, x| {
+            let y = t.mul(x, x);
+            let z = t.sigmoid(y);
+            t.mul(z, x)
+        },
+    ];
+    for op in unary {
+        check(vec![a.clone()], |t, v| op(t, v[0]));
+    }
+}
+
+#[test]
+fn losses_match_closed_forms() {
+    let output = array![[0.2, 0.9], [0.6, 0.4]];
+    let reference = array![[0.0, 1.0], [1.0, 0.0]];
+
+    let (sse, sse_gradient) = Loss::Sse.evaluate(&output, &reference);
+    assert!((sse - (&output - &reference).mapv(|e| e * e).sum()).abs() < 1e-12);
+    assert!(close(&sse_gradient, &(&output - &reference), 1e-12));
+
+    let (ce, ce_gradient) = Loss::CrossEntropy.evaluate(&output, &reference);
+    let expected = output
+        .iter()
+        .zip(&reference)
+        .map(|(p, y)| -(y * p.ln() + (1.0 - y) * (1.0 - p).ln()))
+        .sum::<f64>();
+    let expected_gradient = (&output - &reference) / (&output * (1.0 - &output) * 2.0);
+    assert!((ce - expected).abs() < 1e-12);
+    assert!(close(&ce_gradient, &expected_gradient, 1e-12));
+}
+
+fn swish(tape: &mut Tape, input: Var, params: &[Var]) -> Var {
+    let weighted = tape.matmul(params[0], input);
+    let z = tape.add(weighted, params[1]);
+    let gate = tape.sigmoid(z);
+    tape.mul(z, gate)
+}
+
+fn network() -> Network {
+    let mut rng = StdRng::seed_from_u64(5);
+    let swish = ExprLayer::new(
+        "swish",
+        swish,
+        vec![random(&mut rng, 4, 3), Array2::zeros((4, 1))],
+    );
+    Network::from_layers(vec![
+        Box::new(swish),
+        Box::new(FFLayer::new(4, 2, InitializationMethods::Random, &Sigmoid)),
+    ])
+}
+
+fn samples() -> Vec<TrainingSample> {
+    let mut rng = StdRng::seed_from_u64(9);
+    (0..5)
+        .map(|_| TrainingSample {
+            input: random(&mut rng, 3, 1),
+            expected_output: random(&mut rng, 2, 1),
+        })
+        .collect()
+}
+
+#[test]
+fn expression_layer() {
+    let check = check_gradients(&mut network(), &samples(), EPSILON);
+    assert!(check.passed(1e-5), "{check:?}");
+}
+
+#[test]
+fn expression_layer_round_trip() {
+    let mut network = network();
+    let saved = serde_json::to_string(&SavedNetwork::from(&network)).unwrap();
+    let mut read =
+        Network::try_from(serde_json::from_str::<SavedNetwork>(&saved).unwrap()).unwrap();
+
+    let input = random(&mut StdRng::seed_from_u64(1), 3, 4);
+    network.process_data(&input);
+    read.process_data(&input);
+    assert!(close(network.output(), read.output(), 1e-12));
+}
+
+#[test]
+fn unregistered_expressions_are_errors() {
+    let saved = serde_json::to_string(&SavedNetwork::from(&network()))
+        .unwrap()
+        .replace("\"swish\"", "\"never_registered\"");
+    let saved = serde_json::from_str::<SavedNetwork>(&saved).unwrap();
+    assert!(Network::try_from(saved).is_err());
+}
+
+#[test]
+fn custom_loss() {
+    // Sum of absolute errors, scored on the tape
+    let absolute = |tape: &mut Tape, output: Var, reference: Var| {
+        let error = tape.sub(output, reference);
+        let squared = tape.square(error);
+        let absolute = tape.sqrt(squared);
+        tape.sum(absolute)
+    };
+    let data = samples();
+    let mut network = network();
+    let error = network.accumulate_gradient_with(&data, absolute);
+
+    let (inputs, references) = stack_samples(&data);
+    network.process_data(&inputs);
+    let expected = (network.output() - &references).mapv(f64::abs).sum();
+    assert!((error - expected).abs() < 1e-12);
+
+    let (_, gradient) = loss_gradient(absolute, network.output(), &references);
+    let signs = (network.output() - &references).mapv(|e| e.signum() / 2.0);
+    assert!(close(&gradient, &signs, 1e-9));
+}
@@ -0,0 +1,44 @@
+use ndarray::array;
+use porcino_core::cascade::{CascadeCorrelation, CascadeTraining};
+use porcino_core::data::stack_samples;
+use porcino_core::network::Network;
+use porcino_core::persistence::SavedNetwork;
+use porcino_core::traits::Predictor;
+use porcino_data::parse::TrainingSample;
+
+fn curve() -> Vec<TrainingSample> {
+    (0..40)
+        .map(|i| {
+            let x = i as f64 / 40.0 * 6.0 - 3.0;
+            TrainingSample {
+                input: array![[x]],
+                expected_output: array![[x.sin()]],
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn cascade_grows_until_target_error() {
+    let data = curve();
+    let training = CascadeTraining {
+        target_error: 1e-3,
+        ..Default::default()
+    };
+    let mut cascade = CascadeCorrelation::fit(&data, training);
+
+    // A line can't follow a sine, so units had to be added, each one helping
+    assert!(cascade.hidden_units() > 0);
+    assert!(cascade.hidden_units() < training.max_hidden);
+    let history = cascade.history();
+    assert!(history.windows(2).all(|pair| pair[1] <= pair[0] + 1e-12));
+    assert!(*history.last().unwrap() <= training.target_error);
+
+    // The exported network survives serialization and predicts the same
+    let (inputs, _) = stack_samples(&data);
+    let saved = serde_json::to_string(&SavedNetwork::from(&cascade.to_network())).unwrap();
+    let mut network =
+        Network::try_from(serde_json::from_str::<SavedNetwork>(&saved).unwrap()).unwrap();
+    let difference = network.predict(&inputs) - cascade.predict(&inputs);
+    assert!(difference.iter().all(|d| d.abs() < 1e-9));
+}
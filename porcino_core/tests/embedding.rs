@@ -0,0 +1,57 @@
+use porcino_core::data::stack_samples;
+use porcino_core::enums::InitializationMethods;
+use porcino_core::network::Embedding;
+use porcino_core::traits::Layer;
+use porcino_data::parse::{
+    get_sampled_data, parse_data_file, ClassType, ColumnType, DataSettings, Metadata, ParameterType,
+};
+
+const CSV: &str = "red,1.5,x,small,yes
+green,2.5,y,large,no
+blue,0.5,z,small,yes
+red,3.5,w,large,no
+";
+
+#[test]
+fn category_cardinality_sizes_the_tables() {
+    let path = std::env::temp_dir().join(format!("porcino_{}_categories.csv", std::process::id()));
+    std::fs::write(&path, CSV).unwrap();
+    let settings = DataSettings {
+        columns: vec![
+            ColumnType::Parameter(ParameterType::Label),
+            ColumnType::Parameter(ParameterType::Numeric),
+            ColumnType::Ignored,
+            ColumnType::Parameter(ParameterType::Label),
+            ColumnType::Class(ClassType::Label),
+        ],
+    };
+    let data = parse_data_file(&path, &settings, false, ",").unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Distinct labels seen in every categorical param, the ignored column and the class
+    // don't count
+    assert_eq!(data.meta.param_categories, [Some(3), None, Some(2)]);
+    assert_eq!(data.meta.categories(), [Some(3), None, Some(2)]);
+
+    let mut embedding = Embedding::new(&data.meta.categories(), 4, InitializationMethods::One);
+    let shapes: Vec<_> = embedding.tables.iter().map(|table| table.dim()).collect();
+    assert_eq!(shapes, [(3, 4), (2, 4)]);
+    assert_eq!(embedding.outputs(), 9);
+
+    let (inputs, _) = stack_samples(&get_sampled_data(&data));
+    assert_eq!(embedding.feed_forward(&inputs).dim(), (9, 4));
+}
+
+#[test]
+fn metadata_without_categories_reads_as_numeric() {
+    let meta = Metadata {
+        params: vec![0, 1],
+        classes: vec![2],
+        ..Default::default()
+    };
+    assert_eq!(meta.categories(), [None, None]);
+    assert_eq!(
+        Embedding::new(&meta.categories(), 4, InitializationMethods::One).outputs(),
+        2
+    );
+}
@@ -0,0 +1,127 @@
+use ndarray::{array, Array2};
+use porcino_core::energy::{pretrain, Hopfield, LearningRule, Rbm, RbmTraining};
+use porcino_core::enums::{InitializationMethods, Mode};
+use porcino_core::network::{Dropout, FFLayer, Linear, Network, Sigmoid};
+use porcino_data::parse::TrainingSample;
+
+// Three bipolar patterns over 16 units, one per column
+fn patterns() -> Array2<f64> {
+    Array2::from_shape_fn((16, 3), |(unit, pattern)| match pattern {
+        0 => {
+            if unit < 8 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+        1 => {
+            if unit % 2 == 0 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+        _ => {
+            if (unit / 2) % 2 == 0 {
+                1.0
+            } else {
+                -1.0
+            }
+        }
+    })
+}
+
+// Horizontal and vertical bars on a 3x3 image, with the bar itself as the target
+fn bars() -> Vec<TrainingSample> {
+    (0..6)
+        .map(|bar| {
+            let input = Array2::from_shape_fn((9, 1), |(pixel, _)| {
+                let (row, col) = (pixel / 3, pixel % 3);
+                if (bar < 3 && row == bar) || (bar >= 3 && col == bar - 3) {
+                    1.0
+                } else {
+                    0.0
+                }
+            });
+            TrainingSample {
+                input,
+                expected_output: array![[bar as f64 / 5.0]],
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn hopfield_recalls_noisy_patterns() {
+    let stored = patterns();
+    for rule in [LearningRule::Hebbian, LearningRule::Storkey] {
+        let mut memory = Hopfield::new(16);
+        memory.store(&stored, rule);
+        assert_eq!(memory.weights, memory.weights.t());
+
+        // Two flipped units in every pattern
+        let mut noisy = stored.clone();
+        for (pattern, mut column) in noisy.columns_mut().into_iter().enumerate() {
+            column[pattern] *= -1.0;
+            column[15 - pattern] *= -1.0;
+        }
+        let recalled = memory.recall(&noisy, 10);
+        assert_eq!(recalled, stored, "{rule:?}");
+        for (before, after) in noisy.columns().into_iter().zip(recalled.columns()) {
+            assert!(memory.energy(after) <= memory.energy(before));
+        }
+    }
+}
+
+#[test]
+fn rbm_learns_to_reconstruct() {
+    let (data, _) = porcino_core::data::stack_samples(&bars());
+    let training = RbmTraining {
+        epochs: 500,
+        batch_size: 6,
+        ..RbmTraining::default()
+    };
+    let mut rbm = Rbm::new(9, 6, 1);
+    let before = (&rbm.reconstruct(&data) - &data).mapv(|e| e * e).sum();
+    rbm.train(&data, &training);
+    let after = (&rbm.reconstruct(&data) - &data).mapv(|e| e * e).sum();
+    assert!(after < before / 2.0, "error {after} from {before}");
+}
+
+#[test]
+fn pretraining_initializes_hidden_layers() {
+    let data = bars();
+    let mut network = Network::from_layers(vec![
+        Box::new(FFLayer::new(9, 6, InitializationMethods::Zero, &Sigmoid)),
+        Box::new(FFLayer::new(6, 4, InitializationMethods::Zero, &Sigmoid)),
+        Box::new(FFLayer::new(4, 1, InitializationMethods::Zero, &Linear)),
+    ]);
+    let errors = pretrain(&mut network, &data, &RbmTraining::default());
+    assert_eq!(errors.len(), 2);
+    for layer in &network.layers[..2] {
+        assert!(layer.params()[0].iter().any(|w| *w != 0.0));
+    }
+    // Output layer is left to supervised training
+    assert!(network.layers[2].params()[0].iter().all(|w| *w == 0.0));
+
+    let start = network.accumulate_gradient(&data);
+    let mut error = start;
+    for _ in 0..500 {
+        error = network.gradient_descent(&data, 0.1);
+    }
+    assert!(error < start, "error {error} from {start}");
+}
+
+#[test]
+fn pretraining_keeps_the_mode() {
+    let data = bars();
+    let mut network = Network::from_layers(vec![
+        Box::new(FFLayer::new(9, 6, InitializationMethods::Zero, &Sigmoid)),
+        Box::new(Dropout::with_seed(0.5, 1)),
+        Box::new(FFLayer::new(6, 1, InitializationMethods::Zero, &Linear)),
+    ]);
+    network.set_mode(Mode::Inference);
+    let errors = pretrain(&mut network, &data, &RbmTraining::default());
+    assert_eq!(errors.len(), 1);
+    assert_eq!(network.mode(), Mode::Inference);
+}
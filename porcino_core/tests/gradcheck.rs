@@ -0,0 +1,235 @@
+use ndarray::Array2;
+use porcino_core::enums::{InitializationMethods, Mode};
+use porcino_core::errors::Loss;
+use porcino_core::gradcheck::{check_gradients, check_sequence_gradients, GradientCheck};
+use porcino_core::network::{
+    Activations, AvgPool, BatchNorm, Conv1D, Conv2D, Dropout, Embedding, FFLayer, Flatten,
+    GraphBuilder, Head, Linear, MaxPool, Network, Rbf, RbfKernel, Shape, Sigmoid, Tanh,
+};
+use porcino_core::recurrent::{Elman, Gru, Lstm, SequenceNetwork};
+use porcino_core::traits::{Activation, Layer, RecurrentLayer};
+use porcino_data::parse::TrainingSample;
+use porcino_data::sequence::{SequenceMode, SequenceSample};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const EPSILON: f64 = 1e-6;
+const TOLERANCE: f64 = 1e-5;
+
+const INIT: InitializationMethods = InitializationMethods::Random;
+
+fn random(rng: &mut StdRng, rows: usize, cols: usize) -> Array2<f64> {
+    Array2::from_shape_fn((rows, cols), |_| rng.gen_range(-1.0..1.0))
+}
+
+fn samples(inputs: usize, outputs: usize, count: usize) -> Vec<TrainingSample> {
+    let mut rng = StdRng::seed_from_u64(7);
+    (0..count)
+        .map(|_| TrainingSample {
+            input: random(&mut rng, inputs, 1),
+            // Between 0 and 1, so the same targets also suit cross-entropy
+            expected_output: random(&mut rng, outputs, 1).mapv(|v| (v + 1.0) / 2.0),
+        })
+        .collect()
+}
+
+fn assert_close(check: &GradientCheck) {
+    for (layer, result) in check.layers.iter().enumerate() {
+        assert!(
+            result.max_relative_error <= TOLERANCE,
+            "layer {layer} is off by {:e}",
+            result.max_relative_error
+        );
+    }
+}
+
+fn check_chain(layers: Vec<Box<dyn Layer>>, inputs: usize, outputs: usize) {
+    let mut network = Network::from_layers(layers);
+    let check = check_gradients(&mut network, &samples(inputs, outputs, 4), EPSILON);
+    assert_close(&check);
+}
+
+#[test]
+fn activations() {
+    let activations: [&'static (dyn Activation + Send + Sync); 3] = [&Sigmoid, &Linear, &Tanh];
+    for activation in activations {
+        check_chain(
+            vec![
+                Box::new(FFLayer::new(3, 5, INIT, activation)),
+                Box::new(FFLayer::new(5, 2, INIT, activation)),
+            ],
+            3,
+            2,
+        );
+    }
+}
+
+#[test]
+fn losses() {
+    for loss in [Loss::Sse, Loss::CrossEntropy] {
+        let heads = vec![
+            Head {
+                activation: Activations::Sigmoid,
+                loss,
+                ..Head::new("first", vec![0, 2])
+            },
+            Head {
+                activation: Activations::Sigmoid,
+                loss,
+                weight: 0.5,
+                ..Head::new("second", vec![1])
+            },
+        ];
+        let trunk: Vec<Box<dyn Layer>> = vec![Box::new(FFLayer::new(3, 4, INIT, &Tanh))];
+        let mut network = Network::with_heads(trunk, 4, heads, INIT);
+        let check = check_gradients(&mut network, &samples(3, 3, 4), EPSILON);
+        assert_eq!(check.layers.len(), 3);
+        assert_close(&check);
+    }
+}
+
+#[test]
+#[should_panic(expected = "at least one head")]
+fn heads_are_needed() {
+    let trunk: Vec<Box<dyn Layer>> = vec![Box::new(FFLayer::new(3, 4, INIT, &Tanh))];
+    Network::with_heads(trunk, 4, Vec::new(), INIT);
+}
+
+#[test]
+fn batch_norm() {
+    check_chain(
+        vec![
+            Box::new(FFLayer::new(3, 4, INIT, &Linear)),
+            Box::new(BatchNorm::new(4)),
+            Box::new(FFLayer::new(4, 2, INIT, &Sigmoid)),
+        ],
+        3,
+        2,
+    );
+}
+
+#[test]
+fn dropout_in_inference() {
+    let mut network = Network::from_layers(vec![
+        Box::new(FFLayer::new(3, 4, INIT, &Tanh)),
+        Box::new(Dropout::with_seed(0.5, 1)),
+        Box::new(FFLayer::new(4, 2, INIT, &Sigmoid)),
+    ]);
+    network.set_mode(Mode::Inference);
+    assert_close(&check_gradients(&mut network, &samples(3, 2, 4), EPSILON));
+}
+
+#[test]
+fn convolution_and_pooling() {
+    let input = Shape::new(2, 5, 5);
+    let conv = Conv2D::new(input, 3, (3, 3), INIT, &Tanh).with_padding((1, 1));
+    let conv_out = conv.output_shape();
+    let max = MaxPool::new(conv_out, (2, 2));
+    let max_out = max.output_shape();
+    let avg = AvgPool::with_stride(max_out, (2, 2), (1, 1));
+    let flatten = Flatten::new(avg.output_shape());
+    let flat = flatten.outputs();
+    check_chain(
+        vec![
+            Box::new(conv),
+            Box::new(max),
+            Box::new(avg),
+            Box::new(flatten),
+            Box::new(FFLayer::new(flat, 2, INIT, &Sigmoid)),
+        ],
+        input.size(),
+        2,
+    );
+}
+
+#[test]
+fn convolution_1d() {
+    let input = Shape::signal(2, 8);
+    let conv = Conv1D::new(input, 3, 3, INIT, &Sigmoid).with_stride(2);
+    let flatten = Flatten::new(conv.output_shape());
+    let flat = flatten.outputs();
+    check_chain(
+        vec![
+            Box::new(conv),
+            Box::new(flatten),
+            Box::new(FFLayer::new(flat, 2, INIT, &Linear)),
+        ],
+        input.size(),
+        2,
+    );
+}
+
+#[test]
+fn embedding() {
+    let categories = [Some(3), None, Some(4), None];
+    let embedding = Embedding::new(&categories, 2, INIT);
+    let outputs = embedding.outputs();
+    let mut network = Network::from_layers(vec![
+        Box::new(embedding),
+        Box::new(FFLayer::new(outputs, 2, INIT, &Sigmoid)),
+    ]);
+    let mut data = samples(4, 2, 6);
+    for (i, sample) in data.iter_mut().enumerate() {
+        sample.input[[0, 0]] = (i % 3) as f64;
+        sample.input[[2, 0]] = (i % 4) as f64;
+    }
+    assert_close(&check_gradients(&mut network, &data, EPSILON));
+}
+
+#[test]
+fn radial_basis() {
+    let data = samples(3, 2, 6);
+    for kernel in [RbfKernel::Gaussian, RbfKernel::Multiquadric] {
+        let mut rng = StdRng::seed_from_u64(3);
+        let rbf = Rbf::new(
+            random(&mut rng, 4, 2),
+            random(&mut rng, 4, 1).mapv(|v| v.abs() + 0.5),
+            kernel,
+        );
+        // Only the layers around it have parameters, checked through its input gradient
+        let mut network = Network::from_layers(vec![
+            Box::new(FFLayer::new(3, 2, INIT, &Tanh)),
+            Box::new(rbf),
+            Box::new(FFLayer::new(4, 2, INIT, &Sigmoid)),
+        ]);
+        assert_close(&check_gradients(&mut network, &data, EPSILON));
+    }
+}
+
+#[test]
+fn graph_joins() {
+    let mut graph = GraphBuilder::new();
+    let first = graph.input(2);
+    let second = graph.input(3);
+    let a = graph.layer(Box::new(FFLayer::new(2, 4, INIT, &Tanh)), first);
+    let b = graph.layer(Box::new(FFLayer::new(3, 4, INIT, &Sigmoid)), second);
+    let sum = graph.add(&[a, b]);
+    let joined = graph.concat(&[sum, a]);
+    let out = graph.layer(Box::new(FFLayer::new(8, 2, INIT, &Linear)), joined);
+    let side = graph.layer(Box::new(FFLayer::new(4, 1, INIT, &Sigmoid)), sum);
+    let mut network = graph.build(&[out, side]);
+    assert_close(&check_gradients(&mut network, &samples(5, 3, 4), EPSILON));
+}
+
+#[test]
+fn recurrent_cells() {
+    let cells: [Box<dyn RecurrentLayer>; 3] = [
+        Box::new(Elman::new(2, 3, INIT, &Tanh)),
+        Box::new(Lstm::new(2, 3, INIT)),
+        Box::new(Gru::new(2, 3, INIT)),
+    ];
+    let mut rng = StdRng::seed_from_u64(11);
+    let data = (0..3)
+        .map(|_| SequenceSample {
+            inputs: (0..4).map(|_| random(&mut rng, 2, 1)).collect(),
+            expected_outputs: (0..4).map(|_| random(&mut rng, 1, 1)).collect(),
+        })
+        .collect::<Vec<_>>();
+    for cell in cells {
+        for mode in [SequenceMode::ManyToOne, SequenceMode::ManyToMany] {
+            let head = Network::from_layers(vec![Box::new(FFLayer::new(3, 1, INIT, &Linear))]);
+            let mut network = SequenceNetwork::new(vec![cell.clone()], head, mode);
+            assert_close(&check_sequence_gradients(&mut network, &data, EPSILON));
+        }
+    }
+}
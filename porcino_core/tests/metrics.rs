@@ -0,0 +1,181 @@
+use ndarray::{array, Array2};
+use porcino_core::metrics::{
+    ClassificationReport, ConfusionMatrix, EvaluationReport, Evaluator, RegressionReport,
+    TargetTransform, Task,
+};
+use porcino_data::parse::{ClassType, Metadata, Scaling, TrainingSample};
+
+fn samples(targets: &[Array2<f64>]) -> Vec<TrainingSample> {
+    targets
+        .iter()
+        .map(|target| TrainingSample {
+            input: array![[0.0]],
+            expected_output: target.clone(),
+        })
+        .collect()
+}
+
+fn confusion(counts: &[[usize; 2]; 2]) -> ConfusionMatrix {
+    let mut confusion = ConfusionMatrix::new(2);
+    for (actual, row) in counts.iter().enumerate() {
+        for (predicted, count) in row.iter().enumerate() {
+            for _ in 0..*count {
+                confusion.add(actual, predicted);
+            }
+        }
+    }
+    confusion
+}
+
+#[test]
+fn confusion_matrix_counts() {
+    let confusion = confusion(&[[20, 5], [10, 15]]);
+    assert_eq!(confusion.total(), 50);
+    assert_eq!(confusion.correct(), 35);
+    assert_eq!(confusion.true_positives(1), 15);
+    assert_eq!(confusion.false_positives(1), 5);
+    assert_eq!(confusion.false_negatives(1), 10);
+
+    let scores = confusion.scores(1);
+    assert!((scores.precision - 0.75).abs() < 1e-12);
+    assert!((scores.recall - 0.6).abs() < 1e-12);
+    assert!((scores.f1 - 2.0 * 0.75 * 0.6 / 1.35).abs() < 1e-12);
+    assert_eq!(scores.support, 25);
+}
+
+#[test]
+fn kappa_corrects_for_chance() {
+    // Observed agreement 0.7, agreement expected by chance 0.5
+    assert!((confusion(&[[20, 5], [10, 15]]).kappa() - 0.4).abs() < 1e-12);
+    assert!((confusion(&[[25, 0], [0, 25]]).kappa() - 1.0).abs() < 1e-12);
+    // Predictions independent of the classes
+    assert!(confusion(&[[10, 10], [10, 10]]).kappa().abs() < 1e-12);
+    assert_eq!(confusion(&[[50, 0], [0, 0]]).kappa(), 1.0);
+}
+
+#[test]
+fn roc_auc_ranks_binary_outputs() {
+    let targets = [array![[0.0]], array![[0.0]], array![[1.0]], array![[1.0]]];
+    let outputs = [array![[0.1]], array![[0.4]], array![[0.35]], array![[0.8]]];
+    let report = ClassificationReport::new(&outputs, &samples(&targets));
+
+    // Three of the four positive-negative pairs are ordered correctly
+    assert_eq!(report.roc_auc.len(), 2);
+    for auc in report.roc_auc.iter() {
+        assert!((auc.unwrap() - 0.75).abs() < 1e-12);
+    }
+    assert!((report.macro_roc_auc.unwrap() - 0.75).abs() < 1e-12);
+    assert!((report.accuracy - 0.75).abs() < 1e-12);
+}
+
+#[test]
+fn roc_auc_needs_both_classes() {
+    let targets = [array![[1.0], [0.0]], array![[1.0], [0.0]]];
+    let outputs = [array![[0.9], [0.1]], array![[0.6], [0.4]]];
+    let report = ClassificationReport::new(&outputs, &samples(&targets));
+    assert!(report.roc_auc.iter().all(Option::is_none));
+    assert!(report.macro_roc_auc.is_none());
+}
+
+#[test]
+fn log_loss_of_one_hot_outputs() {
+    let targets = [array![[1.0], [0.0]], array![[0.0], [1.0]]];
+    let outputs = [array![[0.8], [0.2]], array![[0.4], [0.6]]];
+    let report = ClassificationReport::new(&outputs, &samples(&targets));
+    let expected = -(0.8f64.ln() + 0.6f64.ln()) / 2.0;
+    assert!((report.log_loss.unwrap() - expected).abs() < 1e-12);
+
+    // Label indices of more than two classes aren't probabilities
+    let targets = [array![[0.0]], array![[2.0]]];
+    let outputs = [array![[0.0]], array![[1.0]]];
+    let report = ClassificationReport::new(&outputs, &samples(&targets));
+    assert!(report.log_loss.is_none());
+    assert_eq!(report.confusion.counts[(2, 1)], 1);
+}
+
+#[test]
+fn several_label_columns_are_not_one_hot() {
+    let meta = Metadata {
+        classes: vec![1, 2],
+        class_types: vec![ClassType::Label, ClassType::Label],
+        ..Default::default()
+    };
+    assert_eq!(Task::from_metadata(&meta), Task::Regression);
+}
+
+#[test]
+fn regression_report_metrics() {
+    let targets = [array![[1.0]], array![[2.0]], array![[3.0]], array![[4.0]]];
+    let outputs = [array![[1.5]], array![[2.0]], array![[2.5]], array![[5.0]]];
+    let report = RegressionReport::new(&outputs, &samples(&targets), &TargetTransform::default());
+
+    assert!((report.mae - 0.5).abs() < 1e-12);
+    assert!((report.rmse - 0.375f64.sqrt()).abs() < 1e-12);
+    assert!((report.r2 - 0.7).abs() < 1e-12);
+    let mape = 100.0 * (0.5 + 0.5 / 3.0 + 0.25) / 4.0;
+    assert!((report.mape.unwrap() - mape).abs() < 1e-9);
+
+    // Residuals are actual - predicted: -0.5, 0, 0.5, -1
+    let residuals = report.residuals;
+    assert!((residuals.mean + 0.25).abs() < 1e-12);
+    assert!((residuals.median + 0.25).abs() < 1e-12);
+    assert_eq!((residuals.min, residuals.max), (-1.0, 0.5));
+}
+
+#[test]
+fn target_transform_restores_units() {
+    let transform = TargetTransform {
+        scaling: vec![
+            Some(Scaling {
+                min: 10.0,
+                max: 20.0,
+            }),
+            None,
+        ],
+    };
+    let restored = transform.inverse(&array![[0.5, 0.0], [0.3, 7.0]]);
+    assert_eq!(restored, array![[15.0, 10.0], [0.3, 7.0]]);
+
+    // Errors are reported in the units of the data, not the normalized ones
+    let transform = TargetTransform {
+        scaling: vec![Some(Scaling {
+            min: 10.0,
+            max: 20.0,
+        })],
+    };
+    let report = RegressionReport::new(
+        &[array![[0.5]], array![[0.2]]],
+        &samples(&[array![[0.6]], array![[0.2]]]),
+        &transform,
+    );
+    assert!((report.mae - 0.5).abs() < 1e-12);
+    assert_eq!(report.mape, Some(100.0 * (1.0 / 16.0) / 2.0));
+}
+
+#[test]
+fn task_follows_output_columns() {
+    let meta = |class_types: Vec<ClassType>| Metadata {
+        classes: (0..class_types.len()).collect(),
+        class_scaling: vec![None; class_types.len()],
+        class_types,
+        ..Default::default()
+    };
+    assert_eq!(
+        Task::from_metadata(&meta(vec![ClassType::Label])),
+        Task::Classification
+    );
+    assert_eq!(
+        Task::from_metadata(&meta(vec![ClassType::Value])),
+        Task::Regression
+    );
+    assert_eq!(
+        Task::from_metadata(&meta(vec![ClassType::Label, ClassType::NormalizedValue])),
+        Task::Regression
+    );
+    // Data saved before column types were recorded
+    assert_eq!(Task::from_metadata(&meta(vec![])), Task::Classification);
+
+    let evaluator = Evaluator::from_metadata(&meta(vec![ClassType::NormalizedValue]));
+    let report = evaluator.evaluate(&[array![[1.0]]], &samples(&[array![[1.0]]]));
+    assert!(matches!(report, EvaluationReport::Regression(_)));
+}
@@ -0,0 +1,75 @@
+use ndarray::array;
+use porcino_core::metrics::{EvaluationReport, Evaluator, Task};
+use porcino_core::parzen::{Grnn, Pnn};
+use porcino_core::traits::Predictor;
+use porcino_data::parse::TrainingSample;
+
+// Two interleaved rings of points, class 0 inside and class 1 outside
+fn rings(one_hot: bool) -> Vec<TrainingSample> {
+    (0..60)
+        .map(|i| {
+            let class = i % 2;
+            let radius = if class == 0 { 0.5 } else { 1.5 };
+            let angle = i as f64 * 0.37;
+            let expected_output = match (one_hot, class) {
+                (true, 0) => array![[1.0], [0.0]],
+                (true, _) => array![[0.0], [1.0]],
+                (false, class) => array![[class as f64]],
+            };
+            TrainingSample {
+                input: array![[radius * angle.cos()], [radius * angle.sin()]],
+                expected_output,
+            }
+        })
+        .collect()
+}
+
+fn curve() -> Vec<TrainingSample> {
+    (0..50)
+        .map(|i| {
+            let x = i as f64 / 50.0 * 6.0 - 3.0;
+            TrainingSample {
+                input: array![[x]],
+                expected_output: array![[x.sin()]],
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn pnn_separates_classes() {
+    for one_hot in [true, false] {
+        let data = rings(one_hot);
+        let mut pnn = Pnn::fit(&data);
+        assert!(pnn.sigma > 0.0);
+        assert_eq!(pnn.classes(), 2);
+        assert_eq!(pnn.leave_one_out(pnn.sigma).0, 0.0);
+
+        let output = pnn.predict(&array![[0.0], [1.5]]);
+        assert_eq!(output.nrows(), if one_hot { 2 } else { 1 });
+
+        let evaluator = Evaluator {
+            task: Task::Classification,
+            ..Evaluator::default()
+        };
+        match evaluator.report(&mut pnn, &data) {
+            EvaluationReport::Classification(report) => assert_eq!(report.accuracy, 1.0),
+            _ => panic!("classification expected"),
+        }
+    }
+}
+
+#[test]
+fn grnn_tunes_sigma_by_leave_one_out() {
+    let data = curve();
+    let mut grnn = Grnn::fit(&data);
+    let tuned = grnn.leave_one_out(grnn.sigma);
+    assert!(tuned < 1e-2, "leave-one-out error {tuned}");
+    assert!(tuned <= grnn.leave_one_out(grnn.sigma * 10.0));
+    assert!(tuned <= grnn.leave_one_out(grnn.sigma / 10.0));
+
+    let prediction = grnn.predict(&array![[0.5], [-1.0]].reversed_axes());
+    assert_eq!(prediction.dim(), (1, 2));
+    assert!((prediction[[0, 0]] - 0.5f64.sin()).abs() < 0.05);
+    assert!((prediction[[0, 1]] + 1f64.sin()).abs() < 0.05);
+}
@@ -0,0 +1,134 @@
+use ndarray::{array, Array2};
+use porcino_core::enums::{InitializationMethods, Mode};
+use porcino_core::network::{
+    Activations, BatchNorm, FFLayer, Linear, Network, Node, NodeId, Sigmoid,
+};
+use porcino_core::persistence::{self, SavedLayer, SavedNetwork};
+use porcino_core::traits::Predictor;
+use porcino_data::parse::TrainingSample;
+use std::path::PathBuf;
+
+fn temp_file(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("porcino_{}_{name}.json", std::process::id()))
+}
+
+#[test]
+fn batch_norm_statistics_survive_saving() {
+    let mut network = Network::from_layers(vec![
+        Box::new(FFLayer::new(
+            2,
+            3,
+            InitializationMethods::PseudoSpread,
+            &Sigmoid,
+        )),
+        Box::new(BatchNorm::new(3)),
+        Box::new(FFLayer::new(
+            3,
+            1,
+            InitializationMethods::PseudoSpread,
+            &Linear,
+        )),
+    ]);
+    let data: Vec<TrainingSample> = (0..16)
+        .map(|i| {
+            let (a, b) = (i as f64 / 4.0, (i % 3) as f64 * 2.0 - 1.0);
+            TrainingSample {
+                input: array![[a], [b]],
+                expected_output: array![[a - b]],
+            }
+        })
+        .collect();
+    for _ in 0..20 {
+        network.gradient_descent(&data, 0.01);
+    }
+    network.set_mode(Mode::Inference);
+    let inputs = array![[0.5, 2.0, -1.0], [1.0, -1.0, 0.0]];
+    let expected = network.predict(&inputs);
+
+    let path = temp_file("batch_norm");
+    persistence::save(&network, &path).unwrap();
+    let mut restored = persistence::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // Training moved the running statistics away from their initial values
+    match SavedNetwork::from(&restored).layers[1].clone() {
+        SavedLayer::BatchNorm {
+            running_mean,
+            running_var,
+            ..
+        } => {
+            assert_ne!(running_mean, Array2::<f64>::zeros((3, 1)));
+            assert_ne!(running_var, Array2::<f64>::ones((3, 1)));
+        }
+        other => panic!("expected batch norm, got {other:?}"),
+    }
+
+    restored.set_mode(Mode::Inference);
+    let difference = restored.predict(&inputs) - &expected;
+    assert!(difference.iter().all(|d| d.abs() < 1e-12));
+}
+
+fn dense() -> SavedLayer {
+    SavedLayer::Dense {
+        weights: array![[1.0]],
+        biases: array![[0.0]],
+        activation: Activations::Linear,
+        regularization: Default::default(),
+    }
+}
+
+#[test]
+fn malformed_graphs_are_errors() {
+    let input = Node::Input {
+        offset: 0,
+        size: None,
+    };
+    let graphs = [
+        // Reads a node after it
+        (
+            vec![
+                input.clone(),
+                Node::Layer {
+                    layer: 0,
+                    input: NodeId(1),
+                },
+            ],
+            vec![NodeId(1)],
+        ),
+        // Runs a layer that isn't there
+        (
+            vec![
+                input.clone(),
+                Node::Layer {
+                    layer: 3,
+                    input: NodeId(0),
+                },
+            ],
+            vec![NodeId(1)],
+        ),
+        // Missing output
+        (
+            vec![
+                input.clone(),
+                Node::Layer {
+                    layer: 0,
+                    input: NodeId(0),
+                },
+            ],
+            vec![NodeId(5)],
+        ),
+    ];
+
+    let path = temp_file("malformed");
+    for (nodes, outputs) in graphs {
+        let saved = SavedNetwork {
+            layers: vec![dense()],
+            nodes,
+            outputs,
+            heads: Vec::new(),
+        };
+        std::fs::write(&path, serde_json::to_string(&saved).unwrap()).unwrap();
+        assert!(persistence::read(&path).is_err());
+    }
+    std::fs::remove_file(&path).unwrap();
+}
@@ -0,0 +1,102 @@
+use ndarray::array;
+use porcino_core::enums::InitializationMethods;
+use porcino_core::network::{FFLayer, Linear, Network, Regularization};
+use porcino_data::parse::TrainingSample;
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-12
+}
+
+#[test]
+fn penalty_and_its_gradient() {
+    let reg = Regularization {
+        l1: 0.1,
+        l2: 0.4,
+        ..Default::default()
+    };
+    let param = array![[1.0, -2.0], [0.0, 3.0]];
+    assert!(close(reg.penalty(&param), 0.1 * 6.0 + 0.2 * 14.0));
+    let gradient = reg.gradient(&param);
+    assert_eq!(gradient.dim(), (2, 2));
+    for (value, expected) in gradient.iter().zip([0.5, -0.9, 0.0, 1.3]) {
+        assert!(close(*value, expected));
+    }
+
+    // Central differences away from the kink of L1 at zero
+    let h = 1e-6;
+    let mut shifted = param.clone();
+    shifted[(1, 1)] += h;
+    let plus = reg.penalty(&shifted);
+    shifted[(1, 1)] -= 2.0 * h;
+    let minus = reg.penalty(&shifted);
+    assert!(((plus - minus) / (2.0 * h) - gradient[(1, 1)]).abs() < 1e-6);
+}
+
+#[test]
+fn decoupled_decay_bypasses_the_gradient() {
+    let decoupled = Regularization {
+        l2: 0.1,
+        decoupled: true,
+        ..Default::default()
+    };
+    let mut param = array![[2.0, -1.0]];
+    assert_eq!(decoupled.gradient(&param), array![[0.0, 0.0]]);
+    // Not part of the loss either, and scaled by the learning rate
+    assert_eq!(decoupled.penalty(&param), 0.0);
+    decoupled.decay(&mut param, 0.5);
+    assert!(close(param[(0, 0)], 1.9) && close(param[(0, 1)], -0.95));
+
+    let coupled = Regularization {
+        decoupled: false,
+        ..decoupled
+    };
+    coupled.decay(&mut param, 0.5);
+    assert!(close(param[(0, 0)], 1.9));
+}
+
+#[test]
+fn max_norm_rescales_rows() {
+    let reg = Regularization {
+        max_norm: Some(1.0),
+        ..Default::default()
+    };
+    let mut weights = array![[3.0, 4.0], [0.3, 0.4]];
+    reg.constrain(&mut weights);
+    for (value, expected) in weights.iter().zip([0.6, 0.8, 0.3, 0.4]) {
+        assert!(close(*value, expected));
+    }
+}
+
+// One linear neuron fitting its only sample exactly, so only regularization moves it
+fn fitted(regularization: Regularization) -> Network {
+    let mut layer = FFLayer::new(1, 1, InitializationMethods::Zero, &Linear);
+    layer.weights.fill(2.0);
+    layer.biases.fill(1.0);
+    layer.regularization = regularization;
+    Network::from_layers(vec![Box::new(layer)])
+}
+
+#[test]
+fn biases_are_only_penalized_when_included() {
+    let data = [TrainingSample {
+        input: array![[0.0]],
+        expected_output: array![[1.0]],
+    }];
+    let reg = Regularization {
+        l2: 0.5,
+        ..Default::default()
+    };
+
+    let mut network = fitted(reg);
+    assert!(close(network.gradient_descent(&data, 0.1), 1.0));
+    assert!(close(network.params_vector()[0], 1.9));
+    assert!(close(network.params_vector()[1], 1.0));
+
+    let mut network = fitted(Regularization {
+        include_biases: true,
+        ..reg
+    });
+    assert!(close(network.gradient_descent(&data, 0.1), 1.25));
+    assert!(close(network.params_vector()[0], 1.9));
+    assert!(close(network.params_vector()[1], 0.95));
+}
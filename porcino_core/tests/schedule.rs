@@ -0,0 +1,93 @@
+use porcino_core::training::{
+    Constant, CosineAnnealing, ExponentialDecay, LinearWarmup, LrSchedule, ReduceOnPlateau,
+    StepDecay,
+};
+
+fn close(a: f64, b: f64) -> bool {
+    (a - b).abs() < 1e-12
+}
+
+#[test]
+fn decays_follow_the_step() {
+    let mut step = StepDecay {
+        initial: 1.0,
+        factor: 0.5,
+        step_size: 10,
+    };
+    assert!(close(step.learning_rate(9, None), 1.0));
+    assert!(close(step.learning_rate(10, None), 0.5));
+    assert!(close(step.learning_rate(25, None), 0.25));
+
+    let mut exponential = ExponentialDecay {
+        initial: 2.0,
+        gamma: 0.9,
+    };
+    assert!(close(exponential.learning_rate(2, None), 2.0 * 0.81));
+    // Steps past i32::MAX don't wrap around into huge rates
+    let late = exponential.learning_rate(3_000_000_000, None);
+    assert!((0.0..1e-300).contains(&late));
+    let mut step = StepDecay {
+        step_size: 1,
+        ..step
+    };
+    assert!(step.learning_rate(3_000_000_000, None) < 1e-300);
+}
+
+#[test]
+fn cosine_annealing_restarts() {
+    let mut cosine = CosineAnnealing {
+        max: 1.0,
+        min: 0.0,
+        period: 10,
+        period_mult: 1,
+    };
+    assert!(close(cosine.learning_rate(0, None), 1.0));
+    assert!(close(cosine.learning_rate(5, None), 0.5));
+    assert!(close(cosine.learning_rate(10, None), 1.0));
+    assert!(close(cosine.learning_rate(1_000_000_000_005, None), 0.5));
+
+    // Cycles of 10, 20 and 40 steps
+    let mut cosine = CosineAnnealing {
+        period_mult: 2,
+        ..cosine
+    };
+    assert!(close(cosine.learning_rate(10, None), 1.0));
+    assert!(close(cosine.learning_rate(20, None), 0.5));
+    assert!(close(cosine.learning_rate(30, None), 1.0));
+    assert!(close(cosine.learning_rate(50, None), 0.5));
+    assert!(cosine.learning_rate(usize::MAX, None).is_finite());
+}
+
+#[test]
+fn warmup_ramps_into_the_schedule() {
+    let mut warmup = LinearWarmup {
+        steps: 4,
+        schedule: Box::new(StepDecay {
+            initial: 1.0,
+            factor: 0.5,
+            step_size: 2,
+        }),
+    };
+    let rates: Vec<f64> = (0..8)
+        .map(|step| warmup.learning_rate(step, None))
+        .collect();
+    assert_eq!(rates, [0.25, 0.5, 0.75, 1.0, 1.0, 1.0, 0.5, 0.5]);
+    assert!(!warmup.uses_loss());
+    assert!(close(Constant { eta: 0.3 }.learning_rate(7, None), 0.3));
+}
+
+#[test]
+fn plateau_reduces_the_rate() {
+    let mut plateau = ReduceOnPlateau::new(1.0, 0.5, 2, 0.01, 0.2);
+    assert!(plateau.uses_loss());
+    assert_eq!(plateau.learning_rate(0, Some(1.0)), 1.0);
+    assert_eq!(plateau.learning_rate(1, Some(0.995)), 1.0);
+    // Steps without a measured loss don't count against the patience
+    assert_eq!(plateau.learning_rate(2, None), 1.0);
+    assert_eq!(plateau.learning_rate(3, Some(1.2)), 0.5);
+    assert_eq!(plateau.learning_rate(4, Some(0.5)), 0.5);
+    for step in 5..20 {
+        plateau.learning_rate(step, Some(0.6));
+    }
+    assert_eq!(plateau.learning_rate(20, None), 0.2);
+}
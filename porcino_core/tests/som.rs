@@ -0,0 +1,71 @@
+use ndarray::{array, Array2};
+use porcino_core::som::{Som, SomTraining, Topology};
+use porcino_data::parse::TrainingSample;
+
+// Three tight groups of points in the corners of the unit square
+fn clusters() -> Vec<TrainingSample> {
+    let corners = [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0)];
+    (0..30)
+        .map(|i| {
+            let (x, y) = corners[i % 3];
+            let jitter = (i / 3) as f64 * 0.005;
+            TrainingSample {
+                input: array![[x + jitter], [y - jitter]],
+                expected_output: Array2::zeros((0, 1)),
+            }
+        })
+        .collect()
+}
+
+#[test]
+fn grid_neighbours() {
+    let rectangular = Som::new(4, 5, 2, Topology::Rectangular);
+    let hexagonal = Som::new(4, 5, 2, Topology::Hexagonal);
+    // Unit at row 1, column 2 is away from the edges
+    assert_eq!(rectangular.neighbours(7).len(), 4);
+    assert_eq!(hexagonal.neighbours(7).len(), 6);
+    assert_eq!(rectangular.neighbours(0), vec![1, 5]);
+}
+
+#[test]
+fn map_separates_clusters() {
+    let data = clusters();
+    for topology in [Topology::Rectangular, Topology::Hexagonal] {
+        let mut som = Som::new(6, 6, 2, topology);
+        som.initialize(&data, 3);
+        som.train(&data, &SomTraining::default());
+        let error = som.quantization_error(&data);
+        assert!(error < 0.05, "{topology:?} quantization error {error}");
+
+        // Units are shared within a group at most, never across groups
+        let units = som.map(&data);
+        for (i, a) in units.iter().enumerate() {
+            for (j, b) in units.iter().enumerate() {
+                assert!(i % 3 == j % 3 || a != b, "{topology:?} mixes groups");
+            }
+        }
+
+        let u_matrix = som.u_matrix();
+        assert_eq!(u_matrix.dim(), (6, 6));
+        let plane = som.component_plane(1);
+        assert_eq!(plane[[2, 3]], som.weights[[2 * 6 + 3, 1]]);
+    }
+}
+
+#[test]
+fn training_reports_every_epoch() {
+    let data = clusters();
+    let training = SomTraining {
+        epochs: 7,
+        ..SomTraining::default()
+    };
+    let mut som = Som::new(3, 3, 2, Topology::Rectangular);
+    som.initialize(&data, 1);
+    let mut reported = som.clone();
+
+    let mut epochs = Vec::new();
+    reported.train_reporting(&data, &training, |epoch| epochs.push(epoch));
+    som.train(&data, &training);
+    assert_eq!(epochs, (1..=7).collect::<Vec<_>>());
+    assert_eq!(som.weights, reported.weights);
+}
@@ -0,0 +1,160 @@
+use ndarray::array;
+use porcino_core::enums::InitializationMethods;
+use porcino_core::network::{FFLayer, Linear, Network};
+use porcino_core::training::{EarlyStopping, Monitor, Observation, StopConditions, StopReason};
+use std::time::Duration;
+
+fn network() -> Network {
+    Network::from_layers(vec![Box::new(FFLayer::new(
+        1,
+        1,
+        InitializationMethods::Zero,
+        &Linear,
+    ))])
+}
+
+fn observation(
+    epoch: usize,
+    training_loss: f64,
+    validation_loss: Option<f64>,
+    has_validation_set: bool,
+) -> Observation<'static> {
+    Observation {
+        epoch,
+        elapsed: Duration::from_secs(epoch as u64),
+        training_loss: Some(training_loss),
+        validation_loss,
+        has_validation_set,
+        report: None,
+    }
+}
+
+fn early_stopping(patience: usize, min_delta: f64, restore_best: bool) -> StopConditions {
+    StopConditions {
+        early_stopping: Some(EarlyStopping::new(
+            Monitor::TrainingLoss,
+            patience,
+            min_delta,
+            restore_best,
+        )),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn early_stopping_waits_for_patience() {
+    let mut conditions = early_stopping(2, 0.0, false);
+    let mut network = network();
+    for (epoch, loss) in [1.0, 0.9, 0.95].into_iter().enumerate() {
+        let observation = observation(epoch + 1, loss, None, false);
+        assert_eq!(conditions.check(&observation, &mut network), None);
+    }
+    assert_eq!(
+        conditions.check(&observation(4, 0.92, None, false), &mut network),
+        Some(StopReason::EarlyStopping {
+            best_epoch: 2,
+            best_value: 0.9
+        })
+    );
+}
+
+#[test]
+fn improvements_below_min_delta_do_not_count() {
+    let mut conditions = early_stopping(2, 0.05, false);
+    let mut network = network();
+    assert_eq!(
+        conditions.check(&observation(1, 1.0, None, false), &mut network),
+        None
+    );
+    assert_eq!(
+        conditions.check(&observation(2, 0.99, None, false), &mut network),
+        None
+    );
+    assert_eq!(
+        conditions.check(&observation(3, 0.98, None, false), &mut network),
+        Some(StopReason::EarlyStopping {
+            best_epoch: 1,
+            best_value: 1.0
+        })
+    );
+}
+
+#[test]
+fn early_stopping_restores_best_weights() {
+    let mut conditions = early_stopping(1, 0.0, true);
+    let mut network = network();
+    network.set_params_vector(&array![0.5, -0.25]);
+    assert_eq!(
+        conditions.check(&observation(1, 0.1, None, false), &mut network),
+        None
+    );
+
+    network.set_params_vector(&array![3.0, 3.0]);
+    assert!(matches!(
+        conditions.check(&observation(2, 0.2, None, false), &mut network),
+        Some(StopReason::EarlyStopping { best_epoch: 1, .. })
+    ));
+    assert_eq!(network.params_vector(), array![0.5, -0.25]);
+}
+
+#[test]
+fn target_error_follows_the_validation_set() {
+    let mut network = network();
+    let mut conditions = StopConditions {
+        target_error: Some(0.1),
+        ..Default::default()
+    };
+
+    // Without a validation set the training loss is compared
+    assert_eq!(
+        conditions.check(&observation(1, 0.05, None, false), &mut network),
+        Some(StopReason::TargetErrorReached { error: 0.05 })
+    );
+
+    // With one, epochs that skipped validation can't reach the target
+    assert_eq!(
+        conditions.check(&observation(1, 0.05, None, true), &mut network),
+        None
+    );
+    assert_eq!(
+        conditions.check(&observation(2, 0.05, Some(0.2), true), &mut network),
+        None
+    );
+    assert_eq!(
+        conditions.check(&observation(3, 0.05, Some(0.08), true), &mut network),
+        Some(StopReason::TargetErrorReached { error: 0.08 })
+    );
+}
+
+#[test]
+fn time_limit_stops_training() {
+    let mut network = network();
+    let mut conditions = StopConditions {
+        max_duration: Some(Duration::from_secs(3)),
+        ..Default::default()
+    };
+    assert_eq!(
+        conditions.check(&observation(2, 1.0, None, false), &mut network),
+        None
+    );
+    assert_eq!(
+        conditions.check(&observation(3, 1.0, None, false), &mut network),
+        Some(StopReason::TimeLimit {
+            elapsed: Duration::from_secs(3)
+        })
+    );
+}
+
+#[test]
+fn validation_only_runs_when_needed() {
+    let mut conditions = StopConditions {
+        validation_interval: 3,
+        ..Default::default()
+    };
+    assert!(!conditions.validation_due(3));
+
+    conditions.target_error = Some(0.1);
+    assert!(!conditions.validation_due(2));
+    assert!(conditions.validation_due(3));
+    assert!(conditions.validation_due(6));
+}
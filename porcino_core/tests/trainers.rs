@@ -0,0 +1,266 @@
+use ndarray::{array, Axis};
+use porcino_core::data::stack_samples;
+use porcino_core::enums::InitializationMethods;
+use porcino_core::errors::Sse;
+use porcino_core::network::{FFLayer, GraphBuilder, Linear, Network, Rbf, RbfKernel, Tanh};
+use porcino_core::training::{
+    ExtremeLearningMachine, Lbfgs, LevenbergMarquardt, Neuroevolution, Rprop, RpropVariant, Scg,
+    Trainer,
+};
+use porcino_data::parse::TrainingSample;
+
+const INIT: InitializationMethods = InitializationMethods::PseudoSpread;
+
+// y = 2 a - b + 0.5, a single linear layer can fit it exactly
+fn linear_data() -> Vec<TrainingSample> {
+    (0..20)
+        .map(|i| {
+            let (a, b) = ((i % 5) as f64 / 4.0, (i / 5) as f64 / 3.0);
+            TrainingSample {
+                input: array![[a], [b]],
+                expected_output: array![[2.0 * a - b + 0.5]],
+            }
+        })
+        .collect()
+}
+
+fn curve_data() -> Vec<TrainingSample> {
+    (0..40)
+        .map(|i| {
+            let x = i as f64 / 40.0 * 4.0 - 2.0;
+            TrainingSample {
+                input: array![[x]],
+                expected_output: array![[x.sin()]],
+            }
+        })
+        .collect()
+}
+
+fn curve_network() -> Network {
+    Network::from_layers(vec![
+        Box::new(FFLayer::new(1, 6, INIT, &Tanh)),
+        Box::new(FFLayer::new(6, 1, INIT, &Linear)),
+    ])
+}
+
+#[test]
+fn levenberg_marquardt_solves_linear_problems() {
+    let mut network = Network::from_layers(vec![Box::new(FFLayer::new(
+        2,
+        1,
+        InitializationMethods::Zero,
+        &Linear,
+    ))]);
+    let mut trainer = LevenbergMarquardt::default();
+    let mut error = f64::MAX;
+    for _ in 0..5 {
+        error = trainer.epoch(&mut network, &linear_data(), 0.0);
+    }
+    assert!(error < 1e-12, "error {error}");
+}
+
+// Single linear layer already at y = 2 a - b + 0.5, where no step can lower the error
+fn solved_linear_network() -> Network {
+    let mut layer = FFLayer::new(2, 1, InitializationMethods::Zero, &Linear);
+    layer.weights = array![[2.0, -1.0]];
+    layer.biases = array![[0.5]];
+    Network::from_layers(vec![Box::new(layer)])
+}
+
+#[test]
+fn levenberg_marquardt_stops_at_a_minimum() {
+    let mut network = solved_linear_network();
+    let mut trainer = LevenbergMarquardt {
+        mu: 0.0,
+        ..LevenbergMarquardt::default()
+    };
+    let error = trainer.epoch(&mut network, &linear_data(), 0.0);
+    assert_eq!(error, 0.0);
+    assert_eq!(trainer.mu, trainer.mu_max);
+    assert_eq!(network.layers[0].params()[0], &array![[2.0, -1.0]]);
+}
+
+#[test]
+#[should_panic(expected = "damping needs to increase")]
+fn levenberg_marquardt_rejects_a_constant_damping() {
+    let mut trainer = LevenbergMarquardt {
+        mu_inc: 1.0,
+        ..LevenbergMarquardt::default()
+    };
+    trainer.epoch(&mut solved_linear_network(), &linear_data(), 0.0);
+}
+
+#[test]
+fn levenberg_marquardt_never_increases_the_error() {
+    let data = curve_data();
+    let mut network = curve_network();
+    let mut trainer = LevenbergMarquardt::default();
+    let mut previous = network.accumulate_gradient(&data);
+    for _ in 0..30 {
+        let error = trainer.epoch(&mut network, &data, 0.0);
+        assert!(error <= previous);
+        previous = error;
+    }
+    assert!(previous / (data.len() as f64) < 1e-3, "error {previous}");
+}
+
+#[test]
+fn rprop_variants_fit_a_curve() {
+    let data = curve_data();
+    for variant in [
+        RpropVariant::RpropPlus,
+        RpropVariant::RpropMinus,
+        RpropVariant::IRpropPlus,
+        RpropVariant::IRpropMinus,
+    ] {
+        let mut network = curve_network();
+        let mut trainer = Rprop::new(variant);
+        let start = network.accumulate_gradient(&data);
+        let mut error = start;
+        for _ in 0..500 {
+            error = trainer.epoch(&mut network, &data, 0.0);
+        }
+        assert!(
+            error / (data.len() as f64) < 1e-2 && error < start / 10.0,
+            "{variant:?} got stuck at {error}"
+        );
+    }
+}
+
+#[test]
+fn quasi_newton_trainers_fit_a_curve() {
+    let data = curve_data();
+    let trainers: [Box<dyn Trainer>; 2] = [Box::new(Lbfgs::default()), Box::new(Scg::default())];
+    for mut trainer in trainers {
+        let mut network = curve_network();
+        let mut error = f64::MAX;
+        for _ in 0..300 {
+            error = trainer.epoch(&mut network, &data, 0.0);
+        }
+        assert!(
+            error / (data.len() as f64) < 1e-3,
+            "{trainer:?} got stuck at {error}"
+        );
+    }
+}
+
+#[test]
+fn neuroevolution_keeps_improving() {
+    let data = curve_data();
+    let mut network = curve_network();
+    let mut trainer = Neuroevolution::<Sse>::with_seed(7);
+    let start = network.accumulate_gradient(&data);
+    let mut previous = f64::MAX;
+    for _ in 0..200 {
+        let error = trainer.epoch(&mut network, &data, 0.0);
+        // Elitism never loses the best individual
+        assert!(error <= previous);
+        previous = error;
+    }
+    assert!(previous < start / 4.0, "error {previous} from {start}");
+}
+
+#[test]
+fn extreme_learning_machine_solves_in_one_epoch() {
+    let mut network = Network::from_layers(vec![Box::new(FFLayer::new(
+        2,
+        1,
+        InitializationMethods::Zero,
+        &Linear,
+    ))]);
+    let mut trainer = ExtremeLearningMachine::new(1e-12);
+    assert!(!trainer.converged());
+    let error = trainer.epoch(&mut network, &linear_data(), 0.0);
+    assert!(error < 1e-12, "error {error}");
+    assert!(trainer.converged());
+
+    // Only the output layer moves, on top of fixed hidden features
+    let data = curve_data();
+    let mut network = Network::from_layers(vec![
+        Box::new(FFLayer::new(1, 20, InitializationMethods::Random, &Tanh)),
+        Box::new(FFLayer::new(20, 1, INIT, &Linear)),
+    ]);
+    let hidden = network.layers[0].params()[0].clone();
+    let error = ExtremeLearningMachine::default().epoch(&mut network, &data, 0.0);
+    assert_eq!(network.layers[0].params()[0], &hidden);
+    assert!(error / (data.len() as f64) < 1e-3, "error {error}");
+}
+
+#[test]
+fn extreme_learning_machine_skips_other_outputs() {
+    let data = curve_data();
+    let mut graph = GraphBuilder::new();
+    let input = graph.input(1);
+    let hidden = graph.layer(
+        Box::new(FFLayer::new(1, 20, InitializationMethods::Random, &Tanh)),
+        input,
+    );
+    let dense = graph.layer(Box::new(FFLayer::new(20, 1, INIT, &Linear)), hidden);
+    let joined = graph.concat(&[input, hidden]);
+    let mut network = graph.build(&[dense, joined]);
+
+    // The concatenated output is its own target, so only the dense one adds to the error
+    let mut references = network.clone();
+    let (inputs, _) = stack_samples(&data);
+    references.process_data(&inputs);
+    let samples: Vec<TrainingSample> = data
+        .iter()
+        .zip(references.output().columns())
+        .map(|(sample, output)| {
+            let mut expected_output = output.to_owned().insert_axis(Axis(1));
+            expected_output[[0, 0]] = sample.expected_output[[0, 0]];
+            TrainingSample {
+                input: sample.input.clone(),
+                expected_output,
+            }
+        })
+        .collect();
+
+    let error = ExtremeLearningMachine::default().epoch(&mut network, &samples, 0.0);
+    assert!(error / (data.len() as f64) < 1e-3, "error {error}");
+}
+
+#[test]
+fn radial_basis_networks_fit_a_curve() {
+    let data = curve_data();
+    // Multiquadric features grow with the distance and need a smaller step
+    for (kernel, eta) in [(RbfKernel::Gaussian, 1e-2), (RbfKernel::Multiquadric, 1e-4)] {
+        let rbf = Rbf::fit(&data, 8, kernel, 1);
+        assert!(rbf.widths.iter().all(|w| *w > 0.0));
+        let network = Network::from_layers(vec![
+            Box::new(rbf),
+            Box::new(FFLayer::new(8, 1, InitializationMethods::Zero, &Linear)),
+        ]);
+
+        let mut solved = network.clone();
+        let error = ExtremeLearningMachine::default().epoch(&mut solved, &data, 0.0);
+        assert!(
+            error / (data.len() as f64) < 1e-3,
+            "{kernel:?} solved to {error}"
+        );
+
+        let mut trained = network;
+        let start = trained.accumulate_gradient(&data);
+        let mut error = start;
+        for _ in 0..200 {
+            error = trained.gradient_descent(&data, eta);
+        }
+        assert!(
+            error < start / 2.0,
+            "{kernel:?} trained to {error} from {start}"
+        );
+    }
+}
+
+#[test]
+fn params_vector_round_trip() {
+    let mut network = curve_network();
+    let params = network.params_vector();
+    assert_eq!(params.len(), network.param_count());
+    assert_eq!(params.len(), 6 + 6 + 6 + 1);
+
+    let shifted = &params + 1.0;
+    network.set_params_vector(&shifted);
+    assert_eq!(network.params_vector(), shifted);
+    assert_eq!(network.gradient_vector().len(), params.len());
+}
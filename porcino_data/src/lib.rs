@@ -1,2 +1,3 @@
 pub mod parse;
 pub mod persistence;
+pub mod sequence;
@@ -28,28 +28,25 @@ pub fn get_sampled_data(raw_data: &TaggedData) -> Vec<TrainingSample> {
         .data
         .iter()
         .map(|row| TrainingSample {
-            input: Array2::from_shape_vec(
-                (raw_data.meta.params.len(), 1),
-                row.iter()
-                    .enumerate()
-                    .filter(|(idx, _)| raw_data.meta.params.contains(idx))
-                    .map(|(_, v)| *v)
-                    .collect(),
-            )
-            .unwrap(),
-            expected_output: Array2::from_shape_vec(
-                (raw_data.meta.classes.len(), 1),
-                row.iter()
-                    .enumerate()
-                    .filter(|(idx, _)| raw_data.meta.classes.contains(idx))
-                    .map(|(_, v)| *v)
-                    .collect(),
-            )
-            .unwrap(),
+            input: select_columns(row, &raw_data.meta.params),
+            expected_output: select_columns(row, &raw_data.meta.classes),
         })
         .collect()
 }
 
+/// Values of the given columns of a row, as a column vector
+pub(crate) fn select_columns(row: &[f64], columns: &[usize]) -> Array2<f64> {
+    Array2::from_shape_vec(
+        (columns.len(), 1),
+        row.iter()
+            .enumerate()
+            .filter(|(idx, _)| columns.contains(idx))
+            .map(|(_, v)| *v)
+            .collect(),
+    )
+    .unwrap()
+}
+
 pub fn parse_data_file(
     path: &PathBuf,
     settings: &DataSettings,
@@ -125,12 +122,22 @@ pub fn parse_data_file(
         .filter_map(|(idx, column)| match settings.columns[idx] {
             ColumnType::Class(class_type) => {
                 meta.classes.push(new_idx);
+                meta.class_types.push(class_type);
                 new_idx += 1;
-                Some(match class_type {
+                let mut scaling = None;
+                let values = match class_type {
                     ClassType::Value => column
                         .iter()
                         .map(|v| v.parse::<f64>().unwrap())
                         .collect::<Vec<_>>(),
+                    ClassType::NormalizedValue => {
+                        let values = column
+                            .iter()
+                            .map(|v| v.parse::<f64>().unwrap())
+                            .collect::<Vec<_>>();
+                        scaling = Some(Scaling::of(&values));
+                        normalize_values(&values)
+                    }
                     ClassType::Label => column
                         .iter()
                         .map(|v| {
@@ -138,10 +145,19 @@ pub fn parse_data_file(
                             *hm.get(v).unwrap()
                         })
                         .collect(),
-                })
+                };
+                meta.class_scaling.push(scaling);
+                Some(values)
             }
             ColumnType::Parameter(parameter_type) => {
                 meta.params.push(new_idx);
+                meta.param_categories.push(match parameter_type {
+                    ParameterType::Label => labels
+                        .iter()
+                        .find(|(c, _)| *c == idx)
+                        .map(|(_, hm)| hm.len()),
+                    _ => None,
+                });
                 new_idx += 1;
                 Some(match parameter_type {
                     ParameterType::Boolean | ParameterType::NumericUnnormalized => column
@@ -185,16 +201,8 @@ fn transpose_vec<T>(vec: Vec<Vec<T>>, inner_len: usize) -> Vec<Vec<T>> {
 fn normalize_values(values: &[f64]) -> Vec<f64>
 where
 {
-    let min = values
-        .iter()
-        .min_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-    let max = values
-        .iter()
-        .max_by(|a, b| a.partial_cmp(b).unwrap())
-        .unwrap();
-
-    values.iter().map(|v| (*v - *min) / (*max - *min)).collect()
+    let scaling = Scaling::of(values);
+    values.iter().map(|v| scaling.apply(*v)).collect()
 }
 
 pub fn get_file_preview(
@@ -244,14 +252,66 @@ pub enum ParameterType {
     NumericUnnormalized,
     Label,
 }
-#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub enum ClassType {
     Value,
+    NormalizedValue,
     Label,
 }
 
+/// Min-max scaling applied to a column while parsing
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Scaling {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl Scaling {
+    pub fn of(values: &[f64]) -> Self {
+        Self {
+            min: *values
+                .iter()
+                .min_by(|a, b| a.partial_cmp(b).unwrap())
+                .unwrap(),
+            max: *values
+                .iter()
+                .max_by(|a, b| a.partial_cmp(b).unwrap())
+                .unwrap(),
+        }
+    }
+
+    pub fn apply(&self, value: f64) -> f64 {
+        (value - self.min) / (self.max - self.min)
+    }
+
+    pub fn invert(&self, value: f64) -> f64 {
+        value * (self.max - self.min) + self.min
+    }
+}
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize)]
 pub struct Metadata {
     pub params: Vec<usize>,
     pub classes: Vec<usize>,
+    #[serde(default)]
+    pub class_types: Vec<ClassType>,
+    #[serde(default)]
+    pub class_scaling: Vec<Option<Scaling>>,
+    /// Number of distinct labels of every categorical param, `None` for the numeric ones
+    #[serde(default)]
+    pub param_categories: Vec<Option<usize>>,
+}
+
+impl Metadata {
+    /// Cardinality of every param, in the order of `params`. Data saved before
+    /// categories were recorded reads as all numeric.
+    pub fn categories(&self) -> Vec<Option<usize>> {
+        (0..self.params.len())
+            .map(|idx| self.param_categories.get(idx).copied().flatten())
+            .collect()
+    }
+
+    pub fn has_categories(&self) -> bool {
+        self.param_categories.iter().any(Option::is_some)
+    }
 }
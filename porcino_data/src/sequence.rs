@@ -0,0 +1,78 @@
+use crate::parse::{select_columns, TaggedData};
+use ndarray::Array2;
+
+/// A `TrainingSample` spread over time. Every step of `inputs` is a column vector.
+/// `expected_outputs` holds one target per step in many-to-many mode and a single
+/// target for the whole sequence in many-to-one mode.
+#[derive(Clone, Debug)]
+pub struct SequenceSample {
+    pub inputs: Vec<Array2<f64>>,
+    pub expected_outputs: Vec<Array2<f64>>,
+}
+
+#[derive(Copy, Clone, Debug, Default, Eq, PartialEq)]
+pub enum SequenceMode {
+    /// Only the output after the last step is scored
+    #[default]
+    ManyToOne,
+    /// Every step has its own target
+    ManyToMany,
+}
+
+/// How a time series is cut into sequences
+#[derive(Copy, Clone, Debug)]
+pub struct WindowSettings {
+    /// Steps in every sequence
+    pub length: usize,
+    /// Rows between the starts of consecutive sequences
+    pub stride: usize,
+    /// How many rows ahead of a step its target is taken from, 0 labels the step itself
+    /// and 1 makes it a next step forecast
+    pub horizon: usize,
+    pub mode: SequenceMode,
+}
+
+impl Default for WindowSettings {
+    fn default() -> Self {
+        Self {
+            length: 10,
+            stride: 1,
+            horizon: 0,
+            mode: SequenceMode::ManyToOne,
+        }
+    }
+}
+
+/// Slides a window over the rows of a time series, which are expected to be in time order
+pub fn get_windowed_data(raw_data: &TaggedData, settings: &WindowSettings) -> Vec<SequenceSample> {
+    assert!(
+        settings.length > 0 && settings.stride > 0,
+        "window length and stride must be positive"
+    );
+    let rows = &raw_data.data;
+    let span = settings.length + settings.horizon;
+    if rows.len() < span {
+        return Vec::new();
+    }
+
+    (0..=rows.len() - span)
+        .step_by(settings.stride)
+        .map(|start| {
+            let steps = start..start + settings.length;
+            let targets = match settings.mode {
+                SequenceMode::ManyToOne => start + settings.length - 1..start + settings.length,
+                SequenceMode::ManyToMany => steps.clone(),
+            };
+            SequenceSample {
+                inputs: steps
+                    .map(|row| select_columns(&rows[row], &raw_data.meta.params))
+                    .collect(),
+                expected_outputs: targets
+                    .map(|row| {
+                        select_columns(&rows[row + settings.horizon], &raw_data.meta.classes)
+                    })
+                    .collect(),
+            }
+        })
+        .collect()
+}
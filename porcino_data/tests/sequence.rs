@@ -0,0 +1,80 @@
+use ndarray::array;
+use porcino_data::parse::{Metadata, TaggedData};
+use porcino_data::sequence::{get_windowed_data, SequenceMode, WindowSettings};
+
+// Row i holds the param i and the class 10 i
+fn series(rows: usize) -> TaggedData {
+    TaggedData {
+        data: (0..rows).map(|i| vec![i as f64, 10.0 * i as f64]).collect(),
+        meta: Metadata {
+            params: vec![0],
+            classes: vec![1],
+            ..Default::default()
+        },
+    }
+}
+
+fn steps(values: &[ndarray::Array2<f64>]) -> Vec<f64> {
+    values.iter().map(|value| value[(0, 0)]).collect()
+}
+
+#[test]
+fn many_to_one_targets_the_last_step() {
+    let settings = WindowSettings {
+        length: 3,
+        stride: 1,
+        horizon: 0,
+        mode: SequenceMode::ManyToOne,
+    };
+    let windows = get_windowed_data(&series(6), &settings);
+    assert_eq!(windows.len(), 4);
+    assert_eq!(steps(&windows[0].inputs), [0.0, 1.0, 2.0]);
+    assert_eq!(windows[0].expected_outputs, [array![[20.0]]]);
+    assert_eq!(steps(&windows[3].inputs), [3.0, 4.0, 5.0]);
+    assert_eq!(windows[3].expected_outputs, [array![[50.0]]]);
+
+    // A forecast one step ahead needs a row past the window
+    let ahead = WindowSettings {
+        horizon: 1,
+        ..settings
+    };
+    let windows = get_windowed_data(&series(6), &ahead);
+    assert_eq!(windows.len(), 3);
+    assert_eq!(windows[0].expected_outputs, [array![[30.0]]]);
+    assert_eq!(steps(&windows[2].inputs), [2.0, 3.0, 4.0]);
+    assert_eq!(windows[2].expected_outputs, [array![[50.0]]]);
+}
+
+#[test]
+fn many_to_many_targets_every_step() {
+    let settings = WindowSettings {
+        length: 2,
+        stride: 2,
+        horizon: 1,
+        mode: SequenceMode::ManyToMany,
+    };
+    let windows = get_windowed_data(&series(6), &settings);
+    assert_eq!(windows.len(), 2);
+    assert_eq!(steps(&windows[1].inputs), [2.0, 3.0]);
+    assert_eq!(steps(&windows[1].expected_outputs), [30.0, 40.0]);
+}
+
+#[test]
+fn windows_stay_inside_the_series() {
+    let settings = WindowSettings {
+        length: 4,
+        stride: 3,
+        horizon: 0,
+        mode: SequenceMode::ManyToOne,
+    };
+    // Exactly one window fits
+    assert_eq!(get_windowed_data(&series(4), &settings).len(), 1);
+    // Windows start at 0 and 3, the next one at 6 would run past the end
+    assert_eq!(get_windowed_data(&series(8), &settings).len(), 2);
+    assert!(get_windowed_data(&series(3), &settings).is_empty());
+    let ahead = WindowSettings {
+        horizon: 2,
+        ..settings
+    };
+    assert!(get_windowed_data(&series(5), &ahead).is_empty());
+}